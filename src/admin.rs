@@ -0,0 +1,165 @@
+use crate::client::{default_http_client, ErrRes, ErrStatusCode};
+use crate::options::{ChannelsOptions, DisconnectOptions};
+use crate::pipe::{
+    ChannelsRequest, Command, DisconnectRequest, InfoRequest, Method, PresenceRequest, RequestKind,
+};
+use crate::protocol::{ChannelsResult, InfoResult, PresenceResult, Reply};
+use reqwest::Client as ReqClient;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+/// AdminConfig configures client of Centrifugo admin web API.
+#[derive(Clone, Debug, Default)]
+pub struct AdminConfig {
+    /// addr is a base address of Centrifugo admin web, e.g. http://localhost:8000.
+    pub addr: String,
+    /// password is an admin password used to obtain admin token.
+    pub password: Option<String>,
+    /// token is an admin token issued by server, e.g. signed with admin secret.
+    /// When both are set token is used until server rejects it, then client logs
+    /// in with password. Nil value means obtaining token with password.
+    pub token: Option<String>,
+    /// http_client is a custom http client to be used
+    /// default is used if nil
+    pub http_client: Option<ReqClient>,
+}
+
+/// AdminClient is a client of Centrifugo admin web API, so internal tooling can
+/// automate operations available in admin UI.
+pub struct AdminClient {
+    addr: String,
+    password: Option<String>,
+    http_client: ReqClient,
+    token: Mutex<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct AuthResponse {
+    token: String,
+}
+
+impl AdminClient {
+    /// Create a new admin client instance.
+    pub fn new(config: AdminConfig) -> Self {
+        AdminClient {
+            addr: config.addr.trim_end_matches('/').to_string(),
+            password: config.password,
+            http_client: config.http_client.unwrap_or_else(default_http_client),
+            token: Mutex::new(config.token),
+        }
+    }
+
+    /// login exchanges admin password for admin token, token is kept and sent
+    /// with following calls.
+    pub async fn login(&self) -> Result<String, ErrRes> {
+        let password = self
+            .password
+            .as_deref()
+            .ok_or("admin password is not configured")?;
+
+        let response = self
+            .http_client
+            .post(format!("{}/admin/auth", self.addr))
+            .form(&[("password", password)])
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(Box::new(ErrStatusCode::new(status.as_u16(), &body, None)));
+        }
+
+        let auth: AuthResponse = serde_json::from_slice(&body)?;
+        *self.token.lock().map_err(|_| "Lock poisoned")? = Some(auth.token.clone());
+        Ok(auth.token)
+    }
+
+    /// send sends command to admin API and returns its result, client logs in
+    /// when it has no token yet or token was rejected.
+    pub async fn send(&self, command: &Command) -> Result<serde_json::Value, ErrRes> {
+        let token = self.token.lock().map_err(|_| "Lock poisoned")?.clone();
+        let token = match token {
+            Some(token) => token,
+            None => self.login().await?,
+        };
+
+        let reply = match self.send_with_token(command, &token).await {
+            Err(err)
+                if self.password.is_some()
+                    && err
+                        .downcast_ref::<ErrStatusCode>()
+                        .is_some_and(|err| err.code == 401) =>
+            {
+                let token = self.login().await?;
+                self.send_with_token(command, &token).await?
+            }
+            reply => reply?,
+        };
+
+        if let Some(err) = reply.error {
+            return Err(Box::new(err));
+        }
+        Ok(reply.result.unwrap_or(serde_json::Value::Null))
+    }
+
+    async fn send_with_token(&self, command: &Command, token: &str) -> Result<Reply, ErrRes> {
+        let response = self
+            .http_client
+            .post(format!("{}/admin/api", self.addr))
+            .header("Authorization", format!("token {}", token))
+            .json(command)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(Box::new(ErrStatusCode::new(status.as_u16(), &body, None)));
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// info returns information about running server nodes.
+    pub async fn info(&self) -> Result<InfoResult, ErrRes> {
+        let result = self
+            .send(&Command {
+                method: Method::Info,
+                params: RequestKind::InfoRequest(InfoRequest {}),
+            })
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// channels returns active channels matching options.
+    pub async fn channels(&self, options: ChannelsOptions) -> Result<ChannelsResult, ErrRes> {
+        let result = self
+            .send(&Command {
+                method: Method::Channels,
+                params: RequestKind::ChannelsRequest(ChannelsRequest {
+                    pattern: options.pattern,
+                }),
+            })
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// presence returns channel presence information.
+    pub async fn presence(&self, channel: String) -> Result<PresenceResult, ErrRes> {
+        let result = self
+            .send(&Command {
+                method: Method::Presence,
+                params: RequestKind::PresenceRequest(PresenceRequest { channel }),
+            })
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// disconnect closes connections of user.
+    pub async fn disconnect(&self, user: String, options: DisconnectOptions) -> Result<(), ErrRes> {
+        self.send(&Command {
+            method: Method::Disconnect,
+            params: RequestKind::DisconnectRequest(DisconnectRequest { user, options }),
+        })
+        .await?;
+        Ok(())
+    }
+}
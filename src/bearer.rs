@@ -0,0 +1,70 @@
+use crate::client::ErrRes;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// DEFAULT_REFRESH_BEFORE is how long before expiry bearer token is refreshed.
+pub const DEFAULT_REFRESH_BEFORE: Duration = Duration::from_secs(30);
+
+/// BearerToken is an access token issued for API path together with its
+/// expiration time, nil expires_at means token is used until server rejects it.
+#[derive(Clone, Debug)]
+pub struct BearerToken {
+    pub token: String,
+    pub expires_at: Option<Instant>,
+}
+
+/// TokenProvider is an async closure issuing fresh bearer token, see Config.token_provider.
+pub type TokenProvider = Arc<
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<BearerToken, ErrRes>> + Send>> + Send + Sync,
+>;
+
+/// TokenSource caches token returned by provider, provider is invoked again
+/// when token nears expiry or was invalidated after 401 response.
+pub struct TokenSource {
+    provider: TokenProvider,
+    refresh_before: Duration,
+    cached: Mutex<Option<BearerToken>>,
+}
+
+impl TokenSource {
+    /// Create a new source refreshing token refresh_before its expiry.
+    pub fn new(provider: TokenProvider, refresh_before: Duration) -> Arc<Self> {
+        Arc::new(TokenSource {
+            provider,
+            refresh_before,
+            cached: Mutex::new(None),
+        })
+    }
+
+    /// token returns cached token, concurrent callers wait for a single refresh
+    /// when there's no token or it's about to expire.
+    pub async fn token(&self) -> Result<String, ErrRes> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref() {
+            let fresh = match token.expires_at {
+                Some(expires_at) => Instant::now() + self.refresh_before < expires_at,
+                None => true,
+            };
+            if fresh {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let token = (self.provider)().await?;
+        let value = token.token.clone();
+        *cached = Some(token);
+        Ok(value)
+    }
+
+    /// invalidate drops token if it's still the rejected one, so the next call
+    /// to token invokes provider.
+    pub async fn invalidate(&self, rejected: &str) {
+        let mut cached = self.cached.lock().await;
+        if cached.as_ref().is_some_and(|token| token.token == rejected) {
+            *cached = None;
+        }
+    }
+}
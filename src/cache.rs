@@ -0,0 +1,91 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
+
+/// ResultCache keeps results of API calls for a configured TTL, it's used to
+/// serve frequently polled info and channels and to deduplicate publishes.
+pub struct ResultCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Value, Instant)>>,
+    /// computing keeps locks of keys results are being computed for.
+    computing: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// KeyGuard is held while result for key is computed, see ResultCache::lock.
+pub struct KeyGuard<'a> {
+    cache: &'a ResultCache,
+    key: String,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for KeyGuard<'_> {
+    fn drop(&mut self) {
+        let mut computing = self
+            .cache
+            .computing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        // lock is dropped once neither this guard nor waiters hold it.
+        if computing
+            .get(&self.key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 2)
+        {
+            computing.remove(&self.key);
+        }
+    }
+}
+
+impl ResultCache {
+    /// Create a new cache keeping results for ttl.
+    pub fn new(ttl: Duration) -> Self {
+        ResultCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            computing: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// lock waits until no one else computes result for key and returns guard
+    /// to hold while computing it, so concurrent callers find result cached
+    /// instead of computing it again.
+    pub async fn lock(&self, key: &str) -> KeyGuard<'_> {
+        let lock = self
+            .computing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        KeyGuard {
+            cache: self,
+            key: key.to_string(),
+            _guard: lock.lock_owned().await,
+        }
+    }
+
+    /// get returns result cached under key if it's not expired yet.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some((value, cached_at)) if cached_at.elapsed() < self.ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// put caches result under key.
+    pub fn put(&self, key: String, value: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (_, cached_at)| cached_at.elapsed() < self.ttl);
+        entries.insert(key, (value, Instant::now()));
+    }
+
+    /// invalidate drops all cached results.
+    pub fn invalidate(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::pattern::{ChannelPattern, PatternError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// CAPS_CLAIM is a name of token claim capabilities are embedded under.
+pub const CAPS_CLAIM: &str = "caps";
+
+/// Permission is an operation allowed on channels matched by ChannelCapability.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Permission {
+    #[serde(rename = "sub")]
+    Subscribe,
+    #[serde(rename = "pub")]
+    Publish,
+    #[serde(rename = "prs")]
+    Presence,
+    #[serde(rename = "hst")]
+    History,
+}
+
+/// Match tells how channels of ChannelCapability are matched against channel
+/// names, exact match is omitted from encoded claim.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Match {
+    #[default]
+    #[serde(rename = "")]
+    Exact,
+    Wildcard,
+    Regex,
+}
+
+impl Match {
+    fn is_exact(&self) -> bool {
+        *self == Match::Exact
+    }
+}
+
+/// ChannelCapability allows operations on a list of channels or channel patterns.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelCapability {
+    pub channels: Vec<String>,
+    pub allow: Vec<Permission>,
+    #[serde(rename = "match", default, skip_serializing_if = "Match::is_exact")]
+    pub match_: Match,
+}
+
+/// Capabilities is a builder of channel capabilities claim of connection and
+/// subscription tokens, following capabilities model of Centrifugo PRO.
+/// Tokens are signed by application, claims returns value to embed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Capabilities {
+    pub caps: Vec<ChannelCapability>,
+}
+
+impl Capabilities {
+    /// Create empty capabilities.
+    pub fn new() -> Self {
+        Capabilities::default()
+    }
+
+    /// allow allows permissions on channels matched exactly.
+    pub fn allow<I, S>(self, channels: I, permissions: &[Permission]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add(channels, permissions, Match::Exact)
+    }
+
+    /// allow_wildcard allows permissions on channels matched by wildcard
+    /// patterns, e.g. chat:*. Patterns are validated by claims.
+    pub fn allow_wildcard<I, S>(self, patterns: I, permissions: &[Permission]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add(patterns, permissions, Match::Wildcard)
+    }
+
+    /// allow_regex allows permissions on channels matched by regular
+    /// expressions, which are evaluated by server only.
+    pub fn allow_regex<I, S>(self, patterns: I, permissions: &[Permission]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add(patterns, permissions, Match::Regex)
+    }
+
+    fn add<I, S>(mut self, channels: I, permissions: &[Permission], match_: Match) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.caps.push(ChannelCapability {
+            channels: channels.into_iter().map(Into::into).collect(),
+            allow: permissions.to_vec(),
+            match_,
+        });
+        self
+    }
+
+    /// is_empty returns true when no capability was added.
+    pub fn is_empty(&self) -> bool {
+        self.caps.is_empty()
+    }
+
+    /// validate checks that wildcard patterns compile.
+    pub fn validate(&self) -> Result<(), PatternError> {
+        for cap in &self.caps {
+            if cap.match_ == Match::Wildcard {
+                for pattern in &cap.channels {
+                    ChannelPattern::new(pattern)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// claims returns claims to merge into token claims before signing, caps
+    /// claim is keyed by CAPS_CLAIM.
+    pub fn claims(&self) -> Result<Map<String, Value>, PatternError> {
+        self.validate()?;
+        let mut claims = Map::new();
+        claims.insert(
+            CAPS_CLAIM.to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+        Ok(claims)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::client::{Client, ErrRes};
+use crate::options::{
+    HistoryOption, HistoryOptions, PublishOption, PublishOptions, SubscribeOption, SubscribeOptions,
+};
+use crate::protocol::{HistoryResult, PresenceResult, PresenceStatsResult, PublishResult};
+
+/// ChannelHandle is a client bound to a single channel, returned by
+/// Client::channel. It's cheap to clone and shares client state.
+#[derive(Clone)]
+pub struct ChannelHandle {
+    client: Client,
+    channel: String,
+}
+
+impl ChannelHandle {
+    pub(crate) fn new(client: Client, channel: String) -> Self {
+        ChannelHandle { client, channel }
+    }
+
+    /// name returns channel handle is bound to.
+    pub fn name(&self) -> &str {
+        &self.channel
+    }
+
+    /// Publish allows to publish data to channel.
+    pub async fn publish(
+        &self,
+        data: &str,
+        opts: &[PublishOption],
+    ) -> Result<PublishResult, ErrRes> {
+        self.client.publish(self.channel.clone(), data, opts).await
+    }
+
+    /// PublishWithOptions is the same as publish but takes options built with
+    /// PublishOptions::new() instead of closures.
+    pub async fn publish_with_options(
+        &self,
+        data: &str,
+        options: PublishOptions,
+    ) -> Result<PublishResult, ErrRes> {
+        self.client
+            .publish_with_options(self.channel.clone(), data, options)
+            .await
+    }
+
+    /// History returns channel history.
+    pub async fn history(&self, opts: &[HistoryOption]) -> Result<HistoryResult, ErrRes> {
+        self.client.history(self.channel.clone(), opts).await
+    }
+
+    /// HistoryWithOptions is the same as history but takes options built with
+    /// HistoryOptions::new() instead of closures.
+    pub async fn history_with_options(
+        &self,
+        options: HistoryOptions,
+    ) -> Result<HistoryResult, ErrRes> {
+        self.client
+            .history_with_options(self.channel.clone(), options)
+            .await
+    }
+
+    /// HistoryRemove removes channel history.
+    pub async fn history_remove(&self) -> Result<(), ErrRes> {
+        self.client.history_remove(self.channel.clone()).await
+    }
+
+    /// Presence returns channel presence information.
+    pub async fn presence(&self) -> Result<PresenceResult, ErrRes> {
+        self.client.presence(self.channel.clone()).await
+    }
+
+    /// PresenceStats returns short channel presence information (only counters).
+    pub async fn presence_stats(&self) -> Result<PresenceStatsResult, ErrRes> {
+        self.client.presence_stats(self.channel.clone()).await
+    }
+
+    /// Subscribe subscribes user to channel (using server-side subscriptions).
+    pub async fn subscribe(&self, user: String, opts: &[SubscribeOption]) -> Result<(), ErrRes> {
+        self.client
+            .subscribe(self.channel.clone(), user, opts)
+            .await
+    }
+
+    /// SubscribeWithOptions is the same as subscribe but takes options built
+    /// with SubscribeOptions::new() instead of closures.
+    pub async fn subscribe_with_options(
+        &self,
+        user: String,
+        options: SubscribeOptions,
+    ) -> Result<(), ErrRes> {
+        self.client
+            .subscribe_with_options(self.channel.clone(), user, options)
+            .await
+    }
+}
@@ -1,24 +1,84 @@
+use crate::bearer::{TokenProvider, TokenSource, DEFAULT_REFRESH_BEFORE};
+use crate::cache::ResultCache;
+use crate::channel::ChannelHandle;
+use crate::discovery::{DiscoveredEndpoints, ServiceDiscovery};
+use crate::endpoint::{
+    EndpointCache, EndpointRefresher, EndpointSelector, GetAddr, DEFAULT_PROBE_INTERVAL,
+};
+use crate::error::FailureKind;
+use crate::hooks::{
+    ConnectionEvent, ErrorEvent, FallbackEvent, Hook, RequestEvent, ResponseEvent, RetryEvent,
+};
+use crate::middleware::{CommandMiddleware, Middleware, COMMAND_FILTERED_CODE};
+use crate::namespace::Namespaces;
 use crate::options::{
-    ChannelsOption, DisconnectOption, HistoryOption, PublishOption, SubscribeOption,
-    UnsubscribeOption,
+    with_disconnect, with_disconnect_client_whitelist, ChannelsOption, ChannelsOptions,
+    ConnectionsOption, ConnectionsOptions, Disconnect, DisconnectOption, DisconnectOptions,
+    HistoryOption, HistoryOptions, PublishOption, PublishOptions, RefreshOption, RefreshOptions,
+    SubscribeOption, SubscribeOptions, UnsubscribeOption, UnsubscribeOptions,
 };
 use crate::protocol::{
-    BroadcastResult, ChannelsResult, HistoryResult, InfoResult, PresenceResult,
-    PresenceStatsResult, PublishResult, Reply,
+    BroadcastResult, ChannelsResult, ConnectionsResult, Device, DeviceListResult,
+    DeviceRegisterResult, DeviceTopic, DeviceTopicListResult, Error as ProtocolError,
+    GetUserStatusResult, HistoryResult, InfoResult, PresenceResult, PresenceStatsResult,
+    PublishResult, RateLimitResult, Reply, SendPushNotificationResult, TypedResult, UserStatus,
+    UserTopic, UserTopicListResult,
 };
-use reqwest::Client as ReqClient;
+use crate::record::{Exchange, Recorder, REDACTED};
+use crate::retry::{is_retryable, DeadLetterHandler, RetryPolicy};
+use crate::secret::KeyFile;
+#[cfg(feature = "tower")]
+use crate::service::{ApiService, HttpLayer, HttpService};
+use crate::shutdown::{Lifecycle, ShutdownReport};
+use crate::stats::{ClientStats, StatsCounters};
+use crate::timestamp::Timestamp;
+use crate::transport::{TransportCounters, TransportStats};
+use crate::user::UserHandle;
+use crate::validation::DataValidators;
+use bytes::Bytes;
+use futures_util::stream::{self, Stream};
+use hyper_util::client::legacy::connect::HttpInfo;
+use reqwest::header::HeaderValue;
+use reqwest::{Client as ReqClient, ClientBuilder as ReqClientBuilder};
+use serde::{Deserialize, Serialize};
 use serde_json;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::fmt;
-use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-
-use crate::pipe::{Command, Pipe};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::pipe::{
+    Command, DeviceListRequest, DeviceRegisterRequest, DeviceRemoveRequest, DeviceTopicListRequest,
+    DeviceTopicUpdateRequest, DeviceUpdateRequest, Method, Pipe, PipeCommandError,
+    RateLimitRequest, RequestKind, SendPushNotificationRequest, UserTopicListRequest,
+    UserTopicUpdateRequest,
+};
 
 const ERR_MALFORMED_RESPONSE_STRING: &str = "malformed response returned from server";
 const ERR_PIPE_EMPTY_STRING: &str = "no commands in pipe";
 
+/// USER_STATUS_CHUNK_SIZE is a max number of users requested by a single
+/// get_user_status command of Client::get_user_status_many.
+pub const USER_STATUS_CHUNK_SIZE: usize = 1000;
+
+/// MAX_RETRY_FAILED_ATTEMPTS is a max number of attempts, including the first
+/// send, Client::retry_failed makes when retry policy allows retrying until
+/// success.
+pub const MAX_RETRY_FAILED_ATTEMPTS: u32 = 5;
+
+/// REQUEST_ID_HEADER carries ID generated for every HTTP request, so failures can
+/// be correlated with server logs. ID set in this header with CallOptions is
+/// propagated instead of generated one. Every attempt is a separate request, so
+/// each retry gets a new ID and one call may show up in server logs under
+/// several IDs, ErrRequestFailed and ErrStatusCode carry ID of failed attempt.
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+/// DEFAULT_USER_AGENT is sent in User-Agent header unless Config.user_agent is set.
+pub const DEFAULT_USER_AGENT: &str = concat!("rucent/", env!("CARGO_PKG_VERSION"));
+
 #[derive(Debug)]
 struct ErrMalformedResponse {}
 
@@ -47,20 +107,253 @@ impl Error for ErrMalformedResponse {}
 pub struct ErrStatusCode {
     pub code: u16,
     pub body: String,
+    /// request_id is an ID of failed request, nil if request was sent without it.
+    pub request_id: Option<String>,
+    /// message is an error message parsed from JSON body, nil if body is not
+    /// JSON or has no message.
+    pub message: Option<String>,
+    /// error_code is an error code parsed from JSON body, nil if body has none.
+    pub error_code: Option<u32>,
+}
+
+impl ErrStatusCode {
+    /// Create a new error for response with status code and body, parsing
+    /// message and code of JSON error body, e.g. `{"error":{"code":101,
+    /// "message":"unauthorized"}}`, `{"code":101,"message":"unauthorized"}` or
+    /// `{"error":"unauthorized"}`.
+    pub fn new(code: u16, body: &[u8], request_id: Option<String>) -> Self {
+        let (message, error_code) = match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(serde_json::Value::Object(fields)) => {
+                let error = match fields.get("error") {
+                    Some(serde_json::Value::Object(error)) => error,
+                    _ => &fields,
+                };
+                let message = ["message", "error"]
+                    .iter()
+                    .find_map(|name| error.get(*name)?.as_str())
+                    .map(str::to_string);
+                let error_code = error
+                    .get("code")
+                    .and_then(serde_json::Value::as_u64)
+                    .and_then(|code| u32::try_from(code).ok());
+                (message, error_code)
+            }
+            _ => (None, None),
+        };
+        ErrStatusCode {
+            code,
+            body: String::from_utf8_lossy(body).into_owned(),
+            request_id,
+            message,
+            error_code,
+        }
+    }
 }
 
 // Implement the `std::fmt::Display` trait for `ErrStatusCode`
 impl fmt::Display for ErrStatusCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "wrong status code: {}, body {}", self.code, &self.body)
+        match (&self.message, self.error_code) {
+            (Some(message), Some(error_code)) => write!(
+                f,
+                "wrong status code: {}, error {}: {}",
+                self.code, error_code, message
+            )?,
+            (Some(message), None) => {
+                write!(f, "wrong status code: {}, error {}", self.code, message)?
+            }
+            _ => write!(f, "wrong status code: {}, body {}", self.code, &self.body)?,
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, ", request id: {}", request_id)?;
+        }
+        Ok(())
     }
 }
 
 // Implement the `Error` trait for `ErrStatusCode`
 impl Error for ErrStatusCode {}
 
+// ErrRequestFailed is returned when request failed on transport level or its
+// replies could not be decoded, it keeps context to make such errors actionable.
+#[derive(Debug)]
+pub struct ErrRequestFailed {
+    /// methods of commands sent in request.
+    pub methods: Vec<String>,
+    /// endpoint request was sent to.
+    pub endpoint: String,
+    /// attempt number, starting from 1.
+    pub attempt: u32,
+    /// request_id is an ID sent in REQUEST_ID_HEADER.
+    pub request_id: String,
+    /// kind is a category of failure, classified from source.
+    pub kind: FailureKind,
+    pub source: ErrRes,
+}
+
+impl ErrRequestFailed {
+    /// Create a new error of request failed with source, its kind is
+    /// classified with FailureKind::of.
+    pub fn new(
+        methods: Vec<String>,
+        endpoint: String,
+        attempt: u32,
+        request_id: String,
+        source: ErrRes,
+    ) -> Self {
+        ErrRequestFailed {
+            methods,
+            endpoint,
+            attempt,
+            request_id,
+            kind: FailureKind::of(source.as_ref()),
+            source,
+        }
+    }
+}
+
+// Implement the `std::fmt::Display` trait for `ErrRequestFailed`
+impl fmt::Display for ErrRequestFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request failed, methods: [{}], endpoint: {}, attempt: {}, request id: {}, kind: {}: {}",
+            self.methods.join(", "),
+            self.endpoint,
+            self.attempt,
+            self.request_id,
+            self.kind,
+            self.source
+        )
+    }
+}
+
+// Implement the `Error` trait for `ErrRequestFailed`
+impl Error for ErrRequestFailed {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+// ErrResponseTooLarge is returned when server response body exceeds Config.max_response_size.
+#[derive(Debug)]
+pub struct ErrResponseTooLarge {
+    /// limit is a configured max response size in bytes.
+    pub limit: usize,
+    /// size is a number of bytes received (or announced in Content-Length) when
+    /// reading was aborted.
+    pub size: usize,
+    /// request_id is an ID sent in REQUEST_ID_HEADER.
+    pub request_id: String,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrResponseTooLarge`
+impl fmt::Display for ErrResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "response too large: {} bytes, limit {} bytes, request id: {}",
+            self.size, self.limit, self.request_id
+        )
+    }
+}
+
+// Implement the `Error` trait for `ErrResponseTooLarge`
+impl Error for ErrResponseTooLarge {}
+
+// ErrPayloadTooLarge is returned before sending when publication data exceeds
+// Config.max_publication_size or request body exceeds Config.max_request_size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrPayloadTooLarge {
+    /// size is a payload size in bytes.
+    pub size: usize,
+    /// limit is a configured max payload size in bytes.
+    pub limit: usize,
+    /// method is a command with too large publication data, None when the whole
+    /// request body is too large.
+    pub method: Option<String>,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrPayloadTooLarge`
+impl fmt::Display for ErrPayloadTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.method {
+            Some(method) => write!(
+                f,
+                "{} data too large: {} bytes, limit {} bytes",
+                method, self.size, self.limit
+            ),
+            None => write!(
+                f,
+                "request too large: {} bytes, limit {} bytes",
+                self.size, self.limit
+            ),
+        }
+    }
+}
+
+// Implement the `Error` trait for `ErrPayloadTooLarge`
+impl Error for ErrPayloadTooLarge {}
+
 pub type ErrRes = Box<dyn Error + Send + Sync>;
 
+// ConfigError is returned by Client::try_new when config can't be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// field is a name of invalid Config field.
+    pub field: String,
+    pub message: String,
+}
+
+// Implement the `std::fmt::Display` trait for `ConfigError`
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid config {}: {}", self.field, self.message)
+    }
+}
+
+// Implement the `Error` trait for `ConfigError`
+impl Error for ConfigError {}
+
+/// normalize_addr validates API address and fixes common mistakes: surrounding
+/// whitespace, trailing slashes and missing /api path, e.g. http://localhost:8000/
+/// becomes http://localhost:8000/api. Non-empty paths are kept, so address of
+/// API behind a proxy prefix is not changed.
+pub fn normalize_addr(addr: &str) -> Result<String, ConfigError> {
+    let invalid = |message: String| ConfigError {
+        field: "addr".to_string(),
+        message,
+    };
+
+    let addr = addr.trim();
+    if !addr.contains("://") {
+        return Err(invalid(format!(
+            "{addr:?} has no scheme, expected e.g. http://{addr}"
+        )));
+    }
+    let mut url = reqwest::Url::parse(addr).map_err(|err| invalid(format!("{addr:?}: {err}")))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(invalid(format!(
+            "unsupported scheme {:?}, expected http or https",
+            url.scheme()
+        )));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid(format!("{addr:?} has no host")));
+    }
+    // client built without TLS stack can only speak plain HTTP.
+    #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+    if url.scheme() == "https" {
+        return Err(invalid(format!(
+            "{addr:?} requires TLS, enable native-tls or rustls feature"
+        )));
+    }
+
+    let path = url.path().trim_end_matches('/').to_string();
+    url.set_path(if path.is_empty() { "/api" } else { &path });
+    Ok(url.to_string())
+}
+
 /// # Config
 #[derive(Default, Clone)]
 pub struct Config {
@@ -68,43 +361,562 @@ pub struct Config {
     pub addr: Option<String>,
     /// GetAddr when set will be used before every API call to extract
     /// Centrifugo API endpoint. In this case Addr field of Config will be
-    /// ignored. Nil value means using static Config.addr field.
-    pub get_addr: Option<Arc<dyn Fn() -> Result<String, ErrRes>>>,
+    /// ignored unless get_addr_fallback is enabled. Nil value means using
+    /// static Config.addr field.
+    pub get_addr: Option<GetAddr>,
+    /// get_addr_fallback when true makes client send request to addr when
+    /// get_addr fails instead of failing the call, hooks are notified with
+    /// on_endpoint_fallback. Nil value means false.
+    pub get_addr_fallback: Option<bool>,
+    /// get_addr_ttl when set caches result of get_addr for this duration,
+    /// expired value is refreshed in the background. Nil value means get_addr
+    /// is called before every API call.
+    pub get_addr_ttl: Option<Duration>,
+    /// endpoint_refresh_interval when set refreshes result of get_addr and
+    /// endpoints found by discovery in a background task with this interval,
+    /// requests don't wait for resolution except the very first one. Task is
+    /// started with the first request or with start_endpoint_refresher. Nil
+    /// value means endpoints are resolved in the request path.
+    pub endpoint_refresh_interval: Option<Duration>,
+    /// addrs are several API endpoints, e.g. one per zone. Client tracks their
+    /// latency and error rate and sends requests to the fastest healthy one.
+    /// Ignored when get_addr is set. Nil value means using addr.
+    pub addrs: Option<Vec<String>>,
+    /// endpoint_probe_interval is how long endpoint from addrs failing too often
+    /// is avoided before it's probed again. Nil value means DEFAULT_PROBE_INTERVAL.
+    pub endpoint_probe_interval: Option<Duration>,
+    /// discovery finds API endpoints, client sends requests to the fastest
+    /// healthy of them same as with addrs. Ignored when get_addr is set, takes
+    /// precedence over addrs. Nil value means static addr or addrs.
+    pub discovery: Option<Arc<dyn ServiceDiscovery>>,
     /// Centrifugo api key
     pub key: Option<String>,
+    /// key_file when set is a path of file API key is read from instead of key,
+    /// e.g. mounted Kubernetes secret. File is re-read when modified or server
+    /// responds with 401, in the latter case request is repeated once if key
+    /// changed. Nil value means key is used.
+    pub key_file: Option<PathBuf>,
     /// http_client is a custom http client to be used
     /// default is used if nil
     pub http_client: Option<ReqClient>,
+    /// http_layer when set wraps service sending requests with http client
+    /// into tower layers, e.g. tower-http Trace or SetRequestHeader. Nil value
+    /// means requests are sent with http client directly.
+    #[cfg(feature = "tower")]
+    pub http_layer: Option<HttpLayer>,
+    /// max_response_size limits size of response body in bytes, larger responses
+    /// are aborted with ErrResponseTooLarge. Nil value means no limit.
+    pub max_response_size: Option<usize>,
+    /// max_publication_size limits size of serialized data of publish and
+    /// broadcast commands in bytes, larger commands fail with ErrPayloadTooLarge
+    /// without being sent. Nil value means no limit.
+    pub max_publication_size: Option<usize>,
+    /// max_request_size limits size of request body in bytes, larger requests
+    /// fail with ErrPayloadTooLarge without being sent. Nil value means no limit.
+    pub max_request_size: Option<usize>,
+    /// data_validators when set check data of publish and broadcast commands
+    /// per channel or namespace, rejected commands fail with ErrInvalidData
+    /// without being sent. Nil value means no validation.
+    pub data_validators: Option<DataValidators>,
+    /// namespaces when set are namespaces application declared, commands for
+    /// channels of undeclared namespaces or using presence and history not
+    /// declared in namespace fail without being sent. Nil value means any
+    /// channel is allowed.
+    pub namespaces: Option<Namespaces>,
+    /// decompression enables advertising and transparent decoding of gzip, deflate
+    /// and brotli responses by default http client. Nil value means enabled, ignored
+    /// when http_client is set.
+    pub decompression: Option<bool>,
+    /// user_agent is sent in User-Agent header of every request.
+    /// Nil value means DEFAULT_USER_AGENT.
+    pub user_agent: Option<String>,
+    /// client_name when set is sent in X-Client-Name header of every request,
+    /// so server side access logs can attribute API traffic to a service.
+    pub client_name: Option<String>,
+    /// middlewares are applied in order to every HTTP request before it's sent.
+    pub middlewares: Vec<Arc<dyn Middleware>>,
+    /// command_middlewares are applied in order to commands of every request
+    /// before they're validated and serialized.
+    pub command_middlewares: Vec<Arc<dyn CommandMiddleware>>,
+    /// hooks are notified in order about every HTTP request, its replies or
+    /// failure, with methods, endpoint and duration.
+    pub hooks: Vec<Arc<dyn Hook>>,
+    /// recorder when set captures every request and response to it, see
+    /// record module. Nil value means no recording.
+    pub recorder: Option<Arc<Recorder>>,
+    /// strict_decode when true fails on UTF-8 BOM and blank lines in response
+    /// instead of skipping them, see decode_replies. Nil value means false.
+    pub strict_decode: Option<bool>,
+    /// retry_policy when set makes client retry requests failed with connection
+    /// errors, timeouts, 5xx and 429 status codes. Nil value means no retries.
+    pub retry_policy: Option<RetryPolicy>,
+    /// dead_letter when set is invoked with every command of request which
+    /// failed after all retries, so undelivered commands can be persisted or alerted on.
+    pub dead_letter: Option<DeadLetterHandler>,
+    /// results_cache_ttl when set caches results of info and channels calls for
+    /// this duration, so dashboards polling them don't hit server every time.
+    /// Nil value means no caching.
+    pub results_cache_ttl: Option<Duration>,
+    /// idempotency_window when set makes client remember results of publishes
+    /// carrying idempotency key for this duration, repeated publish with the same
+    /// channel and key returns remembered result without hitting server, while
+    /// the first one is in flight it waits for it. Nil value means no client
+    /// side deduplication.
+    pub idempotency_window: Option<Duration>,
+    /// max_in_flight when set limits number of concurrent HTTP requests issued by
+    /// client and its clones, extra requests wait for a free slot. Nil value means
+    /// no limit.
+    pub max_in_flight: Option<usize>,
+    /// auth_scheme defines how api key is sent to server, e.g. for API gateways
+    /// expecting X-API-Key header. Nil value means AuthScheme::Authorization.
+    pub auth_scheme: Option<AuthScheme>,
+    /// basic_auth when set sends HTTP Basic credentials with every request, e.g.
+    /// for reverse proxy in front of server. Both use Authorization header, so
+    /// with api key auth_scheme must be set to pass it in another header,
+    /// otherwise api key isn't sent. Nil value means no basic auth.
+    pub basic_auth: Option<BasicAuth>,
+    /// token_provider when set is used to obtain short-lived token sent in
+    /// `Authorization: Bearer <token>` header. Token is cached and provider is
+    /// invoked again when it nears expiry or server responds with 401, in the
+    /// latter case request is repeated once with fresh token. With api key
+    /// auth_scheme must be set to pass it in another header, otherwise api key
+    /// isn't sent. Nil value means no bearer auth.
+    pub token_provider: Option<TokenProvider>,
+    /// token_refresh_before is how long before expiry bearer token is refreshed.
+    /// Nil value means DEFAULT_REFRESH_BEFORE.
+    pub token_refresh_before: Option<Duration>,
+}
+
+/// BasicAuth is credentials for HTTP Basic authentication.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: Option<String>,
+}
+
+/// AuthScheme defines header used to pass api key with every request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// Authorization sends `Authorization: apikey <key>` header.
+    #[default]
+    Authorization,
+    /// ApiKeyHeader sends `X-API-Key: <key>` header.
+    ApiKeyHeader,
+    /// CustomHeader sends header with given name, value is a format where
+    /// `{key}` is replaced with api key, e.g. `Bearer {key}`.
+    CustomHeader(String, String),
+    /// None sends no auth header, api key is ignored.
+    None,
+}
+
+impl AuthScheme {
+    /// header returns name and value of header passing api key, None is
+    /// returned when nothing should be sent.
+    pub fn header(&self, api_key: &str) -> Option<(String, String)> {
+        match self {
+            AuthScheme::Authorization => {
+                Some(("Authorization".to_string(), format!("apikey {}", api_key)))
+            }
+            AuthScheme::ApiKeyHeader => Some(("X-API-Key".to_string(), api_key.to_string())),
+            AuthScheme::CustomHeader(name, format) => {
+                Some((name.clone(), format.replace("{key}", api_key)))
+            }
+            AuthScheme::None => None,
+        }
+    }
+}
+
+/// CallOptions allows to customize a single API call or pipe.
+#[derive(Clone, Debug, Default)]
+pub struct CallOptions {
+    /// headers are extra HTTP headers sent with request, e.g. tenant routing
+    /// headers for an API gateway in front of Centrifugo.
+    pub headers: Vec<(String, String)>,
+    /// force_refresh bypasses results cache, fresh result is cached again.
+    pub force_refresh: bool,
+    /// api_key overrides Config.key, e.g. for projects routed through the same
+    /// API gateway. Nil value means key of client is used.
+    pub api_key: Option<String>,
+}
+
+impl CallOptions {
+    /// header adds extra HTTP header to send with request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// force_refresh allows to bypass results cache for the call.
+    pub fn force_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    /// api_key allows to send request with another API key, auth scheme of
+    /// client is kept.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
 }
 
 /// # Client
 /// Client is API client for project registered in server.
+#[derive(Clone)]
 pub struct Client {
     pub endpoint: Option<String>,
-    pub get_endpoint: Option<Arc<dyn Fn() -> Result<String, ErrRes>>>,
+    pub get_endpoint: Option<GetAddr>,
+    pub get_addr_fallback: bool,
+    pub endpoint_cache: Option<Arc<EndpointCache>>,
+    pub endpoint_selector: Option<Arc<EndpointSelector>>,
+    pub discovery: Option<Arc<DiscoveredEndpoints>>,
+    pub endpoint_refresher: Option<Arc<EndpointRefresher>>,
     pub api_key: Option<String>,
+    pub key_file: Option<Arc<KeyFile>>,
     pub http_client: ReqClient,
+    /// service is http client wrapped into Config.http_layer, it's not
+    /// updated by set_http_client.
+    #[cfg(feature = "tower")]
+    pub service: Option<HttpService>,
+    pub max_response_size: Option<usize>,
+    pub max_publication_size: Option<usize>,
+    pub max_request_size: Option<usize>,
+    pub data_validators: Option<Arc<DataValidators>>,
+    pub namespaces: Option<Arc<Namespaces>>,
+    pub user_agent: String,
+    pub client_name: Option<String>,
+    pub call_options: CallOptions,
+    pub middlewares: Vec<Arc<dyn Middleware>>,
+    pub command_middlewares: Vec<Arc<dyn CommandMiddleware>>,
+    pub hooks: Vec<Arc<dyn Hook>>,
+    pub recorder: Option<Arc<Recorder>>,
+    pub strict_decode: bool,
+    pub retry_policy: Option<RetryPolicy>,
+    pub dead_letter: Option<DeadLetterHandler>,
+    pub results_cache: Option<Arc<ResultCache>>,
+    pub idempotency_cache: Option<Arc<ResultCache>>,
+    pub lifecycle: Arc<Lifecycle>,
+    pub in_flight: Option<Arc<Semaphore>>,
+    pub auth_scheme: AuthScheme,
+    pub basic_auth: Option<BasicAuth>,
+    pub token_source: Option<Arc<TokenSource>>,
+    pub transport: Arc<TransportCounters>,
+    pub stats: Arc<StatsCounters>,
+}
+
+struct Pages<T, F> {
+    fetch: F,
+    items: VecDeque<T>,
+    /// cursor is a cursor of next page, nil when there are no more pages.
+    cursor: Option<Option<String>>,
+}
+
+// paginate yields items of pages returned by fetch, which gets cursor of page
+// and returns its items with cursor of the next one. Empty cursor or the same
+// cursor returned again ends pagination.
+fn paginate<T, F, Fut>(
+    cursor: Option<String>,
+    fetch: F,
+) -> impl Stream<Item = Result<T, ErrRes>> + Send + 'static
+where
+    T: Send + 'static,
+    F: Fn(Option<String>) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<(Vec<T>, String), ErrRes>> + Send,
+{
+    let pages = Pages {
+        fetch,
+        items: VecDeque::new(),
+        cursor: Some(cursor),
+    };
+    stream::unfold(pages, |mut pages| async move {
+        loop {
+            if let Some(item) = pages.items.pop_front() {
+                return Some((Ok(item), pages));
+            }
+            let cursor = pages.cursor.take()?;
+            match (pages.fetch)(cursor.clone()).await {
+                Ok((items, next_cursor)) => {
+                    pages.items = items.into();
+                    if !next_cursor.is_empty() && cursor.as_deref() != Some(&next_cursor) {
+                        pages.cursor = Some(Some(next_cursor));
+                    }
+                }
+                Err(err) => return Some((Err(err), pages)),
+            }
+        }
+    })
 }
 
 /// DefaultHTTPClent
 pub fn default_http_client() -> ReqClient {
-    ReqClient::builder()
-        .pool_max_idle_per_host(300)// set max pool to 300
-        .timeout(Duration::from_secs(600)) // set timeout to 10 minutes
+    try_default_http_client().unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// try_default_http_client is the same as default_http_client but reports
+/// failure to build client, e.g. when TLS backend can't be initialized.
+pub fn try_default_http_client() -> Result<ReqClient, ConfigError> {
+    default_http_client_builder()
+        .build()
+        .map_err(|err| http_client_error(&err))
+}
+
+fn http_client_error(err: &reqwest::Error) -> ConfigError {
+    ConfigError {
+        field: "http_client".to_string(),
+        message: format!("failed to build default http client: {err}"),
+    }
+}
+
+// build_http_client returns Config.http_client or builds default one
+// honouring Config.decompression.
+fn build_http_client(config: &Config) -> Result<ReqClient, ConfigError> {
+    if let Some(http_client) = &config.http_client {
+        return Ok(http_client.clone());
+    }
+    let decompression = config.decompression.unwrap_or(true);
+    default_http_client_builder()
+        .gzip(decompression)
+        .deflate(decompression)
+        .brotli(decompression)
         .build()
-        .unwrap()
+        .map_err(|err| http_client_error(&err))
+}
+
+// validate_header_value checks that value of Config field can be sent in
+// HTTP header, otherwise every request would fail to be built.
+fn validate_header_value(field: &str, value: &str) -> Result<(), ConfigError> {
+    HeaderValue::from_str(value)
+        .map(|_| ())
+        .map_err(|_| ConfigError {
+            field: field.to_string(),
+            message: "contains characters not allowed in HTTP header".to_string(),
+        })
+}
+
+/// default_http_client_builder returns builder with default http client settings,
+/// used to build http client when Config.http_client is not set.
+pub fn default_http_client_builder() -> ReqClientBuilder {
+    let builder = ReqClient::builder()
+        .pool_max_idle_per_host(300) // set max pool to 300
+        .timeout(Duration::from_secs(600)); // set timeout to 10 minutes
+
+    // prefer rustls when enabled, so openssl is never used even if
+    // native-tls is enabled too through default features.
+    #[cfg(feature = "rustls")]
+    let builder = builder.use_rustls_tls();
+
+    // certificate of server is passed to Hook::on_connect.
+    #[cfg(any(feature = "native-tls", feature = "rustls"))]
+    let builder = builder.tls_info(true);
+
+    builder
 }
 
 impl Client {
     /// Create a new client instance.
     pub fn new(config: Config) -> Self {
-        let http_client = config.http_client.unwrap_or_else(default_http_client);
+        let mut config = config;
+        // invalid address is kept as is, try_new reports it instead.
+        if let Some(addr) = &config.addr {
+            if let Ok(addr) = normalize_addr(addr) {
+                config.addr = Some(addr);
+            }
+        }
+        // failure to build http client is reported by try_new instead.
+        let http_client = build_http_client(&config).unwrap_or_else(|err| panic!("{err}"));
+        let endpoint_cache = match (
+            &config.get_addr,
+            config.get_addr_ttl,
+            config.endpoint_refresh_interval,
+        ) {
+            (Some(get_addr), Some(ttl), _) => Some(EndpointCache::new(get_addr.clone(), ttl)),
+            // refresher keeps cached value fresh, it never expires on its own.
+            (Some(get_addr), None, Some(_)) => {
+                Some(EndpointCache::new(get_addr.clone(), Duration::MAX))
+            }
+            _ => None,
+        };
+        let probe_interval = config
+            .endpoint_probe_interval
+            .unwrap_or(DEFAULT_PROBE_INTERVAL);
+        let discovery = match (&config.get_addr, &config.discovery) {
+            (None, Some(discovery)) => {
+                Some(DiscoveredEndpoints::new(discovery.clone(), probe_interval))
+            }
+            _ => None,
+        };
+        let endpoint_selector = match (&config.get_addr, &discovery, &config.addrs) {
+            (None, Some(discovery), _) => Some(discovery.selector().clone()),
+            (None, None, Some(addrs)) if !addrs.is_empty() => Some(EndpointSelector::new(
+                addrs
+                    .iter()
+                    .map(|addr| normalize_addr(addr).unwrap_or_else(|_| addr.clone()))
+                    .collect(),
+                probe_interval,
+            )),
+            _ => None,
+        };
+        let endpoint_refresher = match config.endpoint_refresh_interval {
+            Some(interval) if endpoint_cache.is_some() || discovery.is_some() => Some(
+                EndpointRefresher::new(interval, endpoint_cache.clone(), discovery.clone()),
+            ),
+            _ => None,
+        };
         Client {
             endpoint: config.addr,
             get_endpoint: config.get_addr,
+            get_addr_fallback: config.get_addr_fallback.unwrap_or(false),
+            endpoint_cache,
+            endpoint_selector,
+            discovery,
+            endpoint_refresher,
             api_key: config.key,
+            key_file: config.key_file.map(KeyFile::new),
+            #[cfg(feature = "tower")]
+            service: config
+                .http_layer
+                .map(|layer| layer(ApiService::new(http_client.clone()))),
             http_client,
+            max_response_size: config.max_response_size,
+            max_publication_size: config.max_publication_size,
+            max_request_size: config.max_request_size,
+            data_validators: config.data_validators.map(Arc::new),
+            namespaces: config.namespaces.map(Arc::new),
+            user_agent: config
+                .user_agent
+                .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            client_name: config.client_name,
+            call_options: CallOptions::default(),
+            middlewares: config.middlewares,
+            command_middlewares: config.command_middlewares,
+            hooks: config.hooks,
+            recorder: config.recorder,
+            strict_decode: config.strict_decode.unwrap_or(false),
+            retry_policy: config.retry_policy,
+            dead_letter: config.dead_letter,
+            results_cache: config
+                .results_cache_ttl
+                .map(|ttl| Arc::new(ResultCache::new(ttl))),
+            idempotency_cache: config
+                .idempotency_window
+                .map(|window| Arc::new(ResultCache::new(window))),
+            lifecycle: Arc::new(Lifecycle::default()),
+            in_flight: config
+                .max_in_flight
+                .map(|limit| Arc::new(Semaphore::new(limit.max(1)))),
+            auth_scheme: config.auth_scheme.unwrap_or_default(),
+            basic_auth: config.basic_auth,
+            token_source: config.token_provider.map(|provider| {
+                TokenSource::new(
+                    provider,
+                    config
+                        .token_refresh_before
+                        .unwrap_or(DEFAULT_REFRESH_BEFORE),
+                )
+            }),
+            transport: Arc::default(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// try_new is the same as new but validates config, so misconfiguration is
+    /// reported at construction instead of failing at request time: there must
+    /// be an endpoint source, addresses and header values must be valid, api key
+    /// must not share Authorization header with basic_auth or token_provider and
+    /// default http client must build. new panics on the latter.
+    pub fn try_new(config: Config) -> Result<Self, ConfigError> {
+        for addr in config.addrs.iter().flatten() {
+            normalize_addr(addr)?;
+        }
+        let has_addrs = config.addrs.as_ref().is_some_and(|addrs| !addrs.is_empty())
+            || config.discovery.is_some();
+        match (&config.addr, &config.get_addr) {
+            (Some(addr), _) => {
+                normalize_addr(addr)?;
+            }
+            (None, None) if !has_addrs => {
+                return Err(ConfigError {
+                    field: "addr".to_string(),
+                    message: "either addr, addrs, discovery or get_addr must be set".to_string(),
+                })
+            }
+            (None, _) => {}
+        }
+        if let Some(key) = &config.key {
+            validate_header_value("key", key)?;
+        }
+        if let Some(key_file) = &config.key_file {
+            let key = KeyFile::read(key_file).map_err(|err| ConfigError {
+                field: "key_file".to_string(),
+                message: format!("failed to read {}: {err}", key_file.display()),
+            })?;
+            validate_header_value("key_file", &key)?;
+        }
+        let has_key = config.key.is_some() || config.key_file.is_some();
+        let key_in_authorization =
+            matches!(config.auth_scheme, None | Some(AuthScheme::Authorization));
+        if has_key && key_in_authorization {
+            let field = if config.basic_auth.is_some() {
+                Some("basic_auth")
+            } else if config.token_provider.is_some() {
+                Some("token_provider")
+            } else {
+                None
+            };
+            if let Some(field) = field {
+                return Err(ConfigError {
+                    field: field.to_string(),
+                    message: "api key is sent in Authorization header too, set auth_scheme to pass it in another header".to_string(),
+                });
+            }
+        }
+        if let Some(user_agent) = &config.user_agent {
+            validate_header_value("user_agent", user_agent)?;
+        }
+        if let Some(client_name) = &config.client_name {
+            validate_header_value("client_name", client_name)?;
+        }
+        let mut config = config;
+        config.http_client = Some(build_http_client(&config)?);
+        Ok(Self::new(config))
+    }
+
+    /// transport_stats returns statistics of HTTP requests and connections made by
+    /// client and its clones, to diagnose capacity issues against API.
+    pub fn transport_stats(&self) -> TransportStats {
+        self.transport.snapshot()
+    }
+
+    /// stats returns commands sent, error replies and bytes transferred by
+    /// client and its clones.
+    pub fn stats(&self) -> ClientStats {
+        self.stats.snapshot()
+    }
+
+    /// reset_stats sets counters returned by stats to zero, returns statistics
+    /// collected before, e.g. to report them periodically as deltas.
+    pub fn reset_stats(&self) -> ClientStats {
+        self.stats.reset()
+    }
+
+    /// shutdown stops accepting new calls on client and its clones, signals
+    /// outbox publishers and watchers started from it to stop and waits up to
+    /// timeout for in-flight requests and those tasks to finish. Returned report
+    /// tells how many of them were still running at deadline.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        self.lifecycle.shutdown(timeout).await
+    }
+
+    /// with_call_options returns a copy of client applying call_opts to every call
+    /// made through it, underlying http client and caches are shared.
+    pub fn with_call_options(&self, call_opts: CallOptions) -> Client {
+        let mut client = self.clone();
+        client.call_options.headers.extend(call_opts.headers);
+        client.call_options.force_refresh |= call_opts.force_refresh;
+        if call_opts.api_key.is_some() {
+            client.call_options.api_key = call_opts.api_key;
         }
+        client
     }
 
     /// set_http_client allows to set custom http client to use for requests.
@@ -114,9 +926,19 @@ impl Client {
 
     /// pipe allows to create new pipe to send several commands in one HTTP request.
     pub fn pipe(&self) -> Pipe {
-        Pipe {
-            commands: Arc::new(Mutex::new(Vec::new())),
-        }
+        Pipe::default()
+    }
+
+    /// channel returns handle with methods bound to channel, so it doesn't have
+    /// to be passed to every call.
+    pub fn channel(&self, channel: impl Into<String>) -> ChannelHandle {
+        ChannelHandle::new(self.clone(), channel.into())
+    }
+
+    /// user returns handle with methods bound to user, so it doesn't have to be
+    /// passed to every call.
+    pub fn user(&self, user: impl Into<String>) -> UserHandle {
+        UserHandle::new(self.clone(), user.into())
     }
 
     /// Publish allows to publish data to channel.
@@ -125,27 +947,86 @@ impl Client {
         channel: String,
         data: &str,
         opts: &[PublishOption],
-    ) -> Result<PublishResult, Box<dyn Error>> {
-        let pipe = self.pipe();
-        pipe.add_publish(channel, data, opts)?;
+    ) -> Result<PublishResult, ErrRes> {
+        let mut options = PublishOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
 
-        let response = self.send_pipe(&pipe).await;
+        self.publish_with_options(channel, data, options).await
+    }
 
-        let result = match response {
-            Ok(response) => response,
-            Err(err) => return Err(err),
+    /// PublishWithOptions is the same as publish but takes options built with
+    /// PublishOptions::new() instead of closures.
+    pub async fn publish_with_options(
+        &self,
+        channel: String,
+        data: &str,
+        options: PublishOptions,
+    ) -> Result<PublishResult, ErrRes> {
+        let dedupe = match (&self.idempotency_cache, &options.idempotency_key) {
+            (Some(cache), Some(key)) => Some((cache, self.cache_key(format!("{channel}:{key}")))),
+            _ => None,
+        };
+        // concurrent publishes with the same key wait for the first one.
+        let _computing = match &dedupe {
+            Some((cache, key)) => Some(cache.lock(key).await),
+            None => None,
         };
+        if let Some((cache, key)) = &dedupe {
+            if let Some(result) = cache.get(key) {
+                let mut result = decode_publish(&serde_json::to_vec(&result)?)?;
+                result.idempotency_key = options.idempotency_key;
+                return Ok(result);
+            }
+        }
 
-        if result.is_empty() {
-            return Err("No reply from server".into());
+        let idempotency_key = options.idempotency_key.clone();
+        let pipe = self.pipe();
+        pipe.add_publish_with_options(channel, data, options)?;
+        let resp = self.send_single(&pipe).await?;
+        if let (Some((cache, key)), Some(result)) = (dedupe, &resp.result) {
+            cache.put(key, result.clone());
         }
+        let mut result = decode_publish(&serde_json::to_vec(&resp.result)?)?;
+        result.idempotency_key = idempotency_key;
+        Ok(result)
+    }
 
-        let resp = &result[0];
-        if let Some(err) = &resp.error {
-            return Err(Box::new(err.clone()));
+    /// PublishMany allows to publish many messages into one channel in a single
+    /// request, returns results in order of items. Nothing is sent when there
+    /// are no items.
+    pub async fn publish_many<T: AsRef<str>>(
+        &self,
+        channel: String,
+        items: impl IntoIterator<Item = T>,
+        opts: &[PublishOption],
+    ) -> Result<Vec<Result<PublishResult, ErrRes>>, ErrRes> {
+        let pipe = self.pipe();
+        for item in items {
+            pipe.add_publish(channel.clone(), item.as_ref(), opts)?;
+        }
+        if pipe.is_empty() {
+            return Ok(Vec::new());
         }
 
-        decode_publish(&serde_json::to_vec(&resp.result).unwrap())
+        let result = match self.send_pipe(&pipe).await {
+            Ok(response) => response,
+            Err(err) => return Err(err),
+        };
+
+        Ok(result
+            .into_iter()
+            .enumerate()
+            .map(|(index, resp)| match resp.error {
+                Some(error) => Err(Box::new(PipeCommandError {
+                    index,
+                    method: Method::Publish,
+                    error,
+                }) as ErrRes),
+                None => decode_publish(&serde_json::to_vec(&resp.result)?),
+            })
+            .collect())
     }
 
     /// Broadcast allows to broadcast the same data into many channels..
@@ -154,9 +1035,14 @@ impl Client {
         channels: Vec<String>,
         data: &str,
         opts: &[PublishOption],
-    ) -> Result<BroadcastResult, Box<dyn Error>> {
+    ) -> Result<BroadcastResult, ErrRes> {
+        let mut options = PublishOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
+        let idempotency_key = options.idempotency_key.clone();
         let pipe = self.pipe();
-        let _ = pipe.add_broadcast(channels, data, opts);
+        pipe.add_broadcast_with_options(channels, data, options)?;
 
         let response = self.send_pipe(&pipe).await;
 
@@ -174,7 +1060,49 @@ impl Client {
             return Err(Box::new(err.clone()));
         }
 
-        decode_broadcast(&serde_json::to_vec(&resp.result).unwrap())
+        let mut result = decode_broadcast(&serde_json::to_vec(&resp.result).unwrap())?;
+        set_idempotency_key(&mut result, idempotency_key);
+        Ok(result)
+    }
+
+    /// BroadcastWithOptions is the same as broadcast but takes options built with
+    /// PublishOptions::new() instead of closures.
+    pub async fn broadcast_with_options(
+        &self,
+        channels: Vec<String>,
+        data: &str,
+        options: PublishOptions,
+    ) -> Result<BroadcastResult, ErrRes> {
+        let pipe = self.pipe();
+        let idempotency_key = options.idempotency_key.clone();
+        pipe.add_broadcast_with_options(channels, data, options)?;
+        let resp = self.send_single(&pipe).await?;
+        let mut result = decode_broadcast(&serde_json::to_vec(&resp.result)?)?;
+        set_idempotency_key(&mut result, idempotency_key);
+        Ok(result)
+    }
+
+    /// BroadcastMap is the same as broadcast but returns results keyed by channel
+    /// instead of responses in order of channels.
+    pub async fn broadcast_map(
+        &self,
+        channels: Vec<String>,
+        data: &str,
+        opts: &[PublishOption],
+    ) -> Result<HashMap<String, Result<PublishResult, ProtocolError>>, ErrRes> {
+        let result = self.broadcast(channels.clone(), data, opts).await?;
+        if result.responses.len() != channels.len() {
+            return Err(Box::new(ErrMalformedResponse {}));
+        }
+
+        Ok(channels
+            .into_iter()
+            .zip(result.responses)
+            .map(|(channel, resp)| match resp.error {
+                Some(error) => (channel, Err(error)),
+                None => (channel, Ok(resp.result)),
+            })
+            .collect())
     }
 
     /// Subscribe allow subscribing user to a channel (using server-side subscriptions).
@@ -183,7 +1111,7 @@ impl Client {
         channel: String,
         user: String,
         opts: &[SubscribeOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_subscribe(channel, user, opts);
 
@@ -206,13 +1134,51 @@ impl Client {
         Ok(())
     }
 
+    /// SubscribeWithOptions is the same as subscribe but takes options built with
+    /// SubscribeOptions::new() instead of closures.
+    pub async fn subscribe_with_options(
+        &self,
+        channel: String,
+        user: String,
+        options: SubscribeOptions,
+    ) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_subscribe_with_options(channel, user, options)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// SubscribeChannels subscribes user to many channels in a single request,
+    /// outcomes are keyed by channel, channel server failed to subscribe user to
+    /// has PipeCommandError.
+    pub async fn subscribe_channels<T: Into<String>>(
+        &self,
+        user: String,
+        channels: impl IntoIterator<Item = T>,
+        opts: &[SubscribeOption],
+    ) -> Result<HashMap<String, Result<(), ErrRes>>, ErrRes> {
+        let pipe = self.pipe();
+        let channels = unique_keys(channels);
+        if channels.is_empty() {
+            return Ok(HashMap::new());
+        }
+        for channel in &channels {
+            pipe.add_subscribe(channel.clone(), user.clone(), opts)?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+        Ok(keyed_results(channels, result, Method::Subscribe, |_| {
+            Ok(())
+        }))
+    }
+
     /// Unsubscribe allows to unsubscribe user from channel.
     pub async fn unsubscribe(
         &self,
         channel: String,
         user: String,
         opts: &[UnsubscribeOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_unsubscribe(channel, user, opts);
 
@@ -235,12 +1201,22 @@ impl Client {
         Ok(())
     }
 
-    /// Disconnect allows to close all connections of user to server.
-    pub async fn disconnect(
+    /// UnsubscribeWithOptions is the same as unsubscribe but takes options built with
+    /// UnsubscribeOptions::new() instead of closures.
+    pub async fn unsubscribe_with_options(
         &self,
+        channel: String,
         user: String,
-        opts: &[DisconnectOption],
-    ) -> Result<(), Box<dyn Error>> {
+        options: UnsubscribeOptions,
+    ) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_unsubscribe_with_options(channel, user, options)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// Disconnect allows to close all connections of user to server.
+    pub async fn disconnect(&self, user: String, opts: &[DisconnectOption]) -> Result<(), ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_disconnect(user, opts);
 
@@ -263,8 +1239,58 @@ impl Client {
         Ok(())
     }
 
+    /// DisconnectWithOptions is the same as disconnect but takes options built with
+    /// DisconnectOptions::new() instead of closures.
+    pub async fn disconnect_with_options(
+        &self,
+        user: String,
+        options: DisconnectOptions,
+    ) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_disconnect_with_options(user, options)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// DisconnectAll allows to disconnect many users in a single request with
+    /// the same disconnect code, connections of clients from whitelist are kept.
+    /// It's a shortcut for disconnect_many, outcomes are keyed by user.
+    pub async fn disconnect_all<T: Into<String>>(
+        &self,
+        users: impl IntoIterator<Item = T>,
+        disconnect: Disconnect,
+        whitelist: Vec<String>,
+    ) -> Result<HashMap<String, Result<(), ErrRes>>, ErrRes> {
+        let mut opts = vec![with_disconnect(disconnect)];
+        if !whitelist.is_empty() {
+            opts.push(with_disconnect_client_whitelist(whitelist));
+        }
+        self.disconnect_many(users, &opts).await
+    }
+
+    /// DisconnectMany disconnects many users in a single request applying the
+    /// same opts to every user, outcomes are keyed by user, user server failed
+    /// to disconnect has PipeCommandError.
+    pub async fn disconnect_many<T: Into<String>>(
+        &self,
+        users: impl IntoIterator<Item = T>,
+        opts: &[DisconnectOption],
+    ) -> Result<HashMap<String, Result<(), ErrRes>>, ErrRes> {
+        let pipe = self.pipe();
+        let users = unique_keys(users);
+        if users.is_empty() {
+            return Ok(HashMap::new());
+        }
+        for user in &users {
+            pipe.add_disconnect(user.clone(), opts)?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+        Ok(keyed_results(users, result, Method::Disconnect, |_| Ok(())))
+    }
+
     /// Presence returns channel presence information.
-    pub async fn presence(&self, channel: String) -> Result<PresenceResult, Box<dyn Error>> {
+    pub async fn presence(&self, channel: String) -> Result<PresenceResult, ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_presence(channel);
 
@@ -288,10 +1314,7 @@ impl Client {
     }
 
     /// PresenceStats returns short channel presence information (only counters).
-    pub async fn presence_stats(
-        &self,
-        channel: String,
-    ) -> Result<PresenceStatsResult, Box<dyn Error>> {
+    pub async fn presence_stats(&self, channel: String) -> Result<PresenceStatsResult, ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_presence_stats(channel);
 
@@ -314,12 +1337,96 @@ impl Client {
         decode_presence_stats(&serde_json::to_vec(&resp.result).unwrap())
     }
 
+    /// PresenceStatsMany returns short presence information of many channels in
+    /// a single request. Fails with PipeCommandError if server failed to return
+    /// stats of any channel.
+    pub async fn presence_stats_many<T: Into<String>>(
+        &self,
+        channels: impl IntoIterator<Item = T>,
+    ) -> Result<HashMap<String, PresenceStatsResult>, ErrRes> {
+        let pipe = self.pipe();
+        let names = unique_keys(channels);
+        if names.is_empty() {
+            return Ok(HashMap::new());
+        }
+        for channel in &names {
+            pipe.add_presence_stats(channel.clone())?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+
+        let mut stats = HashMap::with_capacity(names.len());
+        for (index, (channel, resp)) in names.into_iter().zip(result).enumerate() {
+            if let Some(error) = resp.error {
+                return Err(Box::new(PipeCommandError {
+                    index,
+                    method: Method::PresenceStats,
+                    error,
+                }));
+            }
+            let result = decode_presence_stats(&serde_json::to_vec(&resp.result)?)?;
+            stats.insert(channel, result);
+        }
+        Ok(stats)
+    }
+
+    /// HistoryMany returns history of many channels in a single request, results
+    /// are keyed by channel, channel server failed to return history of has
+    /// PipeCommandError.
+    pub async fn history_many<T: Into<String>>(
+        &self,
+        channels: impl IntoIterator<Item = T>,
+        opts: &[HistoryOption],
+    ) -> Result<HashMap<String, Result<HistoryResult, ErrRes>>, ErrRes> {
+        let pipe = self.pipe();
+        let channels = unique_keys(channels);
+        if channels.is_empty() {
+            return Ok(HashMap::new());
+        }
+        for channel in &channels {
+            pipe.add_history(channel.clone(), opts)?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+        Ok(keyed_results(
+            channels,
+            result,
+            Method::History,
+            decode_history,
+        ))
+    }
+
+    /// PresenceMany returns presence information of many channels in a single
+    /// request, results are keyed by channel, channel server failed to return
+    /// presence of has PipeCommandError.
+    pub async fn presence_many<T: Into<String>>(
+        &self,
+        channels: impl IntoIterator<Item = T>,
+    ) -> Result<HashMap<String, Result<PresenceResult, ErrRes>>, ErrRes> {
+        let pipe = self.pipe();
+        let channels = unique_keys(channels);
+        if channels.is_empty() {
+            return Ok(HashMap::new());
+        }
+        for channel in &channels {
+            pipe.add_presence(channel.clone())?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+        Ok(keyed_results(
+            channels,
+            result,
+            Method::Presence,
+            decode_presence,
+        ))
+    }
+
     /// History returns channel history.
     pub async fn history(
         &self,
         channel: String,
         opts: &[HistoryOption],
-    ) -> Result<HistoryResult, Box<dyn Error>> {
+    ) -> Result<HistoryResult, ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_history(channel, opts);
 
@@ -342,8 +1449,21 @@ impl Client {
         decode_history(&serde_json::to_vec(&resp.result).unwrap())
     }
 
+    /// HistoryWithOptions is the same as history but takes options built with
+    /// HistoryOptions::new() instead of closures.
+    pub async fn history_with_options(
+        &self,
+        channel: String,
+        options: HistoryOptions,
+    ) -> Result<HistoryResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_history_with_options(channel, options)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_history(&serde_json::to_vec(&resp.result)?)
+    }
+
     /// HistoryRemove removes channel history.
-    pub async fn history_remove(&self, channel: String) -> Result<(), Box<dyn Error>> {
+    pub async fn history_remove(&self, channel: String) -> Result<(), ErrRes> {
         let pipe = self.pipe();
         let _ = pipe.add_history_remove(channel);
 
@@ -367,40 +1487,377 @@ impl Client {
     }
 
     /// Channels returns information about active channels (with one or more subscribers) on server.
-    pub async fn channels(
+    pub async fn channels(&self, opts: &[ChannelsOption]) -> Result<ChannelsResult, ErrRes> {
+        let mut options = ChannelsOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
+
+        self.channels_with_options(options).await
+    }
+
+    /// ChannelsWithOptions is the same as channels but takes options built with
+    /// ChannelsOptions::new() instead of closures.
+    pub async fn channels_with_options(
         &self,
-        opts: &[ChannelsOption],
-    ) -> Result<ChannelsResult, Box<dyn Error>> {
+        options: ChannelsOptions,
+    ) -> Result<ChannelsResult, ErrRes> {
+        let key = format!(
+            "channels:{}",
+            options.pattern.as_deref().unwrap_or_default()
+        );
         let pipe = self.pipe();
-        let _ = pipe.add_channels(opts);
+        pipe.add_channels_with_options(options)?;
+        let result = self.send_cached(key, &pipe).await?;
+        decode_channels(&serde_json::to_vec(&result)?)
+    }
 
-        let response = self.send_pipe(&pipe).await;
+    /// Info returns information about server nodes.
+    pub async fn info(&self) -> Result<InfoResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_info()?;
+        let result = self.send_cached("info".to_string(), &pipe).await?;
+        decode_info(&serde_json::to_vec(&result)?)
+    }
 
-        let result = match response {
-            Ok(response) => response,
-            Err(err) => return Err(err),
-        };
+    /// Refresh allows to refresh user connection (mostly useful when unidirectional
+    /// transports are used).
+    pub async fn refresh(&self, user: String, opts: &[RefreshOption]) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_refresh(user, opts)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
 
-        if result.is_empty() {
-            return Err("No reply from server".into());
+    /// RefreshWithOptions is the same as refresh but takes options built with
+    /// RefreshOptions::new() instead of closures.
+    pub async fn refresh_with_options(
+        &self,
+        user: String,
+        options: RefreshOptions,
+    ) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_refresh_with_options(user, options)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// RefreshMany extends connections of many users in a single request, every
+    /// entry is a user and moment connections of user expire at, the last
+    /// entry wins if user is repeated. Outcomes are keyed by user, user server
+    /// failed to refresh has PipeCommandError.
+    pub async fn refresh_many<T: Into<String>>(
+        &self,
+        entries: impl IntoIterator<Item = (T, Timestamp)>,
+    ) -> Result<HashMap<String, Result<(), ErrRes>>, ErrRes> {
+        let mut expire_at = HashMap::new();
+        let users = unique_keys(entries.into_iter().map(|(user, at)| {
+            let user = user.into();
+            expire_at.insert(user.clone(), at);
+            user
+        }));
+        if users.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        let resp = &result[0];
-        if let Some(err) = &resp.error {
-            return Err(Box::new(err.clone()));
+        let pipe = self.pipe();
+        for user in &users {
+            let options = RefreshOptions::new().expire_at(expire_at[user]);
+            pipe.add_refresh_with_options(user.clone(), options)?;
         }
 
-        decode_channels(&serde_json::to_vec(&resp.result).unwrap())
+        let result = self.send_pipe(&pipe).await?;
+        Ok(keyed_results(users, result, Method::Refresh, |_| Ok(())))
     }
 
-    /// Info returns information about server nodes.
-    pub async fn info(&self) -> Result<InfoResult, Box<dyn Error>> {
+    /// Connections returns information about user connections (Centrifugo PRO).
+    pub async fn connections(
+        &self,
+        opts: &[ConnectionsOption],
+    ) -> Result<ConnectionsResult, ErrRes> {
         let pipe = self.pipe();
-        let _ = pipe.add_info();
+        pipe.add_connections(opts)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_connections(&serde_json::to_vec(&resp.result)?)
+    }
 
-        let response = self.send_pipe(&pipe).await;
+    /// ConnectionsWithOptions is the same as connections but takes options built with
+    /// ConnectionsOptions::new() instead of closures.
+    pub async fn connections_with_options(
+        &self,
+        options: ConnectionsOptions,
+    ) -> Result<ConnectionsResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_connections_with_options(options)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_connections(&serde_json::to_vec(&resp.result)?)
+    }
 
-        let result = match response {
+    /// UpdateUserStatus updates last activity time of users (Centrifugo PRO).
+    pub async fn update_user_status(
+        &self,
+        users: Vec<String>,
+        state: Option<String>,
+    ) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_update_user_status(users, state)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// GetUserStatus returns last activity information of users (Centrifugo PRO).
+    pub async fn get_user_status(&self, users: Vec<String>) -> Result<GetUserStatusResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_get_user_status(users)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_get_user_status(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// get_user_status_many returns last activity information of many users
+    /// keyed by user, users without status are missing. Users are requested by
+    /// chunks of USER_STATUS_CHUNK_SIZE, all sent in a single request. Fails with
+    /// PipeCommandError if server failed to return statuses of any chunk.
+    pub async fn get_user_status_many<T: Into<String>>(
+        &self,
+        users: impl IntoIterator<Item = T>,
+    ) -> Result<HashMap<String, UserStatus>, ErrRes> {
+        let users = unique_keys(users);
+        if users.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let pipe = self.pipe();
+        for chunk in users.chunks(USER_STATUS_CHUNK_SIZE) {
+            pipe.add_get_user_status(chunk.to_vec())?;
+        }
+
+        let result = self.send_pipe(&pipe).await?;
+
+        let mut statuses = HashMap::with_capacity(users.len());
+        for (index, resp) in result.into_iter().enumerate() {
+            if let Some(error) = resp.error {
+                return Err(Box::new(PipeCommandError {
+                    index,
+                    method: Method::GetUserStatus,
+                    error,
+                }));
+            }
+            let result = decode_get_user_status(&serde_json::to_vec(&resp.result)?)?;
+            for status in result.statuses {
+                statuses.insert(status.user.clone(), status);
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// DeleteUserStatus removes last activity information of users (Centrifugo PRO).
+    pub async fn delete_user_status(&self, users: Vec<String>) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_delete_user_status(users)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// DeviceRegister registers device for push notifications (Centrifugo PRO).
+    pub async fn device_register(
+        &self,
+        req: DeviceRegisterRequest,
+    ) -> Result<DeviceRegisterResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_register(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_device_register(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// DeviceUpdate updates registered devices (Centrifugo PRO).
+    pub async fn device_update(&self, req: DeviceUpdateRequest) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_update(req)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// DeviceRemove removes registered devices (Centrifugo PRO).
+    pub async fn device_remove(&self, req: DeviceRemoveRequest) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_remove(req)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// DeviceList returns a page of registered devices (Centrifugo PRO).
+    pub async fn device_list(&self, req: DeviceListRequest) -> Result<DeviceListResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_list(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_device_list(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// device_list_stream returns all devices matching req following cursors,
+    /// pages of req.limit size are requested as stream is consumed. Stream
+    /// ends after first error.
+    pub fn device_list_stream(
+        &self,
+        req: DeviceListRequest,
+    ) -> impl Stream<Item = Result<Device, ErrRes>> + Send + 'static {
+        let client = self.clone();
+        paginate(req.cursor.clone(), move |cursor| {
+            let client = client.clone();
+            let req = DeviceListRequest {
+                cursor,
+                ..req.clone()
+            };
+            async move {
+                let result = client.device_list(req).await?;
+                Ok((result.items, result.next_cursor))
+            }
+        })
+    }
+
+    /// DeviceTopicList returns a page of device topic subscriptions (Centrifugo PRO).
+    pub async fn device_topic_list(
+        &self,
+        req: DeviceTopicListRequest,
+    ) -> Result<DeviceTopicListResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_topic_list(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_device_topic_list(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// device_topic_list_stream returns all device topic subscriptions matching
+    /// req following cursors, see device_list_stream.
+    pub fn device_topic_list_stream(
+        &self,
+        req: DeviceTopicListRequest,
+    ) -> impl Stream<Item = Result<DeviceTopic, ErrRes>> + Send + 'static {
+        let client = self.clone();
+        paginate(req.cursor.clone(), move |cursor| {
+            let client = client.clone();
+            let req = DeviceTopicListRequest {
+                cursor,
+                ..req.clone()
+            };
+            async move {
+                let result = client.device_topic_list(req).await?;
+                Ok((result.items, result.next_cursor))
+            }
+        })
+    }
+
+    /// DeviceTopicUpdate changes topics device is subscribed to (Centrifugo PRO).
+    pub async fn device_topic_update(&self, req: DeviceTopicUpdateRequest) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_device_topic_update(req)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// UserTopicList returns a page of user topic subscriptions (Centrifugo PRO).
+    pub async fn user_topic_list(
+        &self,
+        req: UserTopicListRequest,
+    ) -> Result<UserTopicListResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_user_topic_list(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_user_topic_list(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// user_topic_list_stream returns all user topic subscriptions matching req
+    /// following cursors, see device_list_stream.
+    pub fn user_topic_list_stream(
+        &self,
+        req: UserTopicListRequest,
+    ) -> impl Stream<Item = Result<UserTopic, ErrRes>> + Send + 'static {
+        let client = self.clone();
+        paginate(req.cursor.clone(), move |cursor| {
+            let client = client.clone();
+            let req = UserTopicListRequest {
+                cursor,
+                ..req.clone()
+            };
+            async move {
+                let result = client.user_topic_list(req).await?;
+                Ok((result.items, result.next_cursor))
+            }
+        })
+    }
+
+    /// UserTopicUpdate changes topics user is subscribed to (Centrifugo PRO).
+    pub async fn user_topic_update(&self, req: UserTopicUpdateRequest) -> Result<(), ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_user_topic_update(req)?;
+        self.send_single(&pipe).await?;
+        Ok(())
+    }
+
+    /// SendPushNotification sends push notification to devices (Centrifugo PRO).
+    pub async fn send_push_notification(
+        &self,
+        req: SendPushNotificationRequest,
+    ) -> Result<SendPushNotificationResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_send_push_notification(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_send_push_notification(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// RateLimit consumes tokens from rate limit bucket (Centrifugo PRO).
+    pub async fn rate_limit(&self, req: RateLimitRequest) -> Result<RateLimitResult, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_rate_limit(req)?;
+        let resp = self.send_single(&pipe).await?;
+        decode_rate_limit(&serde_json::to_vec(&resp.result)?)
+    }
+
+    /// send_cached sends pipe with a single command returning its result from
+    /// results cache under key when possible.
+    async fn send_cached(
+        &self,
+        key: String,
+        pipe: &Pipe,
+    ) -> Result<Option<serde_json::Value>, ErrRes> {
+        let cache = match &self.results_cache {
+            Some(cache) => cache,
+            None => return Ok(self.send_single(pipe).await?.result),
+        };
+        let key = self.cache_key(key);
+
+        if !self.call_options.force_refresh {
+            if let Some(result) = cache.get(&key) {
+                return Ok(Some(result));
+            }
+        }
+
+        let result = self.send_single(pipe).await?.result;
+        if let Some(result) = &result {
+            cache.put(key, result.clone());
+        }
+        Ok(result)
+    }
+
+    /// cache_key scopes key of shared caches to API key and headers set with
+    /// call options, so clients of different projects or tenants routed with
+    /// headers never see results of each other.
+    fn cache_key(&self, key: String) -> String {
+        let mut headers: Vec<_> = self
+            .call_options
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.as_str()))
+            .collect();
+        if self.call_options.api_key.is_none() && headers.is_empty() {
+            return key;
+        }
+        headers.sort();
+        // Debug format escapes quotes, so different scopes never collide.
+        format!("{:?}:{key}", (&self.call_options.api_key, headers))
+    }
+
+    /// send_single sends pipe with a single command and returns its reply,
+    /// reply error is returned as protocol::Error.
+    async fn send_single(&self, pipe: &Pipe) -> Result<Reply, ErrRes> {
+        let mut result = match self.send_pipe(pipe).await {
             Ok(response) => response,
             Err(err) => return Err(err),
         };
@@ -409,123 +1866,1140 @@ impl Client {
             return Err("No reply from server".into());
         }
 
-        let resp = &result[0];
-        if let Some(err) = &resp.error {
-            return Err(Box::new(err.clone()));
+        let resp = result.remove(0);
+        if let Some(err) = resp.error {
+            return Err(Box::new(err));
         }
 
-        decode_info(&serde_json::to_vec(&resp.result).unwrap())
+        Ok(resp)
     }
 
     pub async fn send_pipe(&self, pipe: &Pipe) -> Result<Vec<Reply>, Box<dyn Error + Send + Sync>> {
-        let mut commands = pipe.commands.lock().map_err(|_| "Lock poisoned")?;
+        self.send_pipe_with_options(pipe, &CallOptions::default())
+            .await
+    }
+
+    /// send_pipe_checked is the same as send_pipe but fails with PipeCommandError
+    /// of the first command server replied with error to.
+    pub async fn send_pipe_checked(&self, pipe: &Pipe) -> Result<Vec<Reply>, ErrRes> {
+        let replies = self.send_pipe(pipe).await?;
+        if let Some(err) = pipe.errors(&replies).into_iter().next() {
+            return Err(Box::new(err));
+        }
+        Ok(replies)
+    }
+
+    /// retry_failed sends again commands of pipe which got replies with retryable
+    /// error codes, see RetryPolicy::is_retryable_code, and returns replies with
+    /// results of resent commands merged in order of commands. With retry_policy
+    /// set commands still failing are resent until policy allows, policy retrying
+    /// until success is capped with MAX_RETRY_FAILED_ATTEMPTS, otherwise they're
+    /// resent once. Every resend waits for backoff. Replies must be the ones
+    /// received for pipe.
+    pub async fn retry_failed(
+        &self,
+        pipe: &Pipe,
+        replies: Vec<Reply>,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        self.retry_failed_with_options(pipe, replies, &CallOptions::default())
+            .await
+    }
+
+    /// retry_failed_with_options is the same as retry_failed but applies
+    /// call_opts to resent requests, e.g. the ones pipe was sent with.
+    pub async fn retry_failed_with_options(
+        &self,
+        pipe: &Pipe,
+        replies: Vec<Reply>,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        let (commands, _) = pipe.snapshot();
+        if replies.len() != commands.len() {
+            return Err(Box::new(ErrMalformedResponse {}));
+        }
+
+        let default_policy = RetryPolicy {
+            max_attempts: 2,
+            ..Default::default()
+        };
+        let retry_policy = self.retry_policy.as_ref().unwrap_or(&default_policy);
+        let max_attempts = match retry_policy.max_attempts {
+            0 => MAX_RETRY_FAILED_ATTEMPTS,
+            max_attempts => max_attempts,
+        };
+        let mut replies = replies;
+        let mut attempt = 1;
+        loop {
+            let failed: Vec<usize> = replies
+                .iter()
+                .enumerate()
+                .filter(|(_, reply)| {
+                    reply
+                        .error
+                        .as_ref()
+                        .is_some_and(|err| retry_policy.is_retryable_code(err.code))
+                })
+                .map(|(index, _)| index)
+                .collect();
+            if failed.is_empty() || attempt >= max_attempts {
+                return Ok(replies);
+            }
+            tokio::time::sleep(retry_policy.backoff(attempt)).await;
+            attempt += 1;
+
+            let resent: Vec<Command> = failed
+                .iter()
+                .map(|&index| commands[index].clone())
+                .collect();
+            let result = self.send_with_options(&resent, None, call_opts).await?;
+            if result.len() != resent.len() {
+                return Err(Box::new(ErrMalformedResponse {}));
+            }
+            for (index, reply) in failed.into_iter().zip(result) {
+                replies[index] = reply;
+            }
+        }
+    }
+
+    /// send_pipe_typed sends pipe and returns, per command, either result decoded
+    /// according to command method or error returned by server.
+    pub async fn send_pipe_typed(
+        &self,
+        pipe: &Pipe,
+    ) -> Result<Vec<Result<TypedResult, ErrRes>>, ErrRes> {
+        let (commands, encoded) = pipe.snapshot();
+        if commands.is_empty() {
+            return Err(Box::new(ErrPipeEmpty {}));
+        }
+
+        let result = self
+            .send_with_options(&commands, encoded, &CallOptions::default())
+            .await?;
+        if result.len() != commands.len() {
+            return Err(Box::new(ErrMalformedResponse {}));
+        }
+
+        Ok(commands
+            .iter()
+            .zip(result)
+            .map(|(cmd, resp)| match resp.into_result() {
+                Ok(result) => decode_typed(&cmd.method, result),
+                Err(err) => Err(Box::new(err) as ErrRes),
+            })
+            .collect())
+    }
+
+    /// send_pipe_with_options sends pipe applying call_opts to this request only.
+    pub async fn send_pipe_with_options(
+        &self,
+        pipe: &Pipe,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        let (commands, encoded) = pipe.snapshot();
         if commands.is_empty() {
             return Err(Box::new(ErrPipeEmpty {}));
         }
 
-        let response = self.send(commands.deref_mut().to_vec()).await;
+        let num_commands = commands.len();
+        let response = self.send_with_options(&commands, encoded, call_opts).await;
 
         let result: Vec<Reply> = match response {
             Ok(response) => response,
             Err(err) => return Err(err),
         };
 
-        if result.len() != commands.len() {
+        if result.len() != num_commands {
             return Err(Box::new(ErrMalformedResponse {}));
         }
 
         Ok(result)
     }
 
+    /// send_pipe_owned sends pipe moving commands out of it instead of cloning them,
+    /// so pipe is empty and can be reused after successful send. On error commands
+    /// are put back into pipe.
+    pub async fn send_pipe_owned(&self, pipe: &Pipe) -> Result<Vec<Reply>, ErrRes> {
+        let commands = pipe.take_commands().map_err(|err| err.to_string())?;
+        if commands.is_empty() {
+            return Err(Box::new(ErrPipeEmpty {}));
+        }
+
+        let result = match self.send(&commands).await {
+            Ok(result) if result.len() == commands.len() => return Ok(result),
+            Ok(_) => Box::new(ErrMalformedResponse {}),
+            Err(err) => err,
+        };
+
+        pipe.update(|pending| {
+            pending.splice(0..0, commands);
+        });
+        Err(result)
+    }
+
+    /// send sends commands in a single request, serializing them straight into
+    /// request body without copying.
     pub async fn send(
         &self,
-        commands: Vec<Command>,
+        commands: &[Command],
     ) -> Result<Vec<Reply>, Box<dyn Error + Sync + Send>> {
-        // Serialize commands to json string
+        self.send_with_options(commands, None, &CallOptions::default())
+            .await
+    }
+
+    /// call sends command of any method, e.g. PRO-only or experimental one, and
+    /// returns its result, see Pipe::add_custom.
+    pub async fn call(
+        &self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<serde_json::Value, ErrRes> {
+        let pipe = self.pipe();
+        pipe.add_custom(method, params)?;
+        let resp = self.send_single(&pipe).await?;
+        Ok(resp.result.unwrap_or_default())
+    }
+
+    /// send_raw sends body of newline delimited JSON commands as is, e.g. to call
+    /// server API methods client has no typed support for yet. Retries, hooks and
+    /// dead letter handler get commands with untyped params, command middlewares,
+    /// data validators and max_publication_size aren't applied.
+    pub async fn send_raw(&self, body: String) -> Result<Vec<Reply>, ErrRes> {
+        let _guard = self.lifecycle.request()?;
+
+        let mut commands = Vec::new();
+        for line in body.lines().filter(|line| !line.trim().is_empty()) {
+            let raw: RawCommand = serde_json::from_str(line)?;
+            commands.push(Command {
+                method: Method::from(raw.method),
+                params: RequestKind::Value(raw.params),
+            });
+        }
+        if commands.is_empty() {
+            return Err(Box::new(ErrPipeEmpty {}));
+        }
+
+        let result = self
+            .send_body(&commands, Bytes::from(body), &CallOptions::default())
+            .await?;
+        if result.len() != commands.len() {
+            return Err(Box::new(ErrMalformedResponse {}));
+        }
+        Ok(result)
+    }
+
+    /// send_with_options sends commands applying command middlewares, encoded
+    /// when set is commands already serialized, it's not used when middlewares
+    /// may change commands.
+    async fn send_with_options(
+        &self,
+        commands: &[Command],
+        encoded: Option<Bytes>,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        let _guard = self.lifecycle.request()?;
+
+        if self.command_middlewares.is_empty() {
+            return self.send_commands(commands, encoded, call_opts).await;
+        }
+
+        // indices keep positions of commands left after filtering in request,
+        // so replies can be put back in order of commands.
+        let mut sent = commands.to_vec();
+        let mut indices: Vec<usize> = (0..commands.len()).collect();
+        for middleware in &self.command_middlewares {
+            middleware.on_commands(&mut sent)?;
+            (sent, indices) = sent
+                .into_iter()
+                .zip(indices)
+                .filter(|(cmd, _)| middleware.keep(cmd))
+                .unzip();
+        }
+        if sent.len() == commands.len() {
+            return self.send_commands(&sent, None, call_opts).await;
+        }
+
+        let replies = if sent.is_empty() {
+            Vec::new()
+        } else {
+            self.send_commands(&sent, None, call_opts).await?
+        };
+        if replies.len() != sent.len() {
+            return Err(Box::new(ErrMalformedResponse {}));
+        }
+        let mut result: Vec<Reply> = (0..commands.len())
+            .map(|_| Reply {
+                error: Some(ProtocolError {
+                    code: COMMAND_FILTERED_CODE,
+                    message: "command filtered out by middleware".to_string(),
+                }),
+                result: None,
+            })
+            .collect();
+        for (index, reply) in indices.into_iter().zip(replies) {
+            result[index] = reply;
+        }
+        Ok(result)
+    }
+
+    /// send_commands checks and serializes commands unless they're encoded
+    /// already and sends them.
+    async fn send_commands(
+        &self,
+        commands: &[Command],
+        encoded: Option<Bytes>,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        self.check_publication_size(commands)?;
+        self.validate_data(commands)?;
+
+        if let Some(body) = encoded {
+            return self.send_body(commands, body, call_opts).await;
+        }
+        // Serialize commands as newline delimited json into a single buffer.
+        let mut body = Vec::new();
+        for (i, cmd) in commands.iter().enumerate() {
+            if i > 0 {
+                body.push(b'\n');
+            }
+            serde_json::to_writer(&mut body, cmd)?;
+        }
+        self.send_body(commands, Bytes::from(body), call_opts).await
+    }
+
+    /// send_body sends serialized commands, retrying and notifying hooks and
+    /// dead letter handler about commands according to client config.
+    async fn send_body(
+        &self,
+        commands: &[Command],
+        body: Bytes,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        if let Some(limit) = self.max_request_size {
+            if body.len() > limit {
+                return Err(Box::new(ErrPayloadTooLarge {
+                    size: body.len(),
+                    limit,
+                    method: None,
+                }));
+            }
+        }
+
+        self.prepare_endpoints().await?;
+        let mut endpoint = self.resolve_endpoint()?;
+
+        let mut attempt = 1;
+        let mut token_refreshed = false;
+        let mut key_reloaded = false;
+        loop {
+            let token = match &self.token_source {
+                Some(token_source) => Some(token_source.token().await?),
+                None => None,
+            };
+            let started = Instant::now();
+            let result = self
+                .send_attempt(
+                    commands,
+                    &endpoint,
+                    body.clone(),
+                    attempt,
+                    token.as_deref(),
+                    call_opts,
+                )
+                .await;
+            if let Some(endpoint_selector) = &self.endpoint_selector {
+                let failed = result.as_ref().is_err_and(|err| is_retryable(err.as_ref()));
+                endpoint_selector.report(&endpoint, started.elapsed(), failed);
+            }
+            let err = match result {
+                Ok(replies) => return Ok(replies),
+                Err(err) => err,
+            };
+
+            if let (Some(token_source), Some(token)) = (&self.token_source, &token) {
+                let unauthorized = err
+                    .downcast_ref::<ErrStatusCode>()
+                    .is_some_and(|err| err.code == 401);
+                if unauthorized && !token_refreshed {
+                    token_source.invalidate(token).await;
+                    token_refreshed = true;
+                    continue;
+                }
+            }
+
+            if let Some(key_file) = &self.key_file {
+                let unauthorized = err
+                    .downcast_ref::<ErrStatusCode>()
+                    .is_some_and(|err| err.code == 401);
+                let overridden = call_opts.api_key.is_some() || self.call_options.api_key.is_some();
+                if unauthorized && !overridden && !key_reloaded {
+                    key_reloaded = true;
+                    if key_file.reload() {
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(retry_policy) = &self.retry_policy {
+                if retry_policy.is_retryable(err.as_ref()) && retry_policy.can_retry(attempt) {
+                    let delay = retry_policy.backoff(attempt);
+                    self.notify_retry(commands, attempt, delay, &endpoint, err.as_ref());
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    // retry may go to another endpoint if this one got demoted.
+                    if self.endpoint_selector.is_some() {
+                        endpoint = self.resolve_endpoint()?;
+                    }
+                    continue;
+                }
+            }
+
+            if let Some(dead_letter) = &self.dead_letter {
+                for cmd in commands {
+                    dead_letter(cmd, err.as_ref());
+                }
+            }
+            return Err(err);
+        }
+    }
 
-        let mut lines = Vec::with_capacity(commands.len());
+    /// start_endpoint_refresher spawns background task refreshing endpoints,
+    /// see Config.endpoint_refresh_interval. None is returned when refresh
+    /// interval isn't configured or task was already started.
+    pub fn start_endpoint_refresher(&self) -> Option<tokio::task::JoinHandle<()>> {
+        self.endpoint_refresher
+            .as_ref()
+            .and_then(|refresher| refresher.start(&self.lifecycle))
+    }
 
-        for cmd in &commands {
-            lines.push(serde_json::to_string(cmd)?);
+    /// prepare_endpoints makes sure discovered endpoints are resolved before
+    /// resolve_endpoint, in the request path unless refresher is configured.
+    async fn prepare_endpoints(&self) -> Result<(), ErrRes> {
+        self.start_endpoint_refresher();
+        if let Some(discovery) = &self.discovery {
+            discovery
+                .ensure_fresh(self.endpoint_refresher.is_none())
+                .await?;
         }
+        Ok(())
+    }
 
-        let lines = lines.join("\n");
+    /// resolve_endpoint returns endpoint to send requests to.
+    fn resolve_endpoint(&self) -> Result<String, ErrRes> {
+        let resolved = match &self.endpoint_cache {
+            Some(endpoint_cache) => Some(endpoint_cache.get()),
+            None => self
+                .get_endpoint
+                .as_ref()
+                .map(|get_endpoint| get_endpoint()),
+        };
+        if let Some(resolved) = resolved {
+            return match (resolved, &self.endpoint) {
+                (Ok(endpoint), _) => Ok(endpoint),
+                (Err(err), Some(fallback)) if self.get_addr_fallback => {
+                    self.notify_fallback(fallback, err.as_ref());
+                    Ok(fallback.clone())
+                }
+                (Err(err), _) => Err(err),
+            };
+        }
 
-        let endpoint = if let Some(get_endpoint) = &self.get_endpoint {
-            get_endpoint()?
+        let endpoint = if let Some(endpoint) = self
+            .endpoint_selector
+            .as_ref()
+            .and_then(|endpoint_selector| endpoint_selector.select())
+        {
+            endpoint
         } else {
-            self.endpoint.clone().unwrap()
+            self.endpoint.clone().ok_or("no API endpoint available")?
+        };
+        Ok(endpoint)
+    }
+
+    /// warmup resolves endpoint and establishes given number of connections to it
+    /// (including DNS lookup and TLS handshake), so they're kept in pool and the
+    /// first API calls don't pay connection setup cost.
+    pub async fn warmup(&self, connections: usize) -> Result<(), ErrRes> {
+        let _guard = self.lifecycle.request()?;
+        self.prepare_endpoints().await?;
+        let endpoint = self.resolve_endpoint()?;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..connections.max(1) {
+            let request_id = crate::id::uuid_v4();
+            let request = self
+                .http_client
+                .head(&endpoint)
+                .header("User-Agent", &self.user_agent)
+                .header(REQUEST_ID_HEADER, &request_id);
+            let in_flight = self.in_flight.clone();
+            let transport = self.transport.clone();
+            // any response means connection is established, status does not matter.
+            tasks.spawn(async move {
+                let _permit = match &in_flight {
+                    Some(in_flight) => in_flight.acquire().await.ok(),
+                    None => None,
+                };
+                let _in_flight = transport.start();
+                let response = request.send().await.map_err(|err| (request_id, err))?;
+                transport.record(response.url(), &response);
+                Ok(())
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            result?.map_err(|(request_id, err)| -> ErrRes {
+                Box::new(ErrRequestFailed::new(
+                    Vec::new(),
+                    endpoint.clone(),
+                    1,
+                    request_id,
+                    err.into(),
+                ))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// check_publication_size validates data of publish and broadcast commands
+    /// against max_publication_size.
+    fn check_publication_size(&self, commands: &[Command]) -> Result<(), ErrRes> {
+        let limit = match self.max_publication_size {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        for cmd in commands {
+            let data = match &cmd.params {
+                RequestKind::PublishRequest(request) => &request.data,
+                RequestKind::BroadcastRequest(request) => &request.data,
+                _ => continue,
+            };
+            let size = serde_json::to_vec(data)?.len();
+            if size > limit {
+                return Err(Box::new(ErrPayloadTooLarge {
+                    size,
+                    limit,
+                    method: Some(cmd.method.to_string()),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// validate_data checks channels of commands with namespaces and data of
+    /// publish and broadcast commands with data_validators.
+    fn validate_data(&self, commands: &[Command]) -> Result<(), ErrRes> {
+        if let Some(namespaces) = &self.namespaces {
+            for cmd in commands {
+                match &cmd.params {
+                    RequestKind::PublishRequest(request) => {
+                        namespaces.lookup(&request.channel)?;
+                    }
+                    RequestKind::BroadcastRequest(request) => {
+                        for channel in &request.channels {
+                            namespaces.lookup(channel)?;
+                        }
+                    }
+                    RequestKind::PresenceRequest(request) => {
+                        namespaces.check_presence(&request.channel)?
+                    }
+                    RequestKind::PresenceStatsRequest(request) => {
+                        namespaces.check_presence(&request.channel)?
+                    }
+                    RequestKind::HistoryRequest(request) => {
+                        namespaces.check_history(&request.channel)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let data_validators = match &self.data_validators {
+            Some(data_validators) => data_validators,
+            None => return Ok(()),
+        };
+        for cmd in commands {
+            match &cmd.params {
+                RequestKind::PublishRequest(request) => {
+                    data_validators.validate(&request.channel, &request.data)?
+                }
+                RequestKind::BroadcastRequest(request) => {
+                    for channel in &request.channels {
+                        data_validators.validate(channel, &request.data)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// send_attempt performs a single HTTP request notifying hooks about it.
+    async fn send_attempt(
+        &self,
+        commands: &[Command],
+        endpoint: &str,
+        body: Bytes,
+        attempt: u32,
+        token: Option<&str>,
+        call_opts: &CallOptions,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        let request_id = self
+            .call_options
+            .headers
+            .iter()
+            .chain(call_opts.headers.iter())
+            .find(|(name, _)| name.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(crate::id::uuid_v4);
+        if self.hooks.is_empty() {
+            return self
+                .execute_attempt(
+                    commands, endpoint, body, attempt, token, call_opts, request_id,
+                )
+                .await;
+        }
+
+        let methods: Vec<&str> = commands.iter().map(|cmd| cmd.method.as_str()).collect();
+        let request = RequestEvent {
+            methods: &methods,
+            endpoint,
+            attempt,
+            request_id: &request_id,
+        };
+        for hook in &self.hooks {
+            hook.on_request(&request);
+        }
+
+        let started = Instant::now();
+        let result = self
+            .execute_attempt(
+                commands,
+                endpoint,
+                body,
+                attempt,
+                token,
+                call_opts,
+                request_id.clone(),
+            )
+            .await;
+        let duration = started.elapsed();
+        match &result {
+            Ok(replies) => {
+                let response = ResponseEvent {
+                    request: &request,
+                    duration,
+                    errors: replies.iter().filter(|reply| reply.error.is_some()).count(),
+                };
+                for hook in &self.hooks {
+                    hook.on_response(&response);
+                }
+            }
+            Err(err) => {
+                let error = ErrorEvent {
+                    request: &request,
+                    duration,
+                    error: err.as_ref(),
+                };
+                for hook in &self.hooks {
+                    hook.on_error(&error);
+                }
+            }
+        }
+        result
+    }
+
+    /// notify_fallback logs get_addr failure and notifies hooks about request
+    /// sent to static addr instead.
+    fn notify_fallback(&self, endpoint: &str, error: &(dyn Error + Send + Sync + 'static)) {
+        log::warn!("get_addr failed, falling back to {}: {}", endpoint, error);
+        let event = FallbackEvent { endpoint, error };
+        for hook in &self.hooks {
+            hook.on_endpoint_fallback(&event);
+        }
+    }
+
+    /// notify_connection notifies hooks about connection response came over
+    /// when it's new or is going to be closed.
+    fn notify_connection(&self, endpoint: &str, response: &reqwest::Response, opened: bool) {
+        let closing = response
+            .headers()
+            .get(reqwest::header::CONNECTION)
+            .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"close"));
+        if !opened && !closing {
+            return;
+        }
+        let Some(info) = response.extensions().get::<HttpInfo>() else {
+            return;
+        };
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        let peer_certificate = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|tls| tls.peer_certificate());
+        #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+        let peer_certificate = None;
+
+        let event = ConnectionEvent {
+            endpoint,
+            local_addr: info.local_addr(),
+            remote_addr: info.remote_addr(),
+            peer_certificate,
+        };
+        for hook in &self.hooks {
+            if opened {
+                hook.on_connect(&event);
+            }
+            if closing {
+                hook.on_disconnect(&event);
+            }
+        }
+    }
+
+    /// notify_retry logs retry and notifies hooks about it.
+    fn notify_retry(
+        &self,
+        commands: &[Command],
+        attempt: u32,
+        delay: Duration,
+        endpoint: &str,
+        error: &(dyn Error + Send + Sync + 'static),
+    ) {
+        let methods: Vec<&str> = commands.iter().map(|cmd| cmd.method.as_str()).collect();
+        log::warn!(
+            "retrying {} to {} after attempt {} in {:?}: {}",
+            methods.join(","),
+            endpoint,
+            attempt,
+            delay,
+            error
+        );
+        let event = RetryEvent {
+            methods: &methods,
+            attempt,
+            delay,
+            endpoint,
+            error,
+        };
+        for hook in &self.hooks {
+            hook.on_retry(&event);
+        }
+    }
+
+    /// execute_attempt performs a single HTTP request, transport and decode
+    /// failures are wrapped into ErrRequestFailed carrying methods, endpoint and
+    /// attempt.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_attempt(
+        &self,
+        commands: &[Command],
+        endpoint: &str,
+        body: Bytes,
+        attempt: u32,
+        token: Option<&str>,
+        call_opts: &CallOptions,
+        request_id: String,
+    ) -> Result<Vec<Reply>, ErrRes> {
+        let headers = || {
+            self.call_options
+                .headers
+                .iter()
+                .chain(call_opts.headers.iter())
+        };
+        let propagated_id = headers()
+            .find(|(name, _)| name.eq_ignore_ascii_case(REQUEST_ID_HEADER))
+            .map(|(_, value)| value.clone());
+
+        let with_context = |source: ErrRes| -> ErrRes {
+            Box::new(ErrRequestFailed::new(
+                commands.iter().map(|cmd| cmd.method.to_string()).collect(),
+                endpoint.to_string(),
+                attempt,
+                request_id.clone(),
+                source,
+            ))
         };
 
         // Create the HTTP request
         let request_builder = self
             .http_client
-            .post(&endpoint)
-            .header("Content-Type", "application/json");
+            .post(endpoint)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", &self.user_agent);
+
+        // propagated ID is sent with the rest of call headers below.
+        let request_builder = if propagated_id.is_none() {
+            request_builder.header(REQUEST_ID_HEADER, &request_id)
+        } else {
+            request_builder
+        };
+
+        let request_builder = if let Some(client_name) = &self.client_name {
+            request_builder.header("X-Client-Name", client_name)
+        } else {
+            request_builder
+        };
+
+        let file_key = match (&call_opts.api_key, &self.call_options.api_key) {
+            (None, None) => self.key_file.as_ref().and_then(|key_file| key_file.key()),
+            _ => None,
+        };
+        let auth_header = call_opts
+            .api_key
+            .as_ref()
+            .or(self.call_options.api_key.as_ref())
+            .or(file_key.as_ref())
+            .or(self.api_key.as_ref())
+            .and_then(|api_key| self.auth_scheme.header(api_key))
+            // basic and bearer auth take Authorization header, two of them
+            // would be rejected by server.
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case("authorization")
+                    || (self.basic_auth.is_none() && token.is_none())
+            });
+        let auth_header_name = auth_header.as_ref().map(|(name, _)| name.clone());
+        let request_builder = if let Some((name, value)) = auth_header {
+            request_builder.header(name, value)
+        } else {
+            request_builder
+        };
+
+        let request_builder = if let Some(basic_auth) = &self.basic_auth {
+            request_builder.basic_auth(&basic_auth.username, basic_auth.password.as_ref())
+        } else {
+            request_builder
+        };
 
-        let request_builder = if let Some(api_key) = &self.api_key {
-            request_builder.header("Authorization", format!("apikey {}", api_key))
+        let request_builder = if let Some(token) = token {
+            request_builder.bearer_auth(token)
         } else {
             request_builder
         };
 
+        let request_builder = headers().fold(request_builder, |request_builder, (name, value)| {
+            request_builder.header(name, value)
+        });
+
+        let mut request = request_builder
+            .body(body)
+            .build()
+            .map_err(|err| with_context(err.into()))?;
+        for middleware in &self.middlewares {
+            middleware.on_request(&mut request)?;
+        }
+        let body_size = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or(0, <[u8]>::len);
+
+        // request is recorded after middlewares, exactly as it's sent.
+        let recorded = self.recorder.as_ref().map(|_| Exchange {
+            endpoint: endpoint.to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let is_auth = auth_header_name
+                        .as_deref()
+                        .is_some_and(|auth| name.as_str().eq_ignore_ascii_case(auth));
+                    let value = if is_auth {
+                        REDACTED.to_string()
+                    } else {
+                        String::from_utf8_lossy(value.as_bytes()).into_owned()
+                    };
+                    (name.to_string(), value)
+                })
+                .collect(),
+            request: String::from_utf8_lossy(
+                request
+                    .body()
+                    .and_then(|body| body.as_bytes())
+                    .unwrap_or_default(),
+            )
+            .into_owned(),
+            status: 0,
+            response: String::new(),
+        });
+
         // Send request
+        log::debug!(
+            "sending request {} to {}, attempt {}",
+            request_id,
+            endpoint,
+            attempt
+        );
+
+        let _permit = match &self.in_flight {
+            Some(in_flight) => Some(in_flight.acquire().await?),
+            None => None,
+        };
+        self.stats.record_request(commands, body_size);
+        let in_flight = self.transport.start();
+        let response = self.execute(request).await.map_err(with_context)?;
+        drop(in_flight);
+        let opened = self.transport.record(response.url(), &response);
+        if !self.hooks.is_empty() {
+            self.notify_connection(endpoint, &response, opened);
+        }
+        let status = response.status();
+        let bytes = self
+            .read_body(response, &request_id)
+            .await
+            .map_err(|err| match err.downcast::<reqwest::Error>() {
+                Ok(err) => with_context(err),
+                Err(err) => err,
+            })?;
+        self.stats.record_response(bytes.len());
+
+        if let (Some(recorder), Some(mut exchange)) = (&self.recorder, recorded) {
+            exchange.status = status.as_u16();
+            exchange.response = String::from_utf8_lossy(&bytes).into_owned();
+            recorder.record(exchange);
+        }
 
-        let response = request_builder.body(lines).send().await?;
         // Handle non-200 status code
-        if !response.status().is_success() {
-            let status = response.status();
-            let resp_body = response.text().await?;
-            return Err(Box::new(ErrStatusCode {
-                code: status.as_u16(),
-                body: resp_body,
-            }));
+        if !status.is_success() {
+            return Err(Box::new(ErrStatusCode::new(
+                status.as_u16(),
+                &bytes,
+                Some(request_id),
+            )));
         }
 
-        // Deserialize replies from the response body.
-        let bytes = response.bytes().await?;
+        let replies = decode_replies(&bytes, self.strict_decode).map_err(with_context)?;
+        if replies.len() != commands.len() {
+            return Err(with_context(Box::new(ErrMalformedResponse {})));
+        }
+        self.stats.record_replies(&replies);
+        Ok(replies)
+    }
 
-        // Split the JSON by newline and deserialize to Reply structs
-        let replies = String::from_utf8(bytes.to_vec())?
-            .lines()
-            .map(|line| serde_json::from_str::<Reply>(line))
-            .collect::<Result<Vec<Reply>, _>>()?;
+    /// execute sends request with http client or through service when
+    /// Config.http_layer is set.
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, ErrRes> {
+        #[cfg(feature = "tower")]
+        if let Some(service) = &self.service {
+            return service.execute(request).await;
+        }
+        Ok(self.http_client.execute(request).await?)
+    }
 
-        Ok(replies)
+    /// read_body reads response body respecting max_response_size.
+    async fn read_body(
+        &self,
+        mut response: reqwest::Response,
+        request_id: &str,
+    ) -> Result<Vec<u8>, ErrRes> {
+        let limit = match self.max_response_size {
+            Some(limit) => limit,
+            None => return Ok(response.bytes().await?.to_vec()),
+        };
+
+        if let Some(size) = response.content_length() {
+            if size as usize > limit {
+                return Err(Box::new(ErrResponseTooLarge {
+                    limit,
+                    size: size as usize,
+                    request_id: request_id.to_string(),
+                }));
+            }
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(Box::new(ErrResponseTooLarge {
+                    limit,
+                    size: body.len() + chunk.len(),
+                    request_id: request_id.to_string(),
+                }));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body)
     }
 }
 
-pub fn decode_publish(result: &[u8]) -> Result<PublishResult, Box<dyn Error>> {
+/// decode_replies decodes newline delimited replies. Unless strict, UTF-8 BOM
+/// and blank lines some proxies introduce are skipped. Both modes accept CRLF
+/// line endings and trailing newline.
+pub fn decode_replies(body: &[u8], strict: bool) -> Result<Vec<Reply>, ErrRes> {
+    let body = std::str::from_utf8(body)?;
+    if strict {
+        return Ok(body
+            .lines()
+            .map(serde_json::from_str::<Reply>)
+            .collect::<Result<Vec<Reply>, _>>()?);
+    }
+
+    Ok(body
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str::<Reply>)
+        .collect::<Result<Vec<Reply>, _>>()?)
+}
+
+pub fn decode_publish(result: &[u8]) -> Result<PublishResult, ErrRes> {
     let r: PublishResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_broadcast(result: &[u8]) -> Result<BroadcastResult, Box<dyn Error>> {
+/// set_idempotency_key fills idempotency key of every publication of broadcast.
+fn set_idempotency_key(result: &mut BroadcastResult, idempotency_key: Option<String>) {
+    for response in &mut result.responses {
+        response.result.idempotency_key = idempotency_key.clone();
+    }
+}
+
+/// RawCommand is a command of body passed to send_raw, only method is needed
+/// to report command to hooks.
+#[derive(Deserialize)]
+struct RawCommand {
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+/// unique_keys collects keys of batched commands dropping duplicates, order of
+/// first occurrence is kept.
+fn unique_keys<T: Into<String>>(keys: impl IntoIterator<Item = T>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    keys.into_iter()
+        .map(Into::into)
+        .filter(|key| seen.insert(key.clone()))
+        .collect()
+}
+
+/// keyed_results pairs replies with keys commands of method were added for,
+/// reply with error becomes PipeCommandError.
+fn keyed_results<R>(
+    keys: Vec<String>,
+    replies: Vec<Reply>,
+    method: Method,
+    decode: fn(&[u8]) -> Result<R, ErrRes>,
+) -> HashMap<String, Result<R, ErrRes>> {
+    keys.into_iter()
+        .zip(replies)
+        .enumerate()
+        .map(|(index, (key, resp))| {
+            let result = match resp.error {
+                Some(error) => Err(Box::new(PipeCommandError {
+                    index,
+                    method: method.clone(),
+                    error,
+                }) as ErrRes),
+                None => serde_json::to_vec(&resp.result)
+                    .map_err(ErrRes::from)
+                    .and_then(|result| decode(&result)),
+            };
+            (key, result)
+        })
+        .collect()
+}
+
+pub fn decode_broadcast(result: &[u8]) -> Result<BroadcastResult, ErrRes> {
     let r: BroadcastResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_history(result: &[u8]) -> Result<HistoryResult, Box<dyn Error>> {
+pub fn decode_history(result: &[u8]) -> Result<HistoryResult, ErrRes> {
     let r: HistoryResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_channels(result: &[u8]) -> Result<ChannelsResult, Box<dyn Error>> {
+pub fn decode_channels(result: &[u8]) -> Result<ChannelsResult, ErrRes> {
     let r: ChannelsResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_info(result: &[u8]) -> Result<InfoResult, Box<dyn Error>> {
+pub fn decode_info(result: &[u8]) -> Result<InfoResult, ErrRes> {
     let r: InfoResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_presence(result: &[u8]) -> Result<PresenceResult, Box<dyn Error>> {
+pub fn decode_presence(result: &[u8]) -> Result<PresenceResult, ErrRes> {
     let r: PresenceResult = serde_json::from_slice(result)?;
     Ok(r)
 }
 
-pub fn decode_presence_stats(result: &[u8]) -> Result<PresenceStatsResult, Box<dyn Error>> {
+pub fn decode_presence_stats(result: &[u8]) -> Result<PresenceStatsResult, ErrRes> {
     let r: PresenceStatsResult = serde_json::from_slice(result)?;
     Ok(r)
 }
+
+pub fn decode_connections(result: &[u8]) -> Result<ConnectionsResult, ErrRes> {
+    let r: ConnectionsResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_get_user_status(result: &[u8]) -> Result<GetUserStatusResult, ErrRes> {
+    let r: GetUserStatusResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_device_register(result: &[u8]) -> Result<DeviceRegisterResult, ErrRes> {
+    let r: DeviceRegisterResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_device_list(result: &[u8]) -> Result<DeviceListResult, ErrRes> {
+    let r: DeviceListResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_device_topic_list(result: &[u8]) -> Result<DeviceTopicListResult, ErrRes> {
+    let r: DeviceTopicListResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_user_topic_list(result: &[u8]) -> Result<UserTopicListResult, ErrRes> {
+    let r: UserTopicListResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_send_push_notification(result: &[u8]) -> Result<SendPushNotificationResult, ErrRes> {
+    let r: SendPushNotificationResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+pub fn decode_rate_limit(result: &[u8]) -> Result<RateLimitResult, ErrRes> {
+    let r: RateLimitResult = serde_json::from_slice(result)?;
+    Ok(r)
+}
+
+/// decode_typed decodes result of command according to its method.
+pub fn decode_typed(method: &Method, result: serde_json::Value) -> Result<TypedResult, ErrRes> {
+    let result = match method {
+        Method::Publish => TypedResult::Publish(serde_json::from_value(result)?),
+        Method::Broadcast => TypedResult::Broadcast(serde_json::from_value(result)?),
+        Method::Presence => TypedResult::Presence(serde_json::from_value(result)?),
+        Method::PresenceStats => TypedResult::PresenceStats(serde_json::from_value(result)?),
+        Method::History => TypedResult::History(serde_json::from_value(result)?),
+        Method::Channels => TypedResult::Channels(serde_json::from_value(result)?),
+        Method::Info => TypedResult::Info(serde_json::from_value(result)?),
+        Method::Connections => TypedResult::Connections(serde_json::from_value(result)?),
+        Method::GetUserStatus => TypedResult::GetUserStatus(serde_json::from_value(result)?),
+        Method::DeviceRegister => TypedResult::DeviceRegister(serde_json::from_value(result)?),
+        Method::DeviceList => TypedResult::DeviceList(serde_json::from_value(result)?),
+        Method::DeviceTopicList => TypedResult::DeviceTopicList(serde_json::from_value(result)?),
+        Method::UserTopicList => TypedResult::UserTopicList(serde_json::from_value(result)?),
+        Method::SendPushNotification => {
+            TypedResult::SendPushNotification(serde_json::from_value(result)?)
+        }
+        Method::RateLimit => TypedResult::RateLimit(serde_json::from_value(result)?),
+        Method::Subscribe
+        | Method::Unsubscribe
+        | Method::Disconnect
+        | Method::Refresh
+        | Method::HistoryRemove
+        | Method::UpdateUserStatus
+        | Method::DeleteUserStatus
+        | Method::DeviceUpdate
+        | Method::DeviceRemove
+        | Method::DeviceTopicUpdate
+        | Method::UserTopicUpdate => TypedResult::Empty,
+        Method::Custom(_) => TypedResult::Raw(result),
+    };
+    Ok(result)
+}
@@ -0,0 +1,223 @@
+use crate::client::ErrRes;
+use crate::endpoint::EndpointSelector;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Endpoint is a Centrifugo API endpoint found by ServiceDiscovery.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    /// addr is API address, e.g. http://10.0.0.1:8000/api.
+    pub addr: String,
+}
+
+impl From<String> for Endpoint {
+    fn from(addr: String) -> Self {
+        Endpoint { addr }
+    }
+}
+
+impl From<&str> for Endpoint {
+    fn from(addr: &str) -> Self {
+        Endpoint {
+            addr: addr.to_string(),
+        }
+    }
+}
+
+/// ResolveFuture is a future returned by ServiceDiscovery::resolve.
+pub type ResolveFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<Endpoint>, ErrRes>> + Send + 'a>>;
+
+/// ServiceDiscovery finds API endpoints, e.g. in Consul, etcd or Kubernetes.
+/// Client sends requests to the fastest healthy of resolved endpoints, see
+/// Config.discovery.
+pub trait ServiceDiscovery: Send + Sync {
+    /// resolve returns current endpoints.
+    fn resolve(&self) -> ResolveFuture<'_>;
+
+    /// changes returns receiver notified when endpoints may have changed, so
+    /// client resolves them again. Nil value means endpoints are resolved once.
+    fn changes(&self) -> Option<watch::Receiver<()>> {
+        None
+    }
+}
+
+/// StaticDiscovery returns a fixed list of endpoints which can be replaced
+/// with set, notifying client about change.
+pub struct StaticDiscovery {
+    endpoints: Mutex<Vec<Endpoint>>,
+    changes: watch::Sender<()>,
+}
+
+impl StaticDiscovery {
+    /// Create a new discovery returning addrs.
+    pub fn new<T: Into<Endpoint>>(addrs: impl IntoIterator<Item = T>) -> Self {
+        StaticDiscovery {
+            endpoints: Mutex::new(addrs.into_iter().map(Into::into).collect()),
+            changes: watch::channel(()).0,
+        }
+    }
+
+    /// set replaces endpoints.
+    pub fn set<T: Into<Endpoint>>(&self, addrs: impl IntoIterator<Item = T>) {
+        *self.endpoints.lock().unwrap_or_else(|e| e.into_inner()) =
+            addrs.into_iter().map(Into::into).collect();
+        self.changes.send_replace(());
+    }
+}
+
+impl ServiceDiscovery for StaticDiscovery {
+    fn resolve(&self) -> ResolveFuture<'_> {
+        let endpoints = self
+            .endpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        Box::pin(async move { Ok(endpoints) })
+    }
+
+    fn changes(&self) -> Option<watch::Receiver<()>> {
+        Some(self.changes.subscribe())
+    }
+}
+
+/// DnsDiscovery resolves host name into addresses and returns endpoint per
+/// address, e.g. for Kubernetes headless service. Endpoints contain IP
+/// addresses, so it's meant for plain HTTP inside cluster network.
+#[derive(Clone, Debug)]
+pub struct DnsDiscovery {
+    host: String,
+    port: u16,
+    scheme: String,
+    path: String,
+}
+
+impl DnsDiscovery {
+    /// Create a new discovery resolving host, endpoints use port and /api path.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        DnsDiscovery {
+            host: host.into(),
+            port,
+            scheme: "http".to_string(),
+            path: "/api".to_string(),
+        }
+    }
+
+    /// with_scheme allows to set scheme of endpoints, http by default.
+    pub fn with_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.scheme = scheme.into();
+        self
+    }
+
+    /// with_path allows to set path of endpoints, /api by default.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+}
+
+impl ServiceDiscovery for DnsDiscovery {
+    fn resolve(&self) -> ResolveFuture<'_> {
+        Box::pin(async move {
+            let mut seen = HashSet::new();
+            let endpoints = tokio::net::lookup_host((self.host.as_str(), self.port))
+                .await?
+                .filter(|addr| seen.insert(*addr))
+                .map(|addr| Endpoint {
+                    addr: format!("{}://{}{}", self.scheme, addr, self.path),
+                })
+                .collect();
+            Ok(endpoints)
+        })
+    }
+}
+
+/// DiscoveredEndpoints keeps endpoints found by discovery in selector used by
+/// client, endpoints are resolved before the first request and again after
+/// discovery notifies about change.
+pub struct DiscoveredEndpoints {
+    discovery: Arc<dyn ServiceDiscovery>,
+    selector: Arc<EndpointSelector>,
+    changes: Mutex<Option<watch::Receiver<()>>>,
+    resolved: AtomicBool,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl DiscoveredEndpoints {
+    /// Create a new set of endpoints probing demoted ones every probe_interval.
+    pub fn new(discovery: Arc<dyn ServiceDiscovery>, probe_interval: Duration) -> Arc<Self> {
+        let changes = discovery.changes();
+        Arc::new(DiscoveredEndpoints {
+            discovery,
+            selector: EndpointSelector::new(Vec::new(), probe_interval),
+            changes: Mutex::new(changes),
+            resolved: AtomicBool::new(false),
+            refreshing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// selector returns selector endpoints are kept in.
+    pub fn selector(&self) -> &Arc<EndpointSelector> {
+        &self.selector
+    }
+
+    /// refresh resolves endpoints and replaces them in selector, statistics of
+    /// endpoints which are still there are kept.
+    pub async fn refresh(&self) -> Result<(), ErrRes> {
+        if let Some(changes) = self
+            .changes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            changes.mark_unchanged();
+        }
+        let endpoints = self.discovery.resolve().await?;
+        self.selector.set_endpoints(
+            endpoints
+                .into_iter()
+                .map(|endpoint| endpoint.addr)
+                .collect(),
+        );
+        self.resolved.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// subscribe_changes returns a new receiver of discovery change notifications.
+    pub fn subscribe_changes(&self) -> Option<watch::Receiver<()>> {
+        self.discovery.changes()
+    }
+
+    /// ensure_fresh refreshes endpoints unless they were resolved and, when
+    /// track_changes is true, discovery didn't notify about change since then.
+    pub(crate) async fn ensure_fresh(&self, track_changes: bool) -> Result<(), ErrRes> {
+        if !self.is_stale(track_changes) {
+            return Ok(());
+        }
+        let _refreshing = self.refreshing.lock().await;
+        // concurrent caller could refresh endpoints while we waited.
+        if !self.is_stale(track_changes) {
+            return Ok(());
+        }
+        self.refresh().await
+    }
+
+    fn is_stale(&self, track_changes: bool) -> bool {
+        if !self.resolved.load(Ordering::Acquire) {
+            return true;
+        }
+        if !track_changes {
+            return false;
+        }
+        self.changes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|changes| changes.has_changed().unwrap_or(false))
+    }
+}
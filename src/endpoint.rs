@@ -0,0 +1,328 @@
+use crate::client::ErrRes;
+use crate::discovery::DiscoveredEndpoints;
+use crate::shutdown::Lifecycle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// GetAddr is a closure returning Centrifugo API endpoint, see Config.get_addr.
+pub type GetAddr = Arc<dyn Fn() -> Result<String, ErrRes> + Send + Sync>;
+
+/// EndpointCache caches result of get_addr for a configured TTL.
+///
+/// When cached value expires it's still returned to the caller while a fresh
+/// value is fetched in the background, so API calls never wait for get_addr
+/// except the very first one.
+pub struct EndpointCache {
+    get_addr: GetAddr,
+    ttl: Duration,
+    cached: Mutex<Option<(String, Instant)>>,
+    refreshing: AtomicBool,
+}
+
+impl EndpointCache {
+    /// Create a new cache around get_addr keeping values for ttl.
+    pub fn new(get_addr: GetAddr, ttl: Duration) -> Arc<Self> {
+        Arc::new(EndpointCache {
+            get_addr,
+            ttl,
+            cached: Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+        })
+    }
+
+    /// get returns cached endpoint, calling get_addr only when nothing is cached yet.
+    pub fn get(self: &Arc<Self>) -> Result<String, ErrRes> {
+        let cached = self
+            .cached
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        match cached {
+            Some((addr, fetched_at)) => {
+                if fetched_at.elapsed() >= self.ttl {
+                    self.refresh_in_background();
+                }
+                Ok(addr)
+            }
+            None => self.refresh(),
+        }
+    }
+
+    /// invalidate drops cached endpoint so the next get calls get_addr again.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// refresh calls get_addr and replaces cached endpoint with its result.
+    pub fn refresh(&self) -> Result<String, ErrRes> {
+        let addr = (self.get_addr)()?;
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((addr.clone(), Instant::now()));
+        Ok(addr)
+    }
+
+    fn refresh_in_background(self: &Arc<Self>) {
+        // only one refresh at a time, concurrent callers keep using stale value.
+        if self.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let cache = Arc::clone(self);
+        let task = move || {
+            if let Err(err) = cache.refresh() {
+                log::warn!("failed to refresh endpoint, keep using stale one: {err}");
+            }
+            cache.refreshing.store(false, Ordering::Release);
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(task);
+            }
+            Err(_) => task(),
+        }
+    }
+}
+
+/// DEFAULT_PROBE_INTERVAL is how long endpoint stays demoted before it's probed
+/// with a request again, unless Config.endpoint_probe_interval is set.
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// SAMPLE_WEIGHT is a weight of the latest sample in rolling latency and error rate.
+const SAMPLE_WEIGHT: f64 = 0.3;
+
+/// DEMOTE_ERROR_RATE is a rolling error rate at which endpoint is demoted.
+const DEMOTE_ERROR_RATE: f64 = 0.5;
+
+/// EndpointStats describes state of endpoint tracked by EndpointSelector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointStats {
+    pub addr: String,
+    /// latency is a rolling latency of successful requests, None until the first one.
+    pub latency: Option<Duration>,
+    /// error_rate is a rolling share of failed requests, from 0 to 1.
+    pub error_rate: f64,
+    /// healthy is false while endpoint is demoted.
+    pub healthy: bool,
+}
+
+struct EndpointState {
+    addr: String,
+    latency: Option<Duration>,
+    error_rate: f64,
+    demoted_until: Option<Instant>,
+}
+
+/// EndpointSelector picks one of several endpoints, e.g. one per zone, for every
+/// request. It tracks rolling latency and error rate of each endpoint and prefers
+/// the fastest healthy one. Endpoints failing too often are demoted and probed
+/// with a single request once per probe interval until they recover.
+pub struct EndpointSelector {
+    endpoints: Mutex<Vec<EndpointState>>,
+    probe_interval: Duration,
+}
+
+impl EndpointSelector {
+    /// Create a new selector over addrs probing demoted ones every probe_interval.
+    pub fn new(addrs: Vec<String>, probe_interval: Duration) -> Arc<Self> {
+        Arc::new(EndpointSelector {
+            endpoints: Mutex::new(
+                addrs
+                    .into_iter()
+                    .map(|addr| EndpointState {
+                        addr,
+                        latency: None,
+                        error_rate: 0.0,
+                        demoted_until: None,
+                    })
+                    .collect(),
+            ),
+            probe_interval,
+        })
+    }
+
+    /// select returns endpoint to send the next request to, None if there are
+    /// no endpoints. When all endpoints are demoted the one to recover first
+    /// is returned.
+    pub fn select(&self) -> Option<String> {
+        let now = Instant::now();
+        let mut endpoints = self.lock();
+
+        // demoted endpoint due for probing gets a single request.
+        if let Some(endpoint) = endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.demoted_until.is_some_and(|until| until <= now))
+        {
+            endpoint.demoted_until = Some(now + self.probe_interval);
+            return Some(endpoint.addr.clone());
+        }
+
+        // endpoints without samples yet are tried first.
+        let healthy = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.demoted_until.is_none())
+            .min_by_key(|endpoint| endpoint.latency.unwrap_or_default());
+        let endpoint = match healthy {
+            Some(endpoint) => endpoint,
+            None => endpoints
+                .iter()
+                .min_by_key(|endpoint| endpoint.demoted_until)?,
+        };
+        Some(endpoint.addr.clone())
+    }
+
+    /// report records outcome of request sent to addr, failed is true for
+    /// transport errors and transient status codes.
+    pub fn report(&self, addr: &str, latency: Duration, failed: bool) {
+        let mut endpoints = self.lock();
+        let endpoint = match endpoints.iter_mut().find(|endpoint| endpoint.addr == addr) {
+            Some(endpoint) => endpoint,
+            None => return,
+        };
+
+        let sample = if failed { 1.0 } else { 0.0 };
+        endpoint.error_rate = endpoint.error_rate * (1.0 - SAMPLE_WEIGHT) + sample * SAMPLE_WEIGHT;
+        if failed {
+            if endpoint.error_rate >= DEMOTE_ERROR_RATE {
+                endpoint.demoted_until = Some(Instant::now() + self.probe_interval);
+            }
+            return;
+        }
+
+        endpoint.latency = Some(match endpoint.latency {
+            Some(rolling) => rolling.mul_f64(1.0 - SAMPLE_WEIGHT) + latency.mul_f64(SAMPLE_WEIGHT),
+            None => latency,
+        });
+        if endpoint.error_rate < DEMOTE_ERROR_RATE {
+            endpoint.demoted_until = None;
+        }
+    }
+
+    /// set_endpoints replaces tracked endpoints with addrs, statistics of
+    /// endpoints present in both sets are kept.
+    pub fn set_endpoints(&self, addrs: Vec<String>) {
+        let mut endpoints = self.lock();
+        let mut previous: Vec<EndpointState> = std::mem::take(&mut *endpoints);
+        *endpoints = addrs
+            .into_iter()
+            .map(
+                |addr| match previous.iter().position(|endpoint| endpoint.addr == addr) {
+                    Some(index) => previous.swap_remove(index),
+                    None => EndpointState {
+                        addr,
+                        latency: None,
+                        error_rate: 0.0,
+                        demoted_until: None,
+                    },
+                },
+            )
+            .collect();
+    }
+
+    /// stats returns state of tracked endpoints in configured order.
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.lock()
+            .iter()
+            .map(|endpoint| EndpointStats {
+                addr: endpoint.addr.clone(),
+                latency: endpoint.latency,
+                error_rate: endpoint.error_rate,
+                healthy: endpoint.demoted_until.is_none(),
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<EndpointState>> {
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// EndpointRefresher periodically refreshes result of get_addr and endpoints
+/// found by discovery in the background, see Config.endpoint_refresh_interval.
+/// Requests use the latest refreshed endpoints and only wait for resolution
+/// when nothing was resolved yet.
+pub struct EndpointRefresher {
+    interval: Duration,
+    cache: Option<Arc<EndpointCache>>,
+    discovery: Option<Arc<DiscoveredEndpoints>>,
+    started: AtomicBool,
+}
+
+impl EndpointRefresher {
+    /// Create a new refresher of cache and discovery running every interval.
+    pub fn new(
+        interval: Duration,
+        cache: Option<Arc<EndpointCache>>,
+        discovery: Option<Arc<DiscoveredEndpoints>>,
+    ) -> Arc<Self> {
+        Arc::new(EndpointRefresher {
+            interval,
+            cache,
+            discovery,
+            started: AtomicBool::new(false),
+        })
+    }
+
+    /// refresh refreshes endpoints once, failures are logged and previous
+    /// endpoints are kept.
+    pub async fn refresh(&self) {
+        if let Some(cache) = &self.cache {
+            let cache = cache.clone();
+            match tokio::task::spawn_blocking(move || cache.refresh()).await {
+                Ok(Err(err)) => {
+                    log::warn!("failed to refresh endpoint, keep using previous one: {err}")
+                }
+                Err(err) => log::warn!("endpoint refresh panicked: {err}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        if let Some(discovery) = &self.discovery {
+            if let Err(err) = discovery.refresh().await {
+                log::warn!(
+                    "failed to refresh discovered endpoints, keep using previous ones: {err}"
+                );
+            }
+        }
+    }
+
+    /// start spawns background task refreshing endpoints every interval and,
+    /// for discovery, as soon as it notifies about change. Task stops when
+    /// client is shut down. None is returned when task was already started.
+    pub fn start(self: &Arc<Self>, lifecycle: &Arc<Lifecycle>) -> Option<JoinHandle<()>> {
+        if self.started.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        let refresher = self.clone();
+        let lifecycle = lifecycle.clone();
+        let guard = lifecycle.task();
+        let mut changes = self
+            .discovery
+            .as_ref()
+            .and_then(|discovery| discovery.subscribe_changes());
+        Some(tokio::spawn(async move {
+            let _guard = guard;
+            loop {
+                let changed = async {
+                    let changed = match changes.as_mut() {
+                        Some(changes) => changes.changed().await.is_ok(),
+                        None => false,
+                    };
+                    // discovery without notifications is refreshed on interval only.
+                    if !changed {
+                        std::future::pending::<()>().await;
+                    }
+                };
+                tokio::select! {
+                    _ = tokio::time::sleep(refresher.interval) => {}
+                    _ = changed => {}
+                    _ = lifecycle.closed() => return,
+                }
+                refresher.refresh().await;
+            }
+        }))
+    }
+}
@@ -0,0 +1,76 @@
+use crate::client::{Client, ErrRes};
+use crate::id::uuid_v4;
+use crate::options::PublishOption;
+use crate::protocol::{BroadcastResult, Publication, PublishResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Envelope is a standard shape of published message, so consumers of
+/// different services can tell messages apart by type and deduplicate them
+/// by id before looking into payload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Envelope<T> {
+    /// kind is a message type tag, e.g. order.created. Sent as type.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// id is a unique message id, random UUID unless set with with_id.
+    pub id: String,
+    /// timestamp is a unix time in milliseconds when envelope was created.
+    pub timestamp: i64,
+    pub payload: T,
+}
+
+impl<T> Envelope<T> {
+    /// Create a new envelope of kind around payload with random id and
+    /// current timestamp.
+    pub fn new(kind: impl Into<String>, payload: T) -> Self {
+        let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as i64,
+            Err(_) => 0,
+        };
+        Envelope {
+            kind: kind.into(),
+            id: uuid_v4(),
+            timestamp,
+            payload,
+        }
+    }
+
+    /// with_id replaces generated id, e.g. with id of domain event.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+}
+
+impl<T: DeserializeOwned> Envelope<T> {
+    /// decode decodes envelope from data of publication, e.g. from history.
+    pub fn decode(publication: &Publication) -> Result<Self, serde_json::Error> {
+        Self::deserialize(&publication.data)
+    }
+}
+
+impl Client {
+    /// PublishEnvelope allows to publish envelope to channel.
+    pub async fn publish_envelope<T: Serialize>(
+        &self,
+        channel: String,
+        envelope: &Envelope<T>,
+        opts: &[PublishOption],
+    ) -> Result<PublishResult, ErrRes> {
+        let data = serde_json::to_string(envelope)?;
+        self.publish(channel, &data, opts).await
+    }
+
+    /// BroadcastEnvelope allows to broadcast the same envelope to many channels.
+    pub async fn broadcast_envelope<T: Serialize>(
+        &self,
+        channels: Vec<String>,
+        envelope: &Envelope<T>,
+        opts: &[PublishOption],
+    ) -> Result<BroadcastResult, ErrRes> {
+        let data = serde_json::to_string(envelope)?;
+        self.broadcast(channels, &data, opts).await
+    }
+}
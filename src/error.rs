@@ -0,0 +1,164 @@
+use crate::client::{ErrRequestFailed, ErrStatusCode};
+use crate::protocol;
+use crate::retry;
+use std::error::Error;
+use std::fmt;
+
+/// FailureKind is a category of transport failure of request, so "Centrifugo
+/// slow" can be told from "DNS broken" in alerts, see ErrRequestFailed.kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Timeout means request or connection timed out.
+    Timeout,
+    /// Connect means connection to server could not be established, e.g. refused.
+    Connect,
+    /// Dns means server host name could not be resolved.
+    Dns,
+    /// TlsHandshake means TLS session with server could not be established,
+    /// e.g. certificate is not trusted.
+    TlsHandshake,
+    /// BodyDecode means response body could not be read or decompressed.
+    BodyDecode,
+    /// Other is any other failure, e.g. of middleware or reply decoding.
+    Other,
+}
+
+impl FailureKind {
+    /// of classifies err looking for reqwest error in its source chain. DNS and
+    /// TLS failures are told from other connect errors by messages of their
+    /// sources, as connectors don't expose typed errors.
+    pub fn of(err: &(dyn Error + 'static)) -> FailureKind {
+        let err = match find::<reqwest::Error>(err) {
+            Some(err) => err,
+            None => return FailureKind::Other,
+        };
+        if err.is_timeout() {
+            return FailureKind::Timeout;
+        }
+        if err.is_body() || err.is_decode() {
+            return FailureKind::BodyDecode;
+        }
+        if !err.is_connect() {
+            return FailureKind::Other;
+        }
+
+        let mut current = err.source();
+        while let Some(source) = current {
+            let message = source.to_string().to_lowercase();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                return FailureKind::Dns;
+            }
+            if ["tls", "ssl", "certificate", "handshake"]
+                .iter()
+                .any(|word| message.contains(word))
+            {
+                return FailureKind::TlsHandshake;
+            }
+            current = source.source();
+        }
+        FailureKind::Connect
+    }
+}
+
+// Implement the `std::fmt::Display` trait for `FailureKind`
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            FailureKind::Timeout => "timeout",
+            FailureKind::Connect => "connect",
+            FailureKind::Dns => "dns",
+            FailureKind::TlsHandshake => "tls handshake",
+            FailureKind::BodyDecode => "body decode",
+            FailureKind::Other => "other",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// ErrorExt classifies errors returned by client, so callers can branch on
+/// failure category without downcasting. Errors are looked up through the
+/// whole source chain, e.g. status code wrapped into another error is found.
+pub trait ErrorExt {
+    /// as_status_code returns error of request failed with non-200 status.
+    fn as_status_code(&self) -> Option<&ErrStatusCode>;
+
+    /// as_api_error returns error server replied to command with.
+    fn as_api_error(&self) -> Option<&protocol::Error>;
+
+    /// is_timeout reports whether request timed out.
+    fn is_timeout(&self) -> bool;
+
+    /// is_connect reports whether connection to server could not be established.
+    fn is_connect(&self) -> bool;
+
+    /// is_retryable reports whether failure is transient, see retry::is_retryable.
+    fn is_retryable(&self) -> bool;
+
+    /// failure_kind returns category of transport failure of request, nil if
+    /// request did not fail on transport level, e.g. got non-200 status.
+    fn failure_kind(&self) -> Option<FailureKind>;
+}
+
+impl ErrorExt for dyn Error + 'static {
+    fn as_status_code(&self) -> Option<&ErrStatusCode> {
+        find(self)
+    }
+
+    fn as_api_error(&self) -> Option<&protocol::Error> {
+        find(self)
+    }
+
+    fn is_timeout(&self) -> bool {
+        find::<reqwest::Error>(self).is_some_and(reqwest::Error::is_timeout)
+    }
+
+    fn is_connect(&self) -> bool {
+        find::<reqwest::Error>(self).is_some_and(reqwest::Error::is_connect)
+    }
+
+    fn is_retryable(&self) -> bool {
+        retry::is_retryable(self)
+    }
+
+    fn failure_kind(&self) -> Option<FailureKind> {
+        find::<ErrRequestFailed>(self).map(|err| err.kind)
+    }
+}
+
+impl ErrorExt for dyn Error + Send + Sync + 'static {
+    fn as_status_code(&self) -> Option<&ErrStatusCode> {
+        (self as &(dyn Error + 'static)).as_status_code()
+    }
+
+    fn as_api_error(&self) -> Option<&protocol::Error> {
+        (self as &(dyn Error + 'static)).as_api_error()
+    }
+
+    fn is_timeout(&self) -> bool {
+        (self as &(dyn Error + 'static)).is_timeout()
+    }
+
+    fn is_connect(&self) -> bool {
+        (self as &(dyn Error + 'static)).is_connect()
+    }
+
+    fn is_retryable(&self) -> bool {
+        (self as &(dyn Error + 'static)).is_retryable()
+    }
+
+    fn failure_kind(&self) -> Option<FailureKind> {
+        (self as &(dyn Error + 'static)).failure_kind()
+    }
+}
+
+// find returns the first error of type T in source chain of err.
+fn find<'a, T: Error + 'static>(err: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(err) = err.downcast_ref::<T>() {
+            return Some(err);
+        }
+        current = err.source();
+    }
+    None
+}
@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// RequestEvent describes a single HTTP request to Centrifugo API, retries are
+/// reported as separate requests with increasing attempt.
+#[derive(Debug)]
+pub struct RequestEvent<'a> {
+    /// methods are methods of commands sent in request.
+    pub methods: &'a [&'a str],
+    pub endpoint: &'a str,
+    pub attempt: u32,
+    /// request_id is an ID sent in REQUEST_ID_HEADER.
+    pub request_id: &'a str,
+}
+
+impl RequestEvent<'_> {
+    /// commands returns number of commands sent in request.
+    pub fn commands(&self) -> usize {
+        self.methods.len()
+    }
+}
+
+/// ResponseEvent describes request which got replies from server.
+#[derive(Debug)]
+pub struct ResponseEvent<'a> {
+    pub request: &'a RequestEvent<'a>,
+    pub duration: Duration,
+    /// errors is a number of commands server replied to with error.
+    pub errors: usize,
+}
+
+/// ErrorEvent describes request failed with transport error, non-200 status
+/// or malformed response.
+#[derive(Debug)]
+pub struct ErrorEvent<'a> {
+    pub request: &'a RequestEvent<'a>,
+    pub duration: Duration,
+    pub error: &'a (dyn Error + Send + Sync + 'static),
+}
+
+/// RetryEvent describes failed request which is going to be retried.
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// methods are methods of commands sent in request.
+    pub methods: &'a [&'a str],
+    /// attempt is a number of failed attempt, retry is sent as attempt + 1.
+    pub attempt: u32,
+    /// delay is a backoff before retry is sent.
+    pub delay: Duration,
+    /// endpoint is an endpoint failed attempt was sent to, retry may go to
+    /// another one.
+    pub endpoint: &'a str,
+    pub error: &'a (dyn Error + Send + Sync + 'static),
+}
+
+/// FallbackEvent describes get_addr failure after which request is sent to
+/// static addr, see Config.get_addr_fallback.
+#[derive(Debug)]
+pub struct FallbackEvent<'a> {
+    /// endpoint is a static addr request is sent to instead.
+    pub endpoint: &'a str,
+    pub error: &'a (dyn Error + Send + Sync + 'static),
+}
+
+/// ConnectionEvent describes HTTP connection to Centrifugo API. Connections
+/// are only observed through responses sent over them, so they are reported
+/// with the first response and, when server asked to close them with
+/// Connection: close header, with the last one.
+#[derive(Debug)]
+pub struct ConnectionEvent<'a> {
+    pub endpoint: &'a str,
+    pub local_addr: SocketAddr,
+    pub remote_addr: SocketAddr,
+    /// peer_certificate is a DER encoded leaf certificate of server, nil for
+    /// plain http connections or when custom http client doesn't collect it.
+    pub peer_certificate: Option<&'a [u8]>,
+}
+
+/// Hook is notified about every request to Centrifugo API, e.g. for custom
+/// logging or metrics. Unlike Middleware it can't modify requests. Hooks are
+/// called inline, so they are expected to return quickly.
+pub trait Hook: Send + Sync {
+    /// on_request is called right before request is sent.
+    fn on_request(&self, _event: &RequestEvent) {}
+
+    /// on_response is called when request got replies from server.
+    fn on_response(&self, _event: &ResponseEvent) {}
+
+    /// on_error is called when request failed.
+    fn on_error(&self, _event: &ErrorEvent) {}
+
+    /// on_retry is called when failed request is going to be retried, before
+    /// backoff delay.
+    fn on_retry(&self, _event: &RetryEvent) {}
+
+    /// on_endpoint_fallback is called when get_addr failed and request is sent
+    /// to static addr instead.
+    fn on_endpoint_fallback(&self, _event: &FallbackEvent) {}
+
+    /// on_connect is called when response came over a new connection, e.g.
+    /// to diagnose pool churn or certificate issues.
+    fn on_connect(&self, _event: &ConnectionEvent) {}
+
+    /// on_disconnect is called when server closes connection after response.
+    fn on_disconnect(&self, _event: &ConnectionEvent) {}
+}
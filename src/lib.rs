@@ -1,4 +1,43 @@
+pub mod admin;
+pub mod bearer;
+pub mod cache;
+pub mod caps;
+pub mod channel;
 pub mod client;
+pub mod discovery;
+pub mod endpoint;
+pub mod envelope;
+pub mod error;
+pub mod hooks;
+pub mod id;
+pub mod middleware;
+pub mod namespace;
 pub mod options;
+pub mod outbox;
+pub mod pattern;
 pub mod pipe;
+pub mod prelude;
 pub mod protocol;
+pub mod publisher;
+pub mod push;
+#[cfg(feature = "realtime")]
+pub mod realtime;
+pub mod record;
+pub mod retry;
+pub mod secret;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod shutdown;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod timestamp;
+pub mod transport;
+#[cfg(feature = "realtime")]
+pub mod uni;
+pub mod user;
+pub mod validation;
+pub mod watch;
+
+/// Result is a result of client calls, error is ErrRes unless specified.
+pub type Result<T, E = client::ErrRes> = std::result::Result<T, E>;
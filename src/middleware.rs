@@ -0,0 +1,90 @@
+use crate::client::ErrRes;
+use crate::pipe::Command;
+use reqwest::Request;
+
+/// Middleware allows to inspect and modify every HTTP request to Centrifugo API
+/// right before it's sent, after body has been serialized.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, request: &mut Request) -> Result<(), ErrRes>;
+}
+
+/// COMMAND_FILTERED_CODE is a code of error commands filtered out by
+/// CommandMiddleware get as reply. It's outside of range of codes used by
+/// Centrifugo, so it's never confused with error returned by server.
+pub const COMMAND_FILTERED_CODE: u32 = 10000;
+
+/// CommandMiddleware allows to filter, rewrite or annotate commands of every
+/// request before they're serialized, e.g. to prefix channels with tenant.
+/// Unlike Middleware it sees typed commands instead of bytes.
+pub trait CommandMiddleware: Send + Sync {
+    /// on_commands is called with commands of request in order and may modify
+    /// them in place. Returning error fails request without sending it.
+    fn on_commands(&self, _commands: &mut [Command]) -> Result<(), ErrRes> {
+        Ok(())
+    }
+
+    /// keep reports whether command is sent, it's called after on_commands.
+    /// Commands filtered out aren't sent and get error with code
+    /// COMMAND_FILTERED_CODE as reply, so their calls fail instead of
+    /// returning empty results.
+    fn keep(&self, _command: &Command) -> bool {
+        true
+    }
+}
+
+/// DEFAULT_SIGNATURE_HEADER is a header HmacSigner puts signature into by default.
+#[cfg(feature = "hmac")]
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature";
+
+/// HmacSigner signs request body with HMAC-SHA256 and attaches hex encoded
+/// signature as a header, for gateways requiring signed requests.
+#[cfg(feature = "hmac")]
+#[derive(Clone)]
+pub struct HmacSigner {
+    secret: Vec<u8>,
+    header: String,
+}
+
+#[cfg(feature = "hmac")]
+impl HmacSigner {
+    /// Create a new signer putting signature into DEFAULT_SIGNATURE_HEADER.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        HmacSigner {
+            secret: secret.into(),
+            header: DEFAULT_SIGNATURE_HEADER.to_string(),
+        }
+    }
+
+    /// with_header allows to set header name signature is put into.
+    pub fn with_header(mut self, header: impl Into<String>) -> Self {
+        self.header = header.into();
+        self
+    }
+
+    /// sign returns hex encoded HMAC-SHA256 signature of body.
+    pub fn sign(&self, body: &[u8]) -> String {
+        use hmac::{Hmac, Mac};
+
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&self.secret)
+            .expect("HMAC can take key of any size");
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl Middleware for HmacSigner {
+    fn on_request(&self, request: &mut Request) -> Result<(), ErrRes> {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        let signature = self.sign(body);
+
+        request.headers_mut().insert(
+            reqwest::header::HeaderName::from_bytes(self.header.as_bytes())?,
+            signature.parse()?,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::channel::ChannelHandle;
+use crate::client::{Client, ErrRes};
+use crate::pattern::{namespace, NAMESPACE_SEPARATOR};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// ErrUndeclaredNamespace is returned before sending when channel doesn't belong
+// to any namespace declared in Config.namespaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrUndeclaredNamespace {
+    pub channel: String,
+    /// namespace is a namespace of channel, nil for default namespace.
+    pub namespace: Option<String>,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrUndeclaredNamespace`
+impl fmt::Display for ErrUndeclaredNamespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(
+                f,
+                "channel {} is in undeclared namespace {}",
+                self.channel, namespace
+            ),
+            None => write!(
+                f,
+                "channel {} is in undeclared default namespace",
+                self.channel
+            ),
+        }
+    }
+}
+
+// Implement the `Error` trait for `ErrUndeclaredNamespace`
+impl Error for ErrUndeclaredNamespace {}
+
+// ErrNamespaceFeature is returned before sending presence or history command
+// for channel of namespace declared without that feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrNamespaceFeature {
+    pub channel: String,
+    /// feature is either presence or history.
+    pub feature: String,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrNamespaceFeature`
+impl fmt::Display for ErrNamespaceFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is not enabled in namespace of channel {}",
+            self.feature, self.channel
+        )
+    }
+}
+
+// Implement the `Error` trait for `ErrNamespaceFeature`
+impl Error for ErrNamespaceFeature {}
+
+/// Namespace declares channel namespace as configured on server, empty name
+/// stands for default namespace of channels without namespace prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespace {
+    pub name: String,
+    /// presence is true when presence is enabled in namespace.
+    pub presence: bool,
+    /// history is true when history is kept in namespace.
+    pub history: bool,
+}
+
+impl Namespace {
+    /// Create a new namespace without presence and history.
+    pub fn new(name: impl Into<String>) -> Self {
+        Namespace {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// presence marks presence as enabled in namespace.
+    pub fn presence(mut self, presence: bool) -> Self {
+        self.presence = presence;
+        self
+    }
+
+    /// history marks history as kept in namespace.
+    pub fn history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// channel_name returns name of channel in namespace, e.g. chat:index for
+    /// index in chat namespace.
+    pub fn channel_name(&self, name: &str) -> String {
+        if self.name.is_empty() {
+            return name.to_string();
+        }
+        format!("{}{}{}", self.name, NAMESPACE_SEPARATOR, name)
+    }
+
+    /// channel returns handle of channel in namespace, see channel_name.
+    pub fn channel(&self, client: &Client, name: &str) -> ChannelHandle {
+        client.channel(self.channel_name(name))
+    }
+}
+
+/// Namespaces is a registry of namespaces application uses, see
+/// Config.namespaces. Publishes to channels of undeclared namespaces fail with
+/// ErrUndeclaredNamespace and presence or history calls to namespaces without
+/// them fail with ErrNamespaceFeature before being sent.
+#[derive(Clone, Debug, Default)]
+pub struct Namespaces {
+    namespaces: HashMap<String, Namespace>,
+}
+
+impl Namespaces {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// with declares namespace, declaring the same name again replaces it.
+    pub fn with(mut self, namespace: Namespace) -> Self {
+        self.namespaces.insert(namespace.name.clone(), namespace);
+        self
+    }
+
+    /// get returns declared namespace by name.
+    pub fn get(&self, name: &str) -> Option<&Namespace> {
+        self.namespaces.get(name)
+    }
+
+    /// lookup returns declared namespace of channel.
+    pub fn lookup(&self, channel: &str) -> Result<&Namespace, ErrUndeclaredNamespace> {
+        let name = namespace(channel);
+        self.namespaces
+            .get(name.unwrap_or_default())
+            .ok_or_else(|| ErrUndeclaredNamespace {
+                channel: channel.to_string(),
+                namespace: name.map(str::to_string),
+            })
+    }
+
+    /// channel returns handle of channel if its namespace is declared.
+    pub fn channel(
+        &self,
+        client: &Client,
+        channel: &str,
+    ) -> Result<ChannelHandle, ErrUndeclaredNamespace> {
+        self.lookup(channel)?;
+        Ok(client.channel(channel))
+    }
+
+    /// check_presence checks that channel namespace is declared with presence.
+    pub fn check_presence(&self, channel: &str) -> Result<(), ErrRes> {
+        if !self.lookup(channel)?.presence {
+            return Err(Box::new(feature_error(channel, "presence")));
+        }
+        Ok(())
+    }
+
+    /// check_history checks that channel namespace is declared with history.
+    pub fn check_history(&self, channel: &str) -> Result<(), ErrRes> {
+        if !self.lookup(channel)?.history {
+            return Err(Box::new(feature_error(channel, "history")));
+        }
+        Ok(())
+    }
+}
+
+fn feature_error(channel: &str, feature: &str) -> ErrNamespaceFeature {
+    ErrNamespaceFeature {
+        channel: channel.to_string(),
+        feature: feature.to_string(),
+    }
+}
@@ -1,16 +1,78 @@
 #![allow(dead_code)]
 
+use crate::timestamp::{from_system_time, Timestamp};
 use serde;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Clone, Debug, Default, Copy, Serialize, Deserialize)]
+/// unix_time converts time to unix seconds, raw form of Timestamp.
+pub fn unix_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    }
+}
+
+/// unix_time_in returns unix seconds of the moment duration from now.
+pub fn unix_time_in(duration: Duration) -> i64 {
+    unix_time(SystemTime::now() + duration)
+}
+
+/// PublishOptions define the per-publication options. It isn't Copy since it
+/// owns tags, clone it to reuse options across calls.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PublishOptions {
+    /// skip_history skips adding publication to channel history.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub skip_history: Option<bool>,
+    /// tags is a map with arbitrary keys and values attached to publication.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashMap<String, String>>,
+    /// idempotency_key allows server to drop duplicate publications.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// delta enables delta compression for publication.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<bool>,
+}
+
+impl PublishOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn skip_history(mut self, skip: bool) -> Self {
+        self.skip_history = Some(skip);
+        self
+    }
+
+    pub fn tags(mut self, tags: HashMap<String, String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// auto_idempotency_key sets idempotency_key to a random UUID.
+    pub fn auto_idempotency_key(mut self) -> Self {
+        self.idempotency_key = Some(crate::id::uuid_v4());
+        self
+    }
+
+    pub fn delta(mut self, delta: bool) -> Self {
+        self.delta = Some(delta);
+        self
+    }
 }
 
 /// PublishOption is a type to represent vairous publish options
-pub type PublishOption = Box<dyn Fn(&mut PublishOptions)>;
+pub type PublishOption = Box<dyn Fn(&mut PublishOptions) + Send + Sync>;
 
 /// with_skip_history allows to set skip_history field.
 pub fn with_skip_history(skip: bool) -> PublishOption {
@@ -19,6 +81,28 @@ pub fn with_skip_history(skip: bool) -> PublishOption {
     })
 }
 
+/// with_tags allows to set tags field.
+pub fn with_tags(tags: HashMap<String, String>) -> PublishOption {
+    Box::new(move |opts: &mut PublishOptions| opts.tags = Some(tags.clone()))
+}
+
+/// with_idempotency_key allows to set idempotency_key field.
+pub fn with_idempotency_key(key: String) -> PublishOption {
+    Box::new(move |opts: &mut PublishOptions| opts.idempotency_key = Some(key.clone()))
+}
+
+/// with_auto_idempotency_key sets idempotency_key field to a random UUID
+/// generated for every publish the option is applied to, retries of the same
+/// publish reuse it. Key is returned in PublishResult.idempotency_key.
+pub fn with_auto_idempotency_key() -> PublishOption {
+    Box::new(|opts: &mut PublishOptions| opts.idempotency_key = Some(crate::id::uuid_v4()))
+}
+
+/// with_delta allows to set delta field.
+pub fn with_delta(delta: bool) -> PublishOption {
+    Box::new(move |opts: &mut PublishOptions| opts.delta = Some(delta))
+}
+
 /// SubscribeOption define the per-subscription options
 #[derive(Clone, Debug, Serialize, Default, Deserialize)]
 pub struct SubscribeOptions {
@@ -52,11 +136,62 @@ pub struct SubscribeOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recover_since: Option<StreamPosition>,
     /// ClientID to subscribe.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "client",
+        alias = "client_id",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub client_id: Option<String>,
 }
 
-pub type SubscribeOption = Box<dyn Fn(&mut SubscribeOptions)>;
+impl SubscribeOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn info(mut self, info: Value) -> Self {
+        self.info = Some(info);
+        self
+    }
+
+    pub fn presence(mut self, enabled: bool) -> Self {
+        self.presence = Some(enabled);
+        self
+    }
+
+    pub fn join_leave(mut self, enabled: bool) -> Self {
+        self.join_leave = Some(enabled);
+        self
+    }
+
+    pub fn position(mut self, enabled: bool) -> Self {
+        self.position = Some(enabled);
+        self
+    }
+
+    pub fn recover(mut self, enabled: bool) -> Self {
+        self.recover = Some(enabled);
+        self
+    }
+
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn recover_since(mut self, since: StreamPosition) -> Self {
+        self.recover_since = Some(since);
+        self
+    }
+
+    pub fn client(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+}
+
+pub type SubscribeOption = Box<dyn Fn(&mut SubscribeOptions) + Send + Sync>;
 
 pub fn with_subscribe_info(chan_info: Value) -> SubscribeOption {
     Box::new(move |opts: &mut SubscribeOptions| opts.info = Some(chan_info.clone()))
@@ -93,11 +228,27 @@ pub fn with_recover_since(since: StreamPosition) -> SubscribeOption {
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct UnsubscribeOptions {
     /// client_id is unsubscribe.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "client",
+        alias = "client_id",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub client_id: Option<String>,
 }
 
-pub type UnsubscribeOption = Box<dyn Fn(&mut UnsubscribeOptions)>;
+impl UnsubscribeOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn client(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+}
+
+pub type UnsubscribeOption = Box<dyn Fn(&mut UnsubscribeOptions) + Send + Sync>;
 
 pub fn with_unsubscribe_client(client_id: String) -> UnsubscribeOption {
     Box::new(move |opts: &mut UnsubscribeOptions| opts.client_id = Some(client_id.clone()))
@@ -107,19 +258,55 @@ pub fn with_unsubscribe_client(client_id: String) -> UnsubscribeOption {
 pub struct Disconnect {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reconnect: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct DisconnectOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub disconnect: Option<Disconnect>,
+    /// client_whitelist is sent as whitelist and client_id as client, names
+    /// server expects. Old names are still accepted when deserializing.
+    #[serde(
+        rename = "whitelist",
+        alias = "client_whitelist",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub client_whitelist: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "client",
+        alias = "client_id",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub client_id: Option<String>,
 }
 
-pub type DisconnectOption = Box<dyn Fn(&mut DisconnectOptions)>;
+impl DisconnectOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn disconnect(mut self, disconnect: Disconnect) -> Self {
+        self.disconnect = Some(disconnect);
+        self
+    }
+
+    pub fn client_whitelist(mut self, whitelist: Vec<String>) -> Self {
+        self.client_whitelist = Some(whitelist);
+        self
+    }
+
+    pub fn client(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+}
+
+pub type DisconnectOption = Box<dyn Fn(&mut DisconnectOptions) + Send + Sync>;
 
 pub fn with_disconnect(disconnect: Disconnect) -> DisconnectOption {
     Box::new(move |opts: &mut DisconnectOptions| opts.disconnect = Some(disconnect.clone()))
@@ -132,6 +319,7 @@ pub fn with_disconnect_client(client_id: String) -> DisconnectOption {
 pub fn with_disconnect_client_whitelist(whitelist: Vec<String>) -> DisconnectOption {
     Box::new(move |opts: &mut DisconnectOptions| opts.client_whitelist = Some(whitelist.clone()))
 }
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct HistoryOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -142,9 +330,31 @@ pub struct HistoryOptions {
     pub reverse: Option<bool>,
 }
 
+impl HistoryOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn since(mut self, since: StreamPosition) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn limit(mut self, limit: i32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = Some(reverse);
+        self
+    }
+}
+
 pub const NO_LIMIT: i32 = -1;
 
-pub type HistoryOption = Box<dyn Fn(&mut HistoryOptions)>;
+pub type HistoryOption = Box<dyn Fn(&mut HistoryOptions) + Send + Sync>;
 
 pub fn with_limit(limit: i32) -> HistoryOption {
     Box::new(move |opts: &mut HistoryOptions| opts.limit = Some(limit))
@@ -165,14 +375,151 @@ pub fn with_since(since: StreamPosition) -> HistoryOption {
 pub fn with_reverse(reverse: bool) -> HistoryOption {
     Box::new(move |opts: &mut HistoryOptions| opts.reverse = Some(reverse))
 }
-#[derive(Serialize, Deserialize, Debug, Default)]
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ChannelsOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
 }
 
-pub(crate) type ChannelsOption = Box<dyn Fn(&mut ChannelsOptions)>;
+impl ChannelsOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.pattern = Some(pattern.into());
+        self
+    }
+}
+
+pub(crate) type ChannelsOption = Box<dyn Fn(&mut ChannelsOptions) + Send + Sync>;
 
 pub fn with_pattern(pattern: String) -> ChannelsOption {
     Box::new(move |opts: &mut ChannelsOptions| opts.pattern = Some(pattern.clone()))
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RefreshOptions {
+    /// client_id to refresh.
+    #[serde(rename = "client", skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// session to refresh.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// expired marks connection as expired, it will be disconnected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expired: Option<bool>,
+    /// expire_at is a moment in the future when connection should expire.
+    #[serde(
+        default,
+        with = "crate::timestamp::unix_seconds::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expire_at: Option<Timestamp>,
+}
+
+impl RefreshOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn client(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    pub fn expired(mut self, expired: bool) -> Self {
+        self.expired = Some(expired);
+        self
+    }
+
+    pub fn expire_at(mut self, expire_at: Timestamp) -> Self {
+        self.expire_at = Some(expire_at);
+        self
+    }
+
+    /// expire_at_time is the same as expire_at but takes SystemTime.
+    pub fn expire_at_time(self, expire_at: SystemTime) -> Self {
+        self.expire_at(from_system_time(expire_at))
+    }
+
+    /// expire_in sets expiration to duration from now.
+    pub fn expire_in(self, expire_in: Duration) -> Self {
+        self.expire_at(from_system_time(SystemTime::now() + expire_in))
+    }
+}
+
+pub type RefreshOption = Box<dyn Fn(&mut RefreshOptions) + Send + Sync>;
+
+pub fn with_refresh_client(client_id: String) -> RefreshOption {
+    Box::new(move |opts: &mut RefreshOptions| opts.client_id = Some(client_id.clone()))
+}
+
+pub fn with_refresh_session(session: String) -> RefreshOption {
+    Box::new(move |opts: &mut RefreshOptions| opts.session = Some(session.clone()))
+}
+
+pub fn with_refresh_expired(expired: bool) -> RefreshOption {
+    Box::new(move |opts: &mut RefreshOptions| opts.expired = Some(expired))
+}
+
+pub fn with_refresh_expire_at(expire_at: Timestamp) -> RefreshOption {
+    Box::new(move |opts: &mut RefreshOptions| opts.expire_at = Some(expire_at))
+}
+
+pub fn with_refresh_expire_at_time(expire_at: SystemTime) -> RefreshOption {
+    with_refresh_expire_at(from_system_time(expire_at))
+}
+
+/// with_refresh_expire_in sets expiration to duration from the moment option
+/// is applied, i.e. when command is added to pipe.
+pub fn with_refresh_expire_in(expire_in: Duration) -> RefreshOption {
+    Box::new(move |opts: &mut RefreshOptions| {
+        opts.expire_at = Some(from_system_time(SystemTime::now() + expire_in))
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ConnectionsOptions {
+    /// user to return connections of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// expression to filter connections with (CEL expression).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+}
+
+impl ConnectionsOptions {
+    /// new returns empty options to be filled with chainable setters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn expression(mut self, expression: impl Into<String>) -> Self {
+        self.expression = Some(expression.into());
+        self
+    }
+}
+
+pub type ConnectionsOption = Box<dyn Fn(&mut ConnectionsOptions) + Send + Sync>;
+
+pub fn with_connections_user(user: String) -> ConnectionsOption {
+    Box::new(move |opts: &mut ConnectionsOptions| opts.user = Some(user.clone()))
+}
+
+pub fn with_connections_expression(expression: String) -> ConnectionsOption {
+    Box::new(move |opts: &mut ConnectionsOptions| opts.expression = Some(expression.clone()))
+}
@@ -0,0 +1,386 @@
+use crate::client::{Client, ErrRes};
+use crate::options::{PublishOption, PublishOptions};
+use crate::pipe::{Command, Method, PublishRequest, RequestKind};
+use crate::retry::{DeadLetterHandler, RetryPolicy};
+use crate::shutdown::ErrClientClosed;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// OutboxEntry is a command persisted in outbox store waiting to be delivered.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub command: Command,
+}
+
+/// OutboxStore persists commands until they're acknowledged by successful reply.
+pub trait OutboxStore: Send + Sync {
+    /// append persists command and returns its id.
+    fn append(&self, command: &Command) -> Result<u64, ErrRes>;
+    /// pending returns not yet acknowledged entries in order they were appended.
+    fn pending(&self) -> Result<Vec<OutboxEntry>, ErrRes>;
+    /// ack removes entry from store.
+    fn ack(&self, id: u64) -> Result<(), ErrRes>;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Append(Box<OutboxEntry>),
+    Ack { ack: u64 },
+}
+
+struct FileState {
+    file: File,
+    entries: BTreeMap<u64, Command>,
+    next_id: u64,
+}
+
+/// FileStore is an OutboxStore keeping entries in append-only NDJSON log file,
+/// every write is synced to disk before returning. Log is compacted on open and
+/// whenever all entries are acknowledged.
+pub struct FileStore {
+    path: PathBuf,
+    state: Mutex<FileState>,
+}
+
+impl FileStore {
+    /// open opens outbox log at path creating it when missing, entries left
+    /// from previous runs are loaded as pending.
+    pub fn open(path: impl AsRef<Path>) -> Result<FileStore, ErrRes> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = BTreeMap::new();
+        let mut next_id = 1;
+
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line) {
+                    Ok(Record::Append(entry)) => {
+                        next_id = next_id.max(entry.id + 1);
+                        entries.insert(entry.id, entry.command);
+                    }
+                    Ok(Record::Ack { ack }) => {
+                        entries.remove(&ack);
+                    }
+                    // tail may be partially written when process crashed.
+                    Err(err) => log::warn!("skip malformed outbox record: {err}"),
+                }
+            }
+        }
+
+        let file = Self::compact(&path, &entries)?;
+        Ok(FileStore {
+            path,
+            state: Mutex::new(FileState {
+                file,
+                entries,
+                next_id,
+            }),
+        })
+    }
+
+    /// compact rewrites log with pending entries only and returns it opened for append.
+    fn compact(path: &Path, entries: &BTreeMap<u64, Command>) -> Result<File, ErrRes> {
+        let tmp_path = path.with_extension("tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        for (id, command) in entries {
+            let record = Record::Append(Box::new(OutboxEntry {
+                id: *id,
+                command: command.clone(),
+            }));
+            serde_json::to_writer(&mut tmp, &record)?;
+            tmp.write_all(b"\n")?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(OpenOptions::new().append(true).open(path)?)
+    }
+
+    fn write_record(file: &mut File, record: &Record) -> Result<(), ErrRes> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+impl OutboxStore for FileStore {
+    fn append(&self, command: &Command) -> Result<u64, ErrRes> {
+        let mut state = self.state.lock().map_err(|_| "Lock poisoned")?;
+        let id = state.next_id;
+        let record = Record::Append(Box::new(OutboxEntry {
+            id,
+            command: command.clone(),
+        }));
+        Self::write_record(&mut state.file, &record)?;
+        state.next_id += 1;
+        state.entries.insert(id, command.clone());
+        Ok(id)
+    }
+
+    fn pending(&self) -> Result<Vec<OutboxEntry>, ErrRes> {
+        let state = self.state.lock().map_err(|_| "Lock poisoned")?;
+        Ok(state
+            .entries
+            .iter()
+            .map(|(id, command)| OutboxEntry {
+                id: *id,
+                command: command.clone(),
+            })
+            .collect())
+    }
+
+    fn ack(&self, id: u64) -> Result<(), ErrRes> {
+        let mut state = self.state.lock().map_err(|_| "Lock poisoned")?;
+        if state.entries.remove(&id).is_none() {
+            return Ok(());
+        }
+        if state.entries.is_empty() {
+            state.file = Self::compact(&self.path, &state.entries)?;
+            return Ok(());
+        }
+        Self::write_record(&mut state.file, &Record::Ack { ack: id })
+    }
+}
+
+/// DEFAULT_OUTBOX_BATCH_SIZE is a max number of entries sent in one request.
+pub const DEFAULT_OUTBOX_BATCH_SIZE: usize = 100;
+
+/// OutboxPublisher provides at-least-once delivery of publications: commands
+/// are persisted in store before sending and removed only after server replied
+/// without error, so they survive crashes and are retried with backoff. Entries
+/// server replied to with error not accepted by RetryPolicy::is_retryable_code
+/// are dropped right away, as retrying them can't succeed.
+pub struct OutboxPublisher {
+    client: Client,
+    store: Arc<dyn OutboxStore>,
+    retry_policy: RetryPolicy,
+    batch_size: usize,
+    attempts: Mutex<HashMap<u64, u32>>,
+    notify: Notify,
+    dead_letter: Option<DeadLetterHandler>,
+}
+
+impl OutboxPublisher {
+    /// Create a new outbox publisher sending through client, retrying every
+    /// entry failed with transient error until success.
+    pub fn new(client: Client, store: Arc<dyn OutboxStore>) -> Self {
+        OutboxPublisher {
+            client,
+            store,
+            retry_policy: RetryPolicy {
+                max_attempts: 0,
+                ..Default::default()
+            },
+            batch_size: DEFAULT_OUTBOX_BATCH_SIZE,
+            attempts: Mutex::new(HashMap::new()),
+            notify: Notify::new(),
+            dead_letter: None,
+        }
+    }
+
+    /// with_retry_policy allows to set retry policy, entries rejected by server
+    /// more than max_attempts times or with error codes it doesn't retry are
+    /// dropped.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// with_dead_letter allows to set handler invoked with dropped entries,
+    /// together with last error returned by server.
+    pub fn with_dead_letter(mut self, dead_letter: DeadLetterHandler) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+
+    /// with_batch_size allows to set max number of entries sent in one request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// publish persists publish command in store and returns its id, command is
+    /// sent by flush or by background task started with start.
+    pub fn publish(
+        &self,
+        channel: String,
+        data: &str,
+        opts: &[PublishOption],
+    ) -> Result<u64, ErrRes> {
+        let mut options = PublishOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
+
+        let cmd = Command {
+            method: Method::Publish,
+            params: RequestKind::PublishRequest(PublishRequest {
+                channel,
+                data: serde_json::from_str(data)?,
+                options,
+            }),
+        };
+
+        let id = self.store.append(&cmd)?;
+        self.notify.notify_one();
+        Ok(id)
+    }
+
+    /// pending returns number of entries not yet delivered.
+    pub fn pending(&self) -> Result<usize, ErrRes> {
+        Ok(self.store.pending()?.len())
+    }
+
+    /// flush sends pending entries once and returns number of delivered ones.
+    /// Error is returned when request failed with transient error, in this case
+    /// entries stay pending. When request is rejected permanently, e.g. with 400
+    /// status or ErrPayloadTooLarge, entries of batch are sent one by one, so
+    /// the ones causing rejection are dropped and the rest is delivered.
+    pub async fn flush(&self) -> Result<usize, ErrRes> {
+        let pending = self.store.pending()?;
+        let mut delivered = 0;
+
+        for batch in pending.chunks(self.batch_size) {
+            match self.send_batch(batch).await {
+                Ok(count) => delivered += count,
+                Err(err) if self.is_permanent(&*err) && batch.len() == 1 => {
+                    self.drop_entry(&batch[0], 1, &*err)?
+                }
+                Err(err) if self.is_permanent(&*err) => {
+                    for entry in batch {
+                        match self.send_batch(std::slice::from_ref(entry)).await {
+                            Ok(count) => delivered += count,
+                            Err(err) if self.is_permanent(&*err) => {
+                                self.drop_entry(entry, 1, &*err)?
+                            }
+                            Err(err) => return Err(err),
+                        }
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// send_batch sends entries in one request, acks delivered ones and drops
+    /// ones server rejected for good, returns number of delivered entries.
+    async fn send_batch(&self, batch: &[OutboxEntry]) -> Result<usize, ErrRes> {
+        let commands: Vec<Command> = batch.iter().map(|entry| entry.command.clone()).collect();
+        let replies = self.client.send(&commands).await?;
+        if replies.len() != batch.len() {
+            return Err("malformed response returned from server".into());
+        }
+
+        let mut delivered = 0;
+        for (entry, reply) in batch.iter().zip(replies) {
+            match reply.error {
+                None => {
+                    self.store.ack(entry.id)?;
+                    self.attempts
+                        .lock()
+                        .map_err(|_| "Lock poisoned")?
+                        .remove(&entry.id);
+                    delivered += 1;
+                }
+                Some(err) => {
+                    let attempt = {
+                        let mut attempts = self.attempts.lock().map_err(|_| "Lock poisoned")?;
+                        let attempt = attempts.entry(entry.id).or_insert(0);
+                        *attempt += 1;
+                        *attempt
+                    };
+                    // permanently rejected entries would block outbox forever.
+                    let retryable = self.retry_policy.is_retryable_code(err.code);
+                    if !retryable || !self.retry_policy.can_retry(attempt) {
+                        self.drop_entry(entry, attempt, &err)?;
+                    }
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// is_permanent reports whether request failed with error retrying can't
+    /// fix. Shutdown of client isn't one, entries are delivered after restart.
+    fn is_permanent(&self, err: &(dyn Error + 'static)) -> bool {
+        !self.retry_policy.is_retryable(err) && !err.is::<ErrClientClosed>()
+    }
+
+    /// drop_entry removes entry from store and passes it to dead letter handler.
+    fn drop_entry(
+        &self,
+        entry: &OutboxEntry,
+        attempt: u32,
+        err: &(dyn Error + Send + Sync),
+    ) -> Result<(), ErrRes> {
+        log::error!(
+            "drop outbox entry {} after {} attempts: {err}",
+            entry.id,
+            attempt
+        );
+        self.attempts
+            .lock()
+            .map_err(|_| "Lock poisoned")?
+            .remove(&entry.id);
+        self.store.ack(entry.id)?;
+        if let Some(dead_letter) = &self.dead_letter {
+            dead_letter(&entry.command, err);
+        }
+        Ok(())
+    }
+
+    /// start spawns background task delivering entries as they're published,
+    /// including ones left from previous runs, backing off while delivery fails.
+    /// Task stops when client is shut down, undelivered entries stay in store.
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        let lifecycle = self.client.lifecycle.clone();
+        let guard = lifecycle.task();
+        tokio::spawn(async move {
+            let _guard = guard;
+            let mut failures = 0;
+            loop {
+                if lifecycle.is_closed() {
+                    return;
+                }
+
+                let failed = match self.flush().await {
+                    Ok(_) => !matches!(self.pending(), Ok(0)),
+                    Err(err) => {
+                        log::warn!("outbox delivery failed: {err}");
+                        true
+                    }
+                };
+
+                let wait = async {
+                    if failed {
+                        failures += 1;
+                        tokio::time::sleep(self.retry_policy.backoff(failures)).await;
+                    } else {
+                        failures = 0;
+                        self.notify.notified().await;
+                    }
+                };
+                tokio::select! {
+                    _ = wait => {}
+                    _ = lifecycle.closed() => return,
+                }
+            }
+        })
+    }
+}
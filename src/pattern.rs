@@ -0,0 +1,244 @@
+use std::error::Error;
+use std::fmt;
+
+/// NAMESPACE_SEPARATOR separates channel namespace from the rest of channel name.
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// namespace returns namespace of channel, e.g. chat for chat:index, None for
+/// channels in default namespace.
+pub fn namespace(channel: &str) -> Option<&str> {
+    channel
+        .split_once(NAMESPACE_SEPARATOR)
+        .map(|(namespace, _)| namespace)
+}
+
+// PatternError is returned when channel pattern can't be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pattern: String,
+    pub message: String,
+}
+
+// Implement the `std::fmt::Display` trait for `PatternError`
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern {:?}: {}", self.pattern, self.message)
+    }
+}
+
+// Implement the `Error` trait for `PatternError`
+impl Error for PatternError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// Any matches any sequence of characters, including separators.
+    Any,
+    /// Single matches any single character.
+    Single,
+    /// Class matches single character in (or, when negated, not in) ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == c,
+            Token::Any | Token::Single => true,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(from, to)| (*from..=*to).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+/// ChannelPattern is a channel matcher with the same glob semantics as pattern
+/// of channels API command: `*` matches any sequence of characters (namespace
+/// separator included), `?` matches any character, `[abc]`, `[a-z]` and
+/// `[!abc]` match character classes, `{a,b}` matches any of alternatives and
+/// `\` escapes special characters.
+#[derive(Debug, Clone)]
+pub struct ChannelPattern {
+    pattern: String,
+    /// alternatives are patterns with braces expanded.
+    alternatives: Vec<Vec<Token>>,
+}
+
+impl ChannelPattern {
+    /// new compiles pattern.
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut pos = 0;
+        let alternatives =
+            parse_sequence(&chars, &mut pos, false).map_err(|message| PatternError {
+                pattern: pattern.to_string(),
+                message,
+            })?;
+        Ok(ChannelPattern {
+            pattern: pattern.to_string(),
+            alternatives,
+        })
+    }
+
+    /// as_str returns source pattern.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// matches tells whether channel matches pattern.
+    pub fn matches(&self, channel: &str) -> bool {
+        let channel: Vec<char> = channel.chars().collect();
+        self.alternatives
+            .iter()
+            .any(|tokens| match_tokens(tokens, &channel))
+    }
+
+    /// filter returns channels matching pattern, e.g. names from ChannelsResult.
+    pub fn filter<I, S>(&self, channels: I) -> Vec<S>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        channels
+            .into_iter()
+            .filter(|channel| self.matches(channel.as_ref()))
+            .collect()
+    }
+}
+
+impl fmt::Display for ChannelPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// parse_sequence parses pattern from pos until its end or, inside braces,
+/// until `,` or `}`, and returns sequences of tokens with braces expanded.
+fn parse_sequence(
+    chars: &[char],
+    pos: &mut usize,
+    in_braces: bool,
+) -> Result<Vec<Vec<Token>>, String> {
+    let mut sequences = vec![Vec::new()];
+    let push = |sequences: &mut Vec<Vec<Token>>, token: Token| {
+        for sequence in sequences.iter_mut() {
+            sequence.push(token.clone());
+        }
+    };
+
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        match c {
+            ',' | '}' if in_braces => return Ok(sequences),
+            '*' => push(&mut sequences, Token::Any),
+            '?' => push(&mut sequences, Token::Single),
+            '\\' => {
+                *pos += 1;
+                let escaped = *chars.get(*pos).ok_or("trailing escape character")?;
+                push(&mut sequences, Token::Literal(escaped));
+            }
+            '[' => {
+                let class = parse_class(chars, pos)?;
+                push(&mut sequences, class);
+            }
+            '{' => {
+                let mut alternatives = Vec::new();
+                loop {
+                    *pos += 1;
+                    alternatives.extend(parse_sequence(chars, pos, true)?);
+                    match chars.get(*pos) {
+                        Some(',') => continue,
+                        Some('}') => break,
+                        _ => return Err("unclosed '{'".to_string()),
+                    }
+                }
+                sequences = sequences
+                    .iter()
+                    .flat_map(|sequence| {
+                        alternatives.iter().map(move |alternative| {
+                            let mut sequence = sequence.clone();
+                            sequence.extend(alternative.iter().cloned());
+                            sequence
+                        })
+                    })
+                    .collect();
+            }
+            c => push(&mut sequences, Token::Literal(c)),
+        }
+        *pos += 1;
+    }
+
+    if in_braces {
+        return Err("unclosed '{'".to_string());
+    }
+    Ok(sequences)
+}
+
+/// parse_class parses character class starting at pos, leaves pos at `]`.
+fn parse_class(chars: &[char], pos: &mut usize) -> Result<Token, String> {
+    *pos += 1;
+    let negated = chars.get(*pos) == Some(&'!');
+    if negated {
+        *pos += 1;
+    }
+
+    let mut ranges = Vec::new();
+    loop {
+        let from = match chars.get(*pos) {
+            Some(']') if !ranges.is_empty() => return Ok(Token::Class { negated, ranges }),
+            Some('\\') => {
+                *pos += 1;
+                *chars.get(*pos).ok_or("unclosed '['")?
+            }
+            Some(c) => *c,
+            None => return Err("unclosed '['".to_string()),
+        };
+        *pos += 1;
+
+        if chars.get(*pos) == Some(&'-') && chars.get(*pos + 1).is_some_and(|c| *c != ']') {
+            let to = chars[*pos + 1];
+            if to < from {
+                return Err(format!("invalid range {from}-{to}"));
+            }
+            ranges.push((from, to));
+            *pos += 2;
+        } else {
+            ranges.push((from, from));
+        }
+    }
+}
+
+/// match_tokens matches input against tokens, backtracking on the last `*`.
+fn match_tokens(tokens: &[Token], input: &[char]) -> bool {
+    let (mut t, mut i) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < input.len() {
+        match tokens.get(t) {
+            Some(Token::Any) => {
+                star = Some((t, i));
+                t += 1;
+                continue;
+            }
+            Some(token) if token.matches(input[i]) => {
+                t += 1;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match star {
+            Some((star_t, star_i)) => {
+                star = Some((star_t, star_i + 1));
+                t = star_t + 1;
+                i = star_i + 1;
+            }
+            None => return false,
+        }
+    }
+
+    tokens[t..].iter().all(|token| *token == Token::Any)
+}
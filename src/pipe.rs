@@ -1,67 +1,391 @@
+use crate::client::ErrRes;
 use crate::options::{
-    ChannelsOption, ChannelsOptions, DisconnectOption, DisconnectOptions, HistoryOption,
-    HistoryOptions, PublishOption, PublishOptions, SubscribeOption, SubscribeOptions,
-    UnsubscribeOption, UnsubscribeOptions,
+    ChannelsOption, ChannelsOptions, ConnectionsOption, ConnectionsOptions, DisconnectOption,
+    DisconnectOptions, HistoryOption, HistoryOptions, PublishOption, PublishOptions, RefreshOption,
+    RefreshOptions, SubscribeOption, SubscribeOptions, UnsubscribeOption, UnsubscribeOptions,
 };
-use serde::{Deserialize, Serialize};
+use crate::protocol::{self, Reply};
+use crate::timestamp::Timestamp;
+use bytes::Bytes;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
 pub use std::error::Error;
-use std::sync::{Arc, Mutex};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 /// Pipe allows to send several commands in one HTTP request.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Pipe {
-    pub commands: Arc<Mutex<Vec<Command>>>,
+    /// commands buffered in pipe, they're changed only through Pipe methods,
+    /// so encoded never gets stale.
+    commands: Arc<Mutex<Vec<Command>>>,
+    /// encoded keeps JSON of commands serialized when they're added, in order
+    /// of commands, so sending pipe again (e.g. retrying it) doesn't serialize
+    /// them again. It's not used when its length doesn't match commands.
+    encoded: Arc<Mutex<Vec<Bytes>>>,
 }
 
+/// PublishRequest is params of publish command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PublishRequest {
     pub channel: String,
     pub data: serde_json::Value,
+    #[serde(flatten)]
     pub options: PublishOptions,
 }
 
+/// BroadcastRequest is params of broadcast command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BroadcastRequest {
     pub channels: Vec<String>,
     pub data: serde_json::Value,
+    #[serde(flatten)]
     pub options: PublishOptions,
 }
 
+/// SubscribeRequest is params of subscribe command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SubscribeRequest {
     pub channel: String,
     pub user: String,
+    #[serde(flatten)]
     pub options: SubscribeOptions,
 }
 
+/// UnsubscribeRequest is params of unsubscribe command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UnsubscribeRequest {
     pub channel: String,
     pub user: String,
+    #[serde(flatten)]
     pub options: UnsubscribeOptions,
 }
 
+/// DisconnectRequest is params of disconnect command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DisconnectRequest {
     pub user: String,
+    #[serde(flatten)]
     pub options: DisconnectOptions,
 }
 
+/// HistoryRequest is params of history command, options are flattened into
+/// params as server expects them.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HistoryRequest {
     pub channel: String,
+    #[serde(flatten)]
     pub options: HistoryOptions,
 }
 
+/// PresenceRequest is params of presence command.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PresenceRequest {
+    pub channel: String,
+}
+
+/// PresenceStatsRequest is params of presence_stats command.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PresenceStatsRequest {
+    pub channel: String,
+}
+
+/// HistoryRemoveRequest is params of history_remove command.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryRemoveRequest {
+    pub channel: String,
+}
+
+/// InfoRequest is params of info command.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InfoRequest {}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChannelsRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
 }
 
-/// # Request Kinds
-/// This are types for params in Command struct
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RefreshRequest {
+    pub user: String,
+    #[serde(flatten)]
+    pub options: RefreshOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectionsRequest {
+    #[serde(flatten)]
+    pub options: ConnectionsOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateUserStatusRequest {
+    pub users: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetUserStatusRequest {
+    pub users: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteUserStatusRequest {
+    pub users: Vec<String>,
+}
+
+/// DeviceRegisterRequest registers device for push notifications (or updates
+/// existing one when id is set).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceRegisterRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// provider is one of fcm, hms, apns.
+    pub provider: String,
+    pub token: String,
+    /// platform is one of ios, android, web.
+    pub platform: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceUserUpdate {
+    pub user: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceMetaUpdate {
+    pub meta: HashMap<String, String>,
+}
+
+/// DeviceTopicsUpdate changes device topics, op is one of add, remove, set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceTopicsUpdate {
+    pub op: String,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceUpdateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_update: Option<DeviceUserUpdate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta_update: Option<DeviceMetaUpdate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics_update: Option<DeviceTopicsUpdate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceRemoveRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub providers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platforms: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceListRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<DeviceFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_total_count: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_meta: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_topics: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceTopicFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_providers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_platforms: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic_prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceTopicListRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<DeviceTopicFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_total_count: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_device: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i32>,
+}
+
+/// DeviceTopicUpdateRequest changes topics of device, op is one of add, remove, set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceTopicUpdateRequest {
+    pub device_id: String,
+    pub op: String,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UserTopicFilter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topics: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic_prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UserTopicListRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<UserTopicFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_total_count: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i32>,
+}
+
+/// UserTopicUpdateRequest changes topics of user, op is one of add, remove, set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UserTopicUpdateRequest {
+    pub user: String,
+    pub op: String,
+    pub topics: Vec<String>,
+}
+
+/// PushRecipient defines whom push notification is sent to, exactly one field should be set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PushRecipient {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<DeviceFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fcm_tokens: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fcm_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fcm_condition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hms_tokens: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hms_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hms_condition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apns_tokens: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FcmPushNotification {
+    pub message: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HmsPushNotification {
+    pub message: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ApnsPushNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+    pub payload: serde_json::Value,
+}
+
+/// PushNotification contains provider specific payloads of push notification.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PushNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fcm: Option<FcmPushNotification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hms: Option<HmsPushNotification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apns: Option<ApnsPushNotification>,
+    #[serde(
+        default,
+        with = "crate::timestamp::unix_seconds::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expire_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SendPushNotificationRequest {
+    pub recipient: PushRecipient,
+    pub notification: PushNotification,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    #[serde(
+        default,
+        with = "crate::timestamp::unix_seconds::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub send_at: Option<Timestamp>,
+}
+
+/// RateLimitRequest consumes score from token bucket identified by key, which
+/// allows rate tokens per interval (in milliseconds).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RateLimitRequest {
+    pub key: String,
+    pub rate: i64,
+    pub interval: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i64>,
+}
+
+/// # Request Kinds
+/// This are types for params in Command struct. Params are serialized as is,
+/// they are decoded only as part of Command according to its method, see
+/// RequestKind::method.
+#[derive(Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum RequestKind {
     ChannelsRequest(ChannelsRequest),
@@ -70,32 +394,549 @@ pub enum RequestKind {
     SubscribeRequest(SubscribeRequest),
     UnsubscribeRequest(UnsubscribeRequest),
     DisconnectRequest(DisconnectRequest),
+    PresenceRequest(PresenceRequest),
+    PresenceStatsRequest(PresenceStatsRequest),
     HistoryRequest(HistoryRequest),
+    HistoryRemoveRequest(HistoryRemoveRequest),
+    InfoRequest(InfoRequest),
+    RefreshRequest(RefreshRequest),
+    ConnectionsRequest(ConnectionsRequest),
+    UpdateUserStatusRequest(UpdateUserStatusRequest),
+    GetUserStatusRequest(GetUserStatusRequest),
+    DeleteUserStatusRequest(DeleteUserStatusRequest),
+    DeviceRegisterRequest(DeviceRegisterRequest),
+    DeviceUpdateRequest(DeviceUpdateRequest),
+    DeviceRemoveRequest(DeviceRemoveRequest),
+    DeviceListRequest(DeviceListRequest),
+    DeviceTopicListRequest(DeviceTopicListRequest),
+    DeviceTopicUpdateRequest(DeviceTopicUpdateRequest),
+    UserTopicListRequest(UserTopicListRequest),
+    UserTopicUpdateRequest(UserTopicUpdateRequest),
+    SendPushNotificationRequest(Box<SendPushNotificationRequest>),
+    RateLimitRequest(RateLimitRequest),
+    /// Value is params of command with Method::Custom.
     Value(serde_json::Value),
 }
+
+impl RequestKind {
+    /// method returns method params belong to, None for Value which can be
+    /// sent only with Method::Custom.
+    pub fn method(&self) -> Option<Method> {
+        let method = match self {
+            RequestKind::ChannelsRequest(_) => Method::Channels,
+            RequestKind::PublishRequest(_) => Method::Publish,
+            RequestKind::BroadcastRequest(_) => Method::Broadcast,
+            RequestKind::SubscribeRequest(_) => Method::Subscribe,
+            RequestKind::UnsubscribeRequest(_) => Method::Unsubscribe,
+            RequestKind::DisconnectRequest(_) => Method::Disconnect,
+            RequestKind::PresenceRequest(_) => Method::Presence,
+            RequestKind::PresenceStatsRequest(_) => Method::PresenceStats,
+            RequestKind::HistoryRequest(_) => Method::History,
+            RequestKind::HistoryRemoveRequest(_) => Method::HistoryRemove,
+            RequestKind::InfoRequest(_) => Method::Info,
+            RequestKind::RefreshRequest(_) => Method::Refresh,
+            RequestKind::ConnectionsRequest(_) => Method::Connections,
+            RequestKind::UpdateUserStatusRequest(_) => Method::UpdateUserStatus,
+            RequestKind::GetUserStatusRequest(_) => Method::GetUserStatus,
+            RequestKind::DeleteUserStatusRequest(_) => Method::DeleteUserStatus,
+            RequestKind::DeviceRegisterRequest(_) => Method::DeviceRegister,
+            RequestKind::DeviceUpdateRequest(_) => Method::DeviceUpdate,
+            RequestKind::DeviceRemoveRequest(_) => Method::DeviceRemove,
+            RequestKind::DeviceListRequest(_) => Method::DeviceList,
+            RequestKind::DeviceTopicListRequest(_) => Method::DeviceTopicList,
+            RequestKind::DeviceTopicUpdateRequest(_) => Method::DeviceTopicUpdate,
+            RequestKind::UserTopicListRequest(_) => Method::UserTopicList,
+            RequestKind::UserTopicUpdateRequest(_) => Method::UserTopicUpdate,
+            RequestKind::SendPushNotificationRequest(_) => Method::SendPushNotification,
+            RequestKind::RateLimitRequest(_) => Method::RateLimit,
+            RequestKind::Value(_) => return None,
+        };
+        Some(method)
+    }
+}
+
+/// ErrMethodMismatch is returned when command params don't belong to its method.
+#[derive(Clone, Debug)]
+pub struct ErrMethodMismatch {
+    pub method: Method,
+    /// params_method is a method params belong to, None for RequestKind::Value.
+    pub params_method: Option<Method>,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrMethodMismatch`
+impl fmt::Display for ErrMethodMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.params_method {
+            Some(params_method) => write!(
+                f,
+                "{} command can't have params of {} command",
+                self.method, params_method
+            ),
+            None => write!(
+                f,
+                "{} command can't have untyped params, they are allowed only for custom methods",
+                self.method
+            ),
+        }
+    }
+}
+
+// Implement the `Error` trait for `ErrMethodMismatch`
+impl Error for ErrMethodMismatch {}
+/// Method is a method of API command, serialized as its name in server API.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Publish,
+    Broadcast,
+    Subscribe,
+    Unsubscribe,
+    Disconnect,
+    Presence,
+    PresenceStats,
+    History,
+    HistoryRemove,
+    Channels,
+    Info,
+    Refresh,
+    Connections,
+    UpdateUserStatus,
+    GetUserStatus,
+    DeleteUserStatus,
+    DeviceRegister,
+    DeviceUpdate,
+    DeviceRemove,
+    DeviceList,
+    DeviceTopicList,
+    DeviceTopicUpdate,
+    UserTopicList,
+    UserTopicUpdate,
+    SendPushNotification,
+    RateLimit,
+    /// Custom is a method unknown to client, e.g. added in newer server.
+    Custom(String),
+}
+
+impl Method {
+    /// as_str returns name of method in server API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Publish => "publish",
+            Method::Broadcast => "broadcast",
+            Method::Subscribe => "subscribe",
+            Method::Unsubscribe => "unsubscribe",
+            Method::Disconnect => "disconnect",
+            Method::Presence => "presence",
+            Method::PresenceStats => "presence_stats",
+            Method::History => "history",
+            Method::HistoryRemove => "history_remove",
+            Method::Channels => "channels",
+            Method::Info => "info",
+            Method::Refresh => "refresh",
+            Method::Connections => "connections",
+            Method::UpdateUserStatus => "update_user_status",
+            Method::GetUserStatus => "get_user_status",
+            Method::DeleteUserStatus => "delete_user_status",
+            Method::DeviceRegister => "device_register",
+            Method::DeviceUpdate => "device_update",
+            Method::DeviceRemove => "device_remove",
+            Method::DeviceList => "device_list",
+            Method::DeviceTopicList => "device_topic_list",
+            Method::DeviceTopicUpdate => "device_topic_update",
+            Method::UserTopicList => "user_topic_list",
+            Method::UserTopicUpdate => "user_topic_update",
+            Method::SendPushNotification => "send_push_notification",
+            Method::RateLimit => "rate_limit",
+            Method::Custom(method) => method,
+        }
+    }
+}
+
+impl From<&str> for Method {
+    fn from(method: &str) -> Self {
+        match method {
+            "publish" => Method::Publish,
+            "broadcast" => Method::Broadcast,
+            "subscribe" => Method::Subscribe,
+            "unsubscribe" => Method::Unsubscribe,
+            "disconnect" => Method::Disconnect,
+            "presence" => Method::Presence,
+            "presence_stats" => Method::PresenceStats,
+            "history" => Method::History,
+            "history_remove" => Method::HistoryRemove,
+            "channels" => Method::Channels,
+            "info" => Method::Info,
+            "refresh" => Method::Refresh,
+            "connections" => Method::Connections,
+            "update_user_status" => Method::UpdateUserStatus,
+            "get_user_status" => Method::GetUserStatus,
+            "delete_user_status" => Method::DeleteUserStatus,
+            "device_register" => Method::DeviceRegister,
+            "device_update" => Method::DeviceUpdate,
+            "device_remove" => Method::DeviceRemove,
+            "device_list" => Method::DeviceList,
+            "device_topic_list" => Method::DeviceTopicList,
+            "device_topic_update" => Method::DeviceTopicUpdate,
+            "user_topic_list" => Method::UserTopicList,
+            "user_topic_update" => Method::UserTopicUpdate,
+            "send_push_notification" => Method::SendPushNotification,
+            "rate_limit" => Method::RateLimit,
+            _ => Method::Custom(method.to_string()),
+        }
+    }
+}
+
+impl From<String> for Method {
+    fn from(method: String) -> Self {
+        match Method::from(method.as_str()) {
+            Method::Custom(_) => Method::Custom(method),
+            known => known,
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for Method {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Method {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Serialize for Method {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Method {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Method::from(String::deserialize(deserializer)?))
+    }
+}
+
 /// # Command
 /// Command represents API command to send
 ///
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
 pub struct Command {
-    pub method: String,
+    pub method: Method,
     pub params: RequestKind,
 }
 
+impl Command {
+    /// custom creates a command of method unknown to client, e.g. added in
+    /// newer server, params are sent as is.
+    pub fn custom(method: impl Into<String>, params: serde_json::Value) -> Command {
+        Command {
+            method: Method::Custom(method.into()),
+            params: RequestKind::Value(params),
+        }
+    }
+
+    /// validate checks that params belong to method.
+    pub fn validate(&self) -> Result<(), ErrMethodMismatch> {
+        let params_method = self.params.method();
+        let valid = match (&self.method, &params_method) {
+            (Method::Custom(_), None) => true,
+            (method, Some(params_method)) => method == params_method,
+            (_, None) => false,
+        };
+        if valid {
+            return Ok(());
+        }
+        Err(ErrMethodMismatch {
+            method: self.method.clone(),
+            params_method,
+        })
+    }
+}
+
+// RequestKind is untagged, so params are decoded according to method, otherwise
+// any object would match the first variant with all optional fields.
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawCommand {
+            method: Method,
+            params: serde_json::Value,
+        }
+
+        let raw = RawCommand::deserialize(deserializer)?;
+        let params = decode_params(&raw.method, raw.params).map_err(de::Error::custom)?;
+        Ok(Command {
+            method: raw.method,
+            params,
+        })
+    }
+}
+
+fn decode_params(method: &Method, params: serde_json::Value) -> serde_json::Result<RequestKind> {
+    let params = match method {
+        Method::Publish => RequestKind::PublishRequest(serde_json::from_value(params)?),
+        Method::Broadcast => RequestKind::BroadcastRequest(serde_json::from_value(params)?),
+        Method::Subscribe => RequestKind::SubscribeRequest(serde_json::from_value(params)?),
+        Method::Unsubscribe => RequestKind::UnsubscribeRequest(serde_json::from_value(params)?),
+        Method::Disconnect => RequestKind::DisconnectRequest(serde_json::from_value(params)?),
+        Method::History => RequestKind::HistoryRequest(serde_json::from_value(params)?),
+        Method::Channels => RequestKind::ChannelsRequest(serde_json::from_value(params)?),
+        Method::Refresh => RequestKind::RefreshRequest(serde_json::from_value(params)?),
+        Method::Connections => RequestKind::ConnectionsRequest(serde_json::from_value(params)?),
+        Method::UpdateUserStatus => {
+            RequestKind::UpdateUserStatusRequest(serde_json::from_value(params)?)
+        }
+        Method::GetUserStatus => RequestKind::GetUserStatusRequest(serde_json::from_value(params)?),
+        Method::DeleteUserStatus => {
+            RequestKind::DeleteUserStatusRequest(serde_json::from_value(params)?)
+        }
+        Method::DeviceRegister => {
+            RequestKind::DeviceRegisterRequest(serde_json::from_value(params)?)
+        }
+        Method::DeviceUpdate => RequestKind::DeviceUpdateRequest(serde_json::from_value(params)?),
+        Method::DeviceRemove => RequestKind::DeviceRemoveRequest(serde_json::from_value(params)?),
+        Method::DeviceList => RequestKind::DeviceListRequest(serde_json::from_value(params)?),
+        Method::DeviceTopicList => {
+            RequestKind::DeviceTopicListRequest(serde_json::from_value(params)?)
+        }
+        Method::DeviceTopicUpdate => {
+            RequestKind::DeviceTopicUpdateRequest(serde_json::from_value(params)?)
+        }
+        Method::UserTopicList => RequestKind::UserTopicListRequest(serde_json::from_value(params)?),
+        Method::UserTopicUpdate => {
+            RequestKind::UserTopicUpdateRequest(serde_json::from_value(params)?)
+        }
+        Method::SendPushNotification => {
+            RequestKind::SendPushNotificationRequest(serde_json::from_value(params)?)
+        }
+        Method::RateLimit => RequestKind::RateLimitRequest(serde_json::from_value(params)?),
+        Method::Presence => RequestKind::PresenceRequest(serde_json::from_value(params)?),
+        Method::PresenceStats => RequestKind::PresenceStatsRequest(serde_json::from_value(params)?),
+        Method::HistoryRemove => RequestKind::HistoryRemoveRequest(serde_json::from_value(params)?),
+        Method::Info => RequestKind::InfoRequest(serde_json::from_value(params)?),
+        Method::Custom(_) => RequestKind::Value(params),
+    };
+    Ok(params)
+}
+
+/// PipeCommandError is an error returned by server for one of pipe commands,
+/// annotated with position of command in pipe and its method.
+#[derive(Clone, Debug)]
+pub struct PipeCommandError {
+    pub index: usize,
+    pub method: Method,
+    pub error: protocol::Error,
+}
+
+// Implement the `std::fmt::Display` trait for `PipeCommandError`
+impl fmt::Display for PipeCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "command {} ({}) failed: {}",
+            self.index, self.method, self.error
+        )
+    }
+}
+
+// Implement the `Error` trait for `PipeCommandError`
+impl Error for PipeCommandError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// command_errors returns errors contained in replies annotated with index and
+/// method of commands they belong to, replies are expected in order of commands.
+pub fn command_errors(commands: &[Command], replies: &[Reply]) -> Vec<PipeCommandError> {
+    commands
+        .iter()
+        .zip(replies)
+        .enumerate()
+        .filter_map(|(index, (cmd, reply))| {
+            reply.error.as_ref().map(|error| PipeCommandError {
+                index,
+                method: cmd.method.clone(),
+                error: error.clone(),
+            })
+        })
+        .collect()
+}
+
 /// # Pipe
 impl Pipe {
     /// Reset allows to clear client command buffer
     pub fn reset(&self) {
-        let mut commands = self.commands.lock().unwrap();
+        let mut commands = self.lock_commands();
+        self.lock_encoded().clear();
         *commands = Vec::new();
     }
 
-    pub fn add(&self, cmd: Command) -> Result<(), Box<dyn Error>> {
-        let mut commands = self.commands.lock().map_err(|_| "Lock poisoned")?;
+    /// to_json serializes commands buffered in pipe, so they can be persisted or
+    /// shipped to another process and sent later with Pipe::from_json.
+    pub fn to_json(&self) -> Result<String, ErrRes> {
+        let commands = self.lock_commands();
+        Ok(serde_json::to_string(&*commands)?)
+    }
+
+    /// from_json creates pipe from commands serialized with Pipe::to_json.
+    pub fn from_json(json: &str) -> Result<Pipe, ErrRes> {
+        let commands: Vec<Command> = serde_json::from_str(json)?;
+        let encoded = commands
+            .iter()
+            .map(|cmd| Ok(Bytes::from(serde_json::to_vec(cmd)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        Ok(Pipe {
+            commands: Arc::new(Mutex::new(commands)),
+            encoded: Arc::new(Mutex::new(encoded)),
+        })
+    }
+
+    /// len returns number of buffered commands.
+    pub fn len(&self) -> usize {
+        self.lock_commands().len()
+    }
+
+    /// is_empty reports whether pipe has no buffered commands.
+    pub fn is_empty(&self) -> bool {
+        self.lock_commands().is_empty()
+    }
+
+    /// iter returns iterator over snapshot of buffered commands, pipe can be
+    /// modified while iterating.
+    pub fn iter(&self) -> std::vec::IntoIter<Command> {
+        self.lock_commands().clone().into_iter()
+    }
+
+    /// methods returns methods of buffered commands in order.
+    pub fn methods(&self) -> Vec<Method> {
+        self.lock_commands()
+            .iter()
+            .map(|cmd| cmd.method.clone())
+            .collect()
+    }
+
+    /// errors returns errors contained in replies to buffered commands, annotated
+    /// with index and method of command.
+    pub fn errors(&self, replies: &[Reply]) -> Vec<PipeCommandError> {
+        command_errors(&self.lock_commands(), replies)
+    }
+
+    /// remove removes command at idx and returns it, None is returned when idx
+    /// is out of bounds.
+    pub fn remove(&self, idx: usize) -> Option<Command> {
+        let mut commands = self.lock_commands();
+        if idx < commands.len() {
+            let mut encoded = self.lock_encoded();
+            if encoded.len() == commands.len() {
+                encoded.remove(idx);
+            } else {
+                encoded.clear();
+            }
+            Some(commands.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    /// commands returns copy of buffered commands.
+    pub fn commands(&self) -> Vec<Command> {
+        self.lock_commands().clone()
+    }
+
+    /// update allows to change buffered commands in place, e.g. to rewrite
+    /// channels before retrying pipe. Commands serialized when they were added
+    /// are dropped, so changed commands are serialized again when sent.
+    pub fn update<R>(&self, f: impl FnOnce(&mut Vec<Command>) -> R) -> R {
+        let mut commands = self.lock_commands();
+        self.lock_encoded().clear();
+        f(&mut commands)
+    }
+
+    /// snapshot returns copy of buffered commands and their body serialized
+    /// as newline delimited JSON, body is nil when cache of serialized commands
+    /// is out of sync, e.g. after commands were put back by send_pipe_owned.
+    pub(crate) fn snapshot(&self) -> (Vec<Command>, Option<Bytes>) {
+        let commands = self.lock_commands();
+        let encoded = self.lock_encoded();
+        if encoded.len() != commands.len() {
+            return (commands.clone(), None);
+        }
+
+        let size = encoded.iter().map(|cmd| cmd.len() + 1).sum();
+        let mut body = Vec::with_capacity(size);
+        for (i, cmd) in encoded.iter().enumerate() {
+            if i > 0 {
+                body.push(b'\n');
+            }
+            body.extend_from_slice(cmd);
+        }
+        (commands.clone(), Some(Bytes::from(body)))
+    }
+
+    fn lock_encoded(&self) -> MutexGuard<'_, Vec<Bytes>> {
+        self.encoded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// lock_commands locks command buffer ignoring poisoning, commands are only
+    /// pushed and moved as a whole, so buffer stays consistent when another
+    /// task panics holding the lock.
+    pub(crate) fn lock_commands(&self) -> MutexGuard<'_, Vec<Command>> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// take_commands moves buffered commands out of pipe leaving it empty.
+    pub fn take_commands(&self) -> Result<Vec<Command>, ErrRes> {
+        let mut commands = self.lock_commands();
+        self.lock_encoded().clear();
+        Ok(std::mem::take(&mut *commands))
+    }
+
+    /// add appends command to pipe, fails with ErrMethodMismatch when params
+    /// don't belong to command method.
+    pub fn add(&self, cmd: Command) -> Result<(), ErrRes> {
+        cmd.validate()?;
+        let encoded = Bytes::from(serde_json::to_vec(&cmd)?);
+        let mut commands = self.lock_commands();
+        let mut cache = self.lock_encoded();
+        // cache out of sync stays unused until it's cleared.
+        if cache.len() == commands.len() {
+            cache.push(encoded);
+        }
         commands.push(cmd);
         Ok(())
     }
 
+    /// AddCustom adds command of any method, e.g. PRO-only or experimental one.
+    /// Params of methods known to client are decoded into typed request, so
+    /// fields unknown to client are dropped, send_raw passes them as is.
+    pub fn add_custom(&self, method: &str, params: impl Serialize) -> Result<(), ErrRes> {
+        let method = Method::from(method);
+        let params = decode_params(&method, serde_json::to_value(params)?)?;
+        self.add(Command { method, params })
+    }
+
     /// AddPublish adds publish command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
     pub fn add_publish(
@@ -103,14 +944,25 @@ impl Pipe {
         channel: String,
         data: &str,
         opts: &[PublishOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let mut options = PublishOptions::default();
         for opt in opts {
-            opt(&mut options)
+            opt(&mut options);
         }
 
+        self.add_publish_with_options(channel, data, options)
+    }
+
+    /// AddPublishWithOptions is the same as add_publish but takes options built
+    /// with PublishOptions::new() instead of closures.
+    pub fn add_publish_with_options(
+        &self,
+        channel: String,
+        data: &str,
+        options: PublishOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "publish".to_string(),
+            method: Method::Publish,
             params: RequestKind::PublishRequest(PublishRequest {
                 channel,
                 data: serde_json::from_str(data)?,
@@ -118,7 +970,7 @@ impl Pipe {
             }),
         };
 
-        self.add(cmd.clone())?;
+        self.add(cmd)?;
         Ok(())
     }
 
@@ -129,20 +981,32 @@ impl Pipe {
         channels: Vec<String>,
         data: &str,
         opts: &[PublishOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let mut options = PublishOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_broadcast_with_options(channels, data, options)
+    }
+
+    /// AddBroadcastWithOptions is the same as add_broadcast but takes options built
+    /// with PublishOptions::new() instead of closures.
+    pub fn add_broadcast_with_options(
+        &self,
+        channels: Vec<String>,
+        data: &str,
+        options: PublishOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "broadcast".to_string(),
+            method: Method::Broadcast,
             params: RequestKind::BroadcastRequest(BroadcastRequest {
-                channels: channels,
+                channels,
                 data: serde_json::from_str(data)?,
                 options,
             }),
         };
+
         self.add(cmd)?;
         Ok(())
     }
@@ -154,20 +1018,32 @@ impl Pipe {
         channel: String,
         user: String,
         opts: &[SubscribeOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let mut options = SubscribeOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_subscribe_with_options(channel, user, options)
+    }
+
+    /// AddSubscribeWithOptions is the same as add_subscribe but takes options built
+    /// with SubscribeOptions::new() instead of closures.
+    pub fn add_subscribe_with_options(
+        &self,
+        channel: String,
+        user: String,
+        options: SubscribeOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "subscribe".to_string(),
+            method: Method::Subscribe,
             params: RequestKind::SubscribeRequest(SubscribeRequest {
                 channel,
                 user,
                 options,
             }),
         };
+
         self.add(cmd)?;
         Ok(())
     }
@@ -179,14 +1055,25 @@ impl Pipe {
         channel: String,
         user: String,
         opts: &[UnsubscribeOption],
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), ErrRes> {
         let mut options = UnsubscribeOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_unsubscribe_with_options(channel, user, options)
+    }
+
+    /// AddUnsubscribeWithOptions is the same as add_unsubscribe but takes options built
+    /// with UnsubscribeOptions::new() instead of closures.
+    pub fn add_unsubscribe_with_options(
+        &self,
+        channel: String,
+        user: String,
+        options: UnsubscribeOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "unsubscribe".to_string(),
+            method: Method::Unsubscribe,
             params: RequestKind::UnsubscribeRequest(UnsubscribeRequest {
                 channel,
                 user,
@@ -200,18 +1087,24 @@ impl Pipe {
 
     /// AddDisconnect adds disconnect command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_disconnect(
-        &self,
-        user: String,
-        opts: &[DisconnectOption],
-    ) -> Result<(), Box<dyn Error>> {
+    pub fn add_disconnect(&self, user: String, opts: &[DisconnectOption]) -> Result<(), ErrRes> {
         let mut options = DisconnectOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_disconnect_with_options(user, options)
+    }
+
+    /// AddDisconnectWithOptions is the same as add_disconnect but takes options built
+    /// with DisconnectOptions::new() instead of closures.
+    pub fn add_disconnect_with_options(
+        &self,
+        user: String,
+        options: DisconnectOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "disconnect".to_string(),
+            method: Method::Disconnect,
             params: RequestKind::DisconnectRequest(DisconnectRequest { user, options }),
         };
 
@@ -221,12 +1114,10 @@ impl Pipe {
 
     /// AddPresence adds presence command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_presence(&self, channel: String) -> Result<(), Box<dyn Error>> {
+    pub fn add_presence(&self, channel: String) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "presence".to_string(),
-            params: RequestKind::Value(serde_json::json!({
-                "channel": channel,
-            })),
+            method: Method::Presence,
+            params: RequestKind::PresenceRequest(PresenceRequest { channel }),
         };
 
         self.add(cmd)?;
@@ -235,12 +1126,10 @@ impl Pipe {
 
     /// AddPresenceStats adds presence stats command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_presence_stats(&self, channel: String) -> Result<(), Box<dyn Error>> {
+    pub fn add_presence_stats(&self, channel: String) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "presence_stats".to_string(),
-            params: RequestKind::Value(serde_json::json!({
-                "channel": channel,
-            })),
+            method: Method::PresenceStats,
+            params: RequestKind::PresenceStatsRequest(PresenceStatsRequest { channel }),
         };
         self.add(cmd)?;
         Ok(())
@@ -248,18 +1137,24 @@ impl Pipe {
 
     /// AddHistory adds history command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_history(
-        &self,
-        channel: String,
-        opts: &[HistoryOption],
-    ) -> Result<(), Box<dyn Error>> {
+    pub fn add_history(&self, channel: String, opts: &[HistoryOption]) -> Result<(), ErrRes> {
         let mut options = HistoryOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_history_with_options(channel, options)
+    }
+
+    /// AddHistoryWithOptions is the same as add_history but takes options built
+    /// with HistoryOptions::new() instead of closures.
+    pub fn add_history_with_options(
+        &self,
+        channel: String,
+        options: HistoryOptions,
+    ) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "history".to_string(),
+            method: Method::History,
             params: RequestKind::HistoryRequest(HistoryRequest { channel, options }),
         };
 
@@ -269,12 +1164,10 @@ impl Pipe {
 
     /// AddHistoryRemove adds history remove command to client command buffer but not
     /// actually sends request to server until Pipe will be explicitly sent.
-    pub fn add_history_remove(&self, channel: String) -> Result<(), Box<dyn Error>> {
+    pub fn add_history_remove(&self, channel: String) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "history_remove".to_string(),
-            params: RequestKind::Value(serde_json::json!({
-                "channel": channel,
-            })),
+            method: Method::HistoryRemove,
+            params: RequestKind::HistoryRemoveRequest(HistoryRemoveRequest { channel }),
         };
 
         self.add(cmd)?;
@@ -283,14 +1176,20 @@ impl Pipe {
 
     /// AddChannels adds channels command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_channels(&self, opts: &[ChannelsOption]) -> Result<(), Box<dyn Error>> {
+    pub fn add_channels(&self, opts: &[ChannelsOption]) -> Result<(), ErrRes> {
         let mut options = ChannelsOptions::default();
         for opt in opts {
             opt(&mut options);
         }
 
+        self.add_channels_with_options(options)
+    }
+
+    /// AddChannelsWithOptions is the same as add_channels but takes options built
+    /// with ChannelsOptions::new() instead of closures.
+    pub fn add_channels_with_options(&self, options: ChannelsOptions) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "channels".to_string(),
+            method: Method::Channels,
             params: RequestKind::ChannelsRequest(ChannelsRequest {
                 pattern: options.pattern,
             }),
@@ -302,11 +1201,224 @@ impl Pipe {
 
     /// AddInfo adds info command to client command buffer but not actually
     /// sends request to server until Pipe will be explicitly sent.
-    pub fn add_info(&self) -> Result<(), Box<dyn Error>> {
+    pub fn add_info(&self) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::Info,
+            params: RequestKind::InfoRequest(InfoRequest {}),
+        };
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddRefresh adds refresh command to client command buffer but not actually
+    /// sends request to server until Pipe will be explicitly sent.
+    pub fn add_refresh(&self, user: String, opts: &[RefreshOption]) -> Result<(), ErrRes> {
+        let mut options = RefreshOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
+
+        self.add_refresh_with_options(user, options)
+    }
+
+    /// AddRefreshWithOptions is the same as add_refresh but takes options built
+    /// with RefreshOptions::new() instead of closures.
+    pub fn add_refresh_with_options(
+        &self,
+        user: String,
+        options: RefreshOptions,
+    ) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::Refresh,
+            params: RequestKind::RefreshRequest(RefreshRequest { user, options }),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddConnections adds connections command to client command buffer but not actually
+    /// sends request to server until Pipe will be explicitly sent.
+    pub fn add_connections(&self, opts: &[ConnectionsOption]) -> Result<(), ErrRes> {
+        let mut options = ConnectionsOptions::default();
+        for opt in opts {
+            opt(&mut options);
+        }
+
+        self.add_connections_with_options(options)
+    }
+
+    /// AddConnectionsWithOptions is the same as add_connections but takes options built
+    /// with ConnectionsOptions::new() instead of closures.
+    pub fn add_connections_with_options(&self, options: ConnectionsOptions) -> Result<(), ErrRes> {
         let cmd = Command {
-            method: "info".to_string(),
-            params: RequestKind::Value(serde_json::json!({})),
+            method: Method::Connections,
+            params: RequestKind::ConnectionsRequest(ConnectionsRequest { options }),
         };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddUpdateUserStatus adds update user status command to client command buffer
+    /// but not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_update_user_status(
+        &self,
+        users: Vec<String>,
+        state: Option<String>,
+    ) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::UpdateUserStatus,
+            params: RequestKind::UpdateUserStatusRequest(UpdateUserStatusRequest { users, state }),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddGetUserStatus adds get user status command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_get_user_status(&self, users: Vec<String>) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::GetUserStatus,
+            params: RequestKind::GetUserStatusRequest(GetUserStatusRequest { users }),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeleteUserStatus adds delete user status command to client command buffer
+    /// but not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_delete_user_status(&self, users: Vec<String>) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeleteUserStatus,
+            params: RequestKind::DeleteUserStatusRequest(DeleteUserStatusRequest { users }),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceRegister adds device register command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_register(&self, req: DeviceRegisterRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceRegister,
+            params: RequestKind::DeviceRegisterRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceUpdate adds device update command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_update(&self, req: DeviceUpdateRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceUpdate,
+            params: RequestKind::DeviceUpdateRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceRemove adds device remove command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_remove(&self, req: DeviceRemoveRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceRemove,
+            params: RequestKind::DeviceRemoveRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceList adds device list command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_list(&self, req: DeviceListRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceList,
+            params: RequestKind::DeviceListRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceTopicList adds device topic list command to client command buffer but
+    /// not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_topic_list(&self, req: DeviceTopicListRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceTopicList,
+            params: RequestKind::DeviceTopicListRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddDeviceTopicUpdate adds device topic update command to client command buffer
+    /// but not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_device_topic_update(&self, req: DeviceTopicUpdateRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::DeviceTopicUpdate,
+            params: RequestKind::DeviceTopicUpdateRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddUserTopicList adds user topic list command to client command buffer but not
+    /// actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_user_topic_list(&self, req: UserTopicListRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::UserTopicList,
+            params: RequestKind::UserTopicListRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddUserTopicUpdate adds user topic update command to client command buffer but
+    /// not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_user_topic_update(&self, req: UserTopicUpdateRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::UserTopicUpdate,
+            params: RequestKind::UserTopicUpdateRequest(req),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddSendPushNotification adds send push notification command to client command
+    /// buffer but not actually sends request to server until Pipe will be explicitly sent.
+    pub fn add_send_push_notification(
+        &self,
+        req: SendPushNotificationRequest,
+    ) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::SendPushNotification,
+            params: RequestKind::SendPushNotificationRequest(Box::new(req)),
+        };
+
+        self.add(cmd)?;
+        Ok(())
+    }
+
+    /// AddRateLimit adds rate limit command to client command buffer but not actually
+    /// sends request to server until Pipe will be explicitly sent.
+    pub fn add_rate_limit(&self, req: RateLimitRequest) -> Result<(), ErrRes> {
+        let cmd = Command {
+            method: Method::RateLimit,
+            params: RequestKind::RateLimitRequest(req),
+        };
+
         self.add(cmd)?;
         Ok(())
     }
@@ -0,0 +1,20 @@
+pub use crate::client::{CallOptions, Client, Config, ErrRes, ErrStatusCode};
+pub use crate::error::ErrorExt;
+pub use crate::options::{
+    with_auto_idempotency_key, with_connections_expression, with_connections_user, with_delta,
+    with_disconnect, with_disconnect_client, with_disconnect_client_whitelist,
+    with_idempotency_key, with_join_leave, with_limit, with_pattern, with_position, with_presence,
+    with_recover, with_recover_since, with_refresh_client, with_refresh_expire_at,
+    with_refresh_expire_at_time, with_refresh_expire_in, with_refresh_expired,
+    with_refresh_session, with_reverse, with_since, with_skip_history, with_subscribe_client,
+    with_subscribe_data, with_subscribe_info, with_tags, with_unsubscribe_client, Disconnect,
+    DisconnectOptions, HistoryOptions, PublishOptions, RefreshOptions, SubscribeOptions,
+    UnsubscribeOptions,
+};
+pub use crate::pipe::Pipe;
+pub use crate::protocol::{
+    BroadcastResult, ChannelsResult, ConnectionsResult, HistoryResult, InfoResult, PresenceResult,
+    PresenceStatsResult, Publication, PublishResult, Reply,
+};
+pub use crate::publisher::Publisher;
+pub use crate::Result;
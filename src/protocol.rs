@@ -1,6 +1,13 @@
+use crate::options::StreamPosition;
+use crate::pattern::namespace;
+use crate::timestamp::Timestamp;
+use base64::Engine;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::Deref;
 
 /// Error reptrests API request error.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -24,9 +31,108 @@ pub struct Reply {
     pub result: Option<serde_json::Value>,
 }
 
+impl Reply {
+    /// into_result converts reply into result or error returned by server,
+    /// missing result is returned as null.
+    pub fn into_result(self) -> Result<serde_json::Value, Error> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.result.unwrap_or(serde_json::Value::Null)),
+        }
+    }
+}
+
+/// Replies wraps replies to pipe commands, kept in order of commands.
+#[derive(Debug, Default)]
+pub struct Replies(pub Vec<Reply>);
+
+impl Replies {
+    /// errors returns iterator over errors returned by server together with
+    /// index of command they belong to.
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &Error)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, reply)| reply.error.as_ref().map(|err| (index, err)))
+    }
+
+    /// has_errors reports whether any command failed.
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(|reply| reply.error.is_some())
+    }
+
+    /// into_results converts every reply with Reply::into_result.
+    pub fn into_results(self) -> Vec<Result<serde_json::Value, Error>> {
+        self.0.into_iter().map(Reply::into_result).collect()
+    }
+}
+
+impl From<Vec<Reply>> for Replies {
+    fn from(replies: Vec<Reply>) -> Self {
+        Replies(replies)
+    }
+}
+
+impl Deref for Replies {
+    type Target = [Reply];
+
+    fn deref(&self) -> &[Reply] {
+        &self.0
+    }
+}
+
+impl IntoIterator for Replies {
+    type Item = Reply;
+    type IntoIter = std::vec::IntoIter<Reply>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+// DataError is returned when data of publication or client info can't be
+// decoded into requested type.
+#[derive(Debug)]
+pub struct DataError {
+    /// field is a name of decoded field, e.g. data.
+    pub field: &'static str,
+    /// type_name is a name of requested type.
+    pub type_name: &'static str,
+    pub source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+// Implement the `std::fmt::Display` trait for `DataError`
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to decode {} as {}: {}",
+            self.field, self.type_name, self.source
+        )
+    }
+}
+
+// Implement the `Error` trait for `DataError`
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+fn decode_field<T: DeserializeOwned>(
+    field: &'static str,
+    value: &serde_json::Value,
+) -> Result<T, DataError> {
+    T::deserialize(value).map_err(|source| DataError {
+        field,
+        type_name: std::any::type_name::<T>(),
+        source: Box::new(source),
+    })
+}
+
 /// ClientInfo represents information about one client connection to centrifugo.
 /// This struct used in messages published by clients, join/leave events, presence data
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ClientInfo {
     pub user: String,
     pub client: String,
@@ -36,14 +142,89 @@ pub struct ClientInfo {
     pub chan_info: Option<serde_json::Value>,
 }
 
+impl ClientInfo {
+    /// conn_info_as decodes connection info into T, None if it's not set.
+    pub fn conn_info_as<T: DeserializeOwned>(&self) -> Result<Option<T>, DataError> {
+        self.conn_info
+            .as_ref()
+            .map(|info| decode_field("conn_info", info))
+            .transpose()
+    }
+
+    /// chan_info_as decodes channel info into T, None if it's not set.
+    pub fn chan_info_as<T: DeserializeOwned>(&self) -> Result<Option<T>, DataError> {
+        self.chan_info
+            .as_ref()
+            .map(|info| decode_field("chan_info", info))
+            .transpose()
+    }
+}
+
+/// PayloadData is a payload of publication, JSON or binary one sent as b64data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadData {
+    Json(serde_json::Value),
+    Binary(Vec<u8>),
+}
+
 /// Publication represents message published into channel.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Publication {
+    #[serde(default)]
     pub offset: u64,
+    /// data is a JSON payload, null for binary publications.
+    #[serde(default)]
     pub data: serde_json::Value,
+    /// b64data is a base64 encoded binary payload, set instead of data in
+    /// channels with binary payloads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub b64data: Option<String>,
     pub info: Option<ClientInfo>,
 }
 
+impl Publication {
+    /// data_as decodes data into T, e.g. for publications from history.
+    pub fn data_as<T: DeserializeOwned>(&self) -> Result<T, DataError> {
+        decode_field("data", &self.data)
+    }
+
+    /// data_raw returns data as raw JSON, so proxies can pass it through
+    /// without decoding into own types.
+    pub fn data_raw(&self) -> Result<Box<RawValue>, DataError> {
+        serde_json::value::to_raw_value(&self.data).map_err(|source| DataError {
+            field: "data",
+            type_name: std::any::type_name::<RawValue>(),
+            source: Box::new(source),
+        })
+    }
+
+    /// is_binary reports whether publication carries binary payload.
+    pub fn is_binary(&self) -> bool {
+        self.b64data.is_some()
+    }
+
+    /// binary_data decodes b64data, None for JSON publications.
+    pub fn binary_data(&self) -> Option<Result<Vec<u8>, DataError>> {
+        self.b64data.as_ref().map(|b64data| {
+            base64::engine::general_purpose::STANDARD
+                .decode(b64data)
+                .map_err(|source| DataError {
+                    field: "b64data",
+                    type_name: std::any::type_name::<Vec<u8>>(),
+                    source: Box::new(source),
+                })
+        })
+    }
+
+    /// payload returns binary payload when b64data is set, JSON data otherwise.
+    pub fn payload(&self) -> Result<PayloadData, DataError> {
+        match self.binary_data() {
+            Some(data) => data.map(PayloadData::Binary),
+            None => Ok(PayloadData::Json(self.data.clone())),
+        }
+    }
+}
+
 /// NodeInfo contains information and statistics about Centrifugo node.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NodeInfo {
@@ -61,6 +242,39 @@ pub struct NodeInfo {
     pub num_channels: u32,
     /// uptime of node in seconds.
     pub uptime: u32,
+    /// num_subs is a number of client subscriptions on node. Nil value means
+    /// server didn't send it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_subs: Option<u32>,
+    /// process contains resource usage of node process. Nil value means server
+    /// didn't send it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<ProcessInfo>,
+    /// metrics contains node metrics. Nil value means server didn't send them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<NodeMetrics>,
+}
+
+/// ProcessInfo contains resource usage of Centrifugo node process.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProcessInfo {
+    /// cpu is a CPU usage of process in percents.
+    #[serde(default)]
+    pub cpu: f64,
+    /// rss is a resident set size of process in bytes.
+    #[serde(default)]
+    pub rss: u64,
+}
+
+/// NodeMetrics contains metrics Centrifugo node collected over interval.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NodeMetrics {
+    /// interval of metrics collection in seconds.
+    #[serde(default)]
+    pub interval: f64,
+    /// items are metric values by name.
+    #[serde(default)]
+    pub items: HashMap<String, f64>,
 }
 
 /// Info Result is a reulst of info command
@@ -69,16 +283,90 @@ pub struct InfoResult {
     pub nodes: Vec<NodeInfo>,
 }
 
+/// NodeTotals is a cluster-wide sum of node statistics, see InfoResult::totals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeTotals {
+    pub num_nodes: usize,
+    pub num_clients: u64,
+    pub num_users: u64,
+    pub num_channels: u64,
+}
+
+impl InfoResult {
+    /// totals sums statistics of all nodes. Users and channels present on
+    /// several nodes are counted several times.
+    pub fn totals(&self) -> NodeTotals {
+        self.nodes
+            .iter()
+            .fold(NodeTotals::default(), |mut totals, node| {
+                totals.num_nodes += 1;
+                totals.num_clients += u64::from(node.num_clients);
+                totals.num_users += u64::from(node.num_users);
+                totals.num_channels += u64::from(node.num_channels);
+                totals
+            })
+    }
+
+    /// node_by_name returns node with name.
+    pub fn node_by_name(&self, name: &str) -> Option<&NodeInfo> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// min_version returns the oldest version of Centrifugo running in cluster.
+    pub fn min_version(&self) -> Option<&str> {
+        self.nodes
+            .iter()
+            .map(|node| node.version.as_str())
+            .min_by_key(|version| version_key(version))
+    }
+
+    /// max_version returns the newest version of Centrifugo running in cluster.
+    pub fn max_version(&self) -> Option<&str> {
+        self.nodes
+            .iter()
+            .map(|node| node.version.as_str())
+            .max_by_key(|version| version_key(version))
+    }
+
+    /// has_mixed_versions reports whether nodes run different versions, e.g.
+    /// during rolling upgrade.
+    pub fn has_mixed_versions(&self) -> bool {
+        self.min_version() != self.max_version()
+    }
+}
+
+// version_key makes version comparable by numeric components, so 5.10.0 is
+// newer than 5.9.1 and pre-release like 5.10.0-rc1 is older than 5.10.0.
+fn version_key(version: &str) -> (Vec<u64>, bool) {
+    let version = version.trim_start_matches('v');
+    let release = version.split('+').next().unwrap_or_default();
+    let (numbers, pre_release) = match release.split_once('-') {
+        Some((numbers, _)) => (numbers, true),
+        None => (release, false),
+    };
+    let numbers = numbers
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (numbers, !pre_release)
+}
+
 /// PublishResult is a result of publish command
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct PublishResult {
     pub offset: Option<u64>,
     pub epoch: Option<String>,
+    /// idempotency_key is a key publication was sent with, e.g. generated with
+    /// with_auto_idempotency_key. Filled by client, not sent by server.
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PublishResponse {
     pub error: Option<Error>,
+    /// result is empty when publication into channel failed with error.
+    #[serde(default)]
     pub result: PublishResult,
 }
 
@@ -109,6 +397,65 @@ pub struct HistoryResult {
     pub epoch: String,
 }
 
+impl HistoryResult {
+    /// iter returns iterator over publications in order returned by server.
+    pub fn iter(&self) -> std::slice::Iter<'_, Publication> {
+        self.publication.iter()
+    }
+
+    /// len returns number of publications.
+    pub fn len(&self) -> usize {
+        self.publication.len()
+    }
+
+    /// is_empty reports whether there are no publications.
+    pub fn is_empty(&self) -> bool {
+        self.publication.is_empty()
+    }
+
+    /// latest returns publication with the highest offset, regardless of order
+    /// history was requested in.
+    pub fn latest(&self) -> Option<&Publication> {
+        self.publication
+            .iter()
+            .max_by_key(|publication| publication.offset)
+    }
+
+    /// offsets returns offsets of publications in order returned by server.
+    pub fn offsets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.publication
+            .iter()
+            .map(|publication| publication.offset)
+    }
+
+    /// position returns current top position of channel stream, e.g. to pass
+    /// to with_since or with_recover_since later.
+    pub fn position(&self) -> StreamPosition {
+        StreamPosition {
+            offset: Some(self.offset),
+            epoch: Some(self.epoch.clone()),
+        }
+    }
+}
+
+impl IntoIterator for HistoryResult {
+    type Item = Publication;
+    type IntoIter = std::vec::IntoIter<Publication>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.publication.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a HistoryResult {
+    type Item = &'a Publication;
+    type IntoIter = std::slice::Iter<'a, Publication>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.publication.iter()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ChannelInfo {
     pub num_clients: u32,
@@ -118,3 +465,228 @@ pub struct ChannelInfo {
 pub struct ChannelsResult {
     pub channels: HashMap<String, ChannelInfo>,
 }
+
+/// NamespaceStats aggregates channels of one namespace, see ChannelsResult::namespaces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NamespaceStats {
+    pub num_channels: usize,
+    pub num_clients: u64,
+}
+
+impl ChannelsResult {
+    /// total_clients returns number of clients subscribed to all channels, client
+    /// subscribed to several channels is counted several times.
+    pub fn total_clients(&self) -> u64 {
+        self.channels
+            .values()
+            .map(|info| u64::from(info.num_clients))
+            .sum()
+    }
+
+    /// sorted_by_clients returns channels with number of clients, the most
+    /// populated first, channels with equal number of clients sorted by name.
+    pub fn sorted_by_clients(&self) -> Vec<(&str, u32)> {
+        let mut channels: Vec<(&str, u32)> = self
+            .channels
+            .iter()
+            .map(|(channel, info)| (channel.as_str(), info.num_clients))
+            .collect();
+        channels.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        channels
+    }
+
+    /// top_n returns at most n the most populated channels.
+    pub fn top_n(&self, n: usize) -> Vec<(&str, u32)> {
+        let mut channels = self.sorted_by_clients();
+        channels.truncate(n);
+        channels
+    }
+
+    /// namespaces groups channels by namespace, None is a key of channels in
+    /// default namespace.
+    pub fn namespaces(&self) -> HashMap<Option<&str>, NamespaceStats> {
+        let mut namespaces: HashMap<Option<&str>, NamespaceStats> = HashMap::new();
+        for (channel, info) in &self.channels {
+            let stats = namespaces.entry(namespace(channel)).or_default();
+            stats.num_channels += 1;
+            stats.num_clients += u64::from(info.num_clients);
+        }
+        namespaces
+    }
+}
+
+/// ConnectionInfo contains information about connection of a user.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConnectionInfo {
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub transport: String,
+    #[serde(default)]
+    pub protocol: String,
+    #[serde(default)]
+    pub user: String,
+    /// state is a connection state: channels, tokens and meta.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<serde_json::Value>,
+}
+
+/// ConnectionsResult is a result of connections command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConnectionsResult {
+    #[serde(default)]
+    pub connections: HashMap<String, ConnectionInfo>,
+}
+
+/// UserStatus contains last activity information of user.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserStatus {
+    pub user: String,
+    /// active is a moment of last user activity.
+    #[serde(
+        default = "crate::timestamp::unix_epoch",
+        with = "crate::timestamp::unix_seconds"
+    )]
+    pub active: Timestamp,
+    /// online is a moment user was last seen online.
+    #[serde(
+        default = "crate::timestamp::unix_epoch",
+        with = "crate::timestamp::unix_seconds"
+    )]
+    pub online: Timestamp,
+    #[serde(default)]
+    pub state: String,
+}
+
+/// GetUserStatusResult is a result of get_user_status command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetUserStatusResult {
+    #[serde(default)]
+    pub statuses: Vec<UserStatus>,
+}
+
+/// DeviceRegisterResult is a result of device_register command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceRegisterResult {
+    pub id: String,
+}
+
+/// Device represents device registered for push notifications.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Device {
+    pub id: String,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+    #[serde(default)]
+    pub meta: HashMap<String, String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+/// DeviceListResult is a result of device_list command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceListResult {
+    #[serde(default)]
+    pub items: Vec<Device>,
+    #[serde(default)]
+    pub next_cursor: String,
+    #[serde(default)]
+    pub total_count: i64,
+}
+
+/// DeviceTopic represents topic device is subscribed to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceTopic {
+    pub id: String,
+    pub topic: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<Device>,
+}
+
+/// DeviceTopicListResult is a result of device_topic_list command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeviceTopicListResult {
+    #[serde(default)]
+    pub items: Vec<DeviceTopic>,
+    #[serde(default)]
+    pub next_cursor: String,
+    #[serde(default)]
+    pub total_count: i64,
+}
+
+/// UserTopic represents topic user is subscribed to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserTopic {
+    pub id: String,
+    pub user: String,
+    pub topic: String,
+}
+
+/// UserTopicListResult is a result of user_topic_list command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserTopicListResult {
+    #[serde(default)]
+    pub items: Vec<UserTopic>,
+    #[serde(default)]
+    pub next_cursor: String,
+    #[serde(default)]
+    pub total_count: i64,
+}
+
+/// SendPushNotificationResult is a result of send_push_notification command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SendPushNotificationResult {
+    /// uid of push notification, can be used to track it.
+    pub uid: String,
+}
+
+/// RateLimitResult is a result of rate_limit command
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RateLimitResult {
+    #[serde(default)]
+    pub allowed: bool,
+    #[serde(default)]
+    pub tokens_left: i64,
+    /// allowed_in is a number of milliseconds to wait until request is allowed.
+    #[serde(default)]
+    pub allowed_in: i64,
+    #[serde(default)]
+    pub server_time: i64,
+}
+
+/// TypedResult is a decoded result of pipe command, variant is chosen according
+/// to command method.
+#[derive(Debug)]
+pub enum TypedResult {
+    Publish(PublishResult),
+    Broadcast(BroadcastResult),
+    Presence(PresenceResult),
+    PresenceStats(PresenceStatsResult),
+    History(HistoryResult),
+    Channels(ChannelsResult),
+    Info(InfoResult),
+    Connections(ConnectionsResult),
+    GetUserStatus(GetUserStatusResult),
+    DeviceRegister(DeviceRegisterResult),
+    DeviceList(DeviceListResult),
+    DeviceTopicList(DeviceTopicListResult),
+    UserTopicList(UserTopicListResult),
+    SendPushNotification(SendPushNotificationResult),
+    RateLimit(RateLimitResult),
+    /// Empty is a result of commands returning no data, e.g. subscribe or disconnect.
+    Empty,
+    /// Raw is a result of commands with method unknown to client.
+    Raw(serde_json::Value),
+}
@@ -0,0 +1,85 @@
+use crate::client::{Client, ErrRes};
+use crate::options::PublishOptions;
+use crate::protocol::{BroadcastResult, PublishResponse, PublishResult};
+use std::future::Future;
+
+/// Publisher is a minimal publishing interface implemented by Client, so
+/// application code can be written against it and use NoopPublisher or own
+/// implementation in tests and local development.
+pub trait Publisher {
+    /// publish publishes data into channel.
+    fn publish(
+        &self,
+        channel: String,
+        data: &str,
+        options: PublishOptions,
+    ) -> impl Future<Output = Result<PublishResult, ErrRes>> + Send;
+
+    /// broadcast publishes the same data into many channels.
+    fn broadcast(
+        &self,
+        channels: Vec<String>,
+        data: &str,
+        options: PublishOptions,
+    ) -> impl Future<Output = Result<BroadcastResult, ErrRes>> + Send;
+}
+
+impl Publisher for Client {
+    fn publish(
+        &self,
+        channel: String,
+        data: &str,
+        options: PublishOptions,
+    ) -> impl Future<Output = Result<PublishResult, ErrRes>> + Send {
+        self.publish_with_options(channel, data, options)
+    }
+
+    fn broadcast(
+        &self,
+        channels: Vec<String>,
+        data: &str,
+        options: PublishOptions,
+    ) -> impl Future<Output = Result<BroadcastResult, ErrRes>> + Send {
+        self.broadcast_with_options(channels, data, options)
+    }
+}
+
+/// NoopPublisher drops everything published and reports success.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopPublisher;
+
+impl Publisher for NoopPublisher {
+    async fn publish(
+        &self,
+        _channel: String,
+        _data: &str,
+        options: PublishOptions,
+    ) -> Result<PublishResult, ErrRes> {
+        Ok(PublishResult {
+            offset: None,
+            epoch: None,
+            idempotency_key: options.idempotency_key,
+        })
+    }
+
+    async fn broadcast(
+        &self,
+        channels: Vec<String>,
+        _data: &str,
+        options: PublishOptions,
+    ) -> Result<BroadcastResult, ErrRes> {
+        Ok(BroadcastResult {
+            responses: channels
+                .iter()
+                .map(|_| PublishResponse {
+                    error: None,
+                    result: PublishResult {
+                        offset: None,
+                        epoch: None,
+                        idempotency_key: options.idempotency_key.clone(),
+                    },
+                })
+                .collect(),
+        })
+    }
+}
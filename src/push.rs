@@ -0,0 +1,325 @@
+use crate::pipe::{
+    ApnsPushNotification, FcmPushNotification, HmsPushNotification, PushNotification,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// FcmMessage is a typed FCM HTTP v1 message sent in PushNotification.fcm,
+/// target (token, topic or condition) is set by PushRecipient.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FcmMessage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification: Option<FcmNotification>,
+    /// data is an arbitrary payload, FCM accepts string values only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android: Option<AndroidConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FcmNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AndroidPriority {
+    Normal,
+    High,
+}
+
+/// AndroidConfig contains Android specific options of FCM message.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AndroidConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collapse_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<AndroidPriority>,
+    /// ttl is a duration in seconds with s suffix, e.g. 3600s, see ttl_string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification: Option<AndroidNotification>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AndroidNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_action: Option<String>,
+}
+
+/// ttl_string formats duration as expected by AndroidConfig.ttl and
+/// HmsAndroidConfig.ttl, e.g. 3600s.
+pub fn ttl_string(ttl: Duration) -> String {
+    format!("{}s", ttl.as_secs())
+}
+
+impl FcmMessage {
+    /// new returns empty message to be filled with chainable setters.
+    pub fn new() -> Self {
+        FcmMessage::default()
+    }
+
+    /// notification sets title and body of displayed notification.
+    pub fn notification(mut self, title: impl Into<String>, body: impl Into<String>) -> Self {
+        let notification = self.notification.get_or_insert_with(Default::default);
+        notification.title = Some(title.into());
+        notification.body = Some(body.into());
+        self
+    }
+
+    /// image sets URL of image displayed in notification.
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.notification.get_or_insert_with(Default::default).image = Some(image.into());
+        self
+    }
+
+    /// data adds key to data payload.
+    pub fn data(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.data
+            .get_or_insert_with(Default::default)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// android sets Android specific options.
+    pub fn android(mut self, android: AndroidConfig) -> Self {
+        self.android = Some(android);
+        self
+    }
+}
+
+impl From<FcmMessage> for FcmPushNotification {
+    fn from(message: FcmMessage) -> Self {
+        FcmPushNotification {
+            message: serde_json::to_value(message).unwrap_or_default(),
+        }
+    }
+}
+
+/// ApnsMessage is a typed APNs request sent in PushNotification.apns, device
+/// tokens are set by PushRecipient.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApnsMessage {
+    /// headers are APNs request headers, e.g. apns-priority.
+    pub headers: HashMap<String, String>,
+    pub payload: ApnsPayload,
+}
+
+/// ApnsPayload is a body of APNs request, aps dictionary and custom keys.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ApnsPayload {
+    pub aps: Aps,
+    /// custom are application keys sent next to aps.
+    #[serde(flatten)]
+    pub custom: Map<String, Value>,
+}
+
+/// Aps is an aps dictionary of APNs payload.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Aps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<ApnsAlert>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub badge: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// content_available is 1 for background notification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_available: Option<u8>,
+    /// mutable_content is 1 when notification service extension may modify it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mutable_content: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApnsAlert {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl ApnsMessage {
+    /// new returns empty message to be filled with chainable setters.
+    pub fn new() -> Self {
+        ApnsMessage::default()
+    }
+
+    /// alert sets title and body of displayed notification.
+    pub fn alert(mut self, title: impl Into<String>, body: impl Into<String>) -> Self {
+        let alert = self.payload.aps.alert.get_or_insert_with(Default::default);
+        alert.title = Some(title.into());
+        alert.body = Some(body.into());
+        self
+    }
+
+    /// badge sets number displayed on app icon.
+    pub fn badge(mut self, badge: u32) -> Self {
+        self.payload.aps.badge = Some(badge);
+        self
+    }
+
+    /// sound sets name of sound file played, default for system sound.
+    pub fn sound(mut self, sound: impl Into<String>) -> Self {
+        self.payload.aps.sound = Some(sound.into());
+        self
+    }
+
+    /// background marks notification as silent background update, see
+    /// Aps.content_available.
+    pub fn background(mut self) -> Self {
+        self.payload.aps.content_available = Some(1);
+        self.headers
+            .insert("apns-push-type".to_string(), "background".to_string());
+        self.headers
+            .insert("apns-priority".to_string(), "5".to_string());
+        self
+    }
+
+    /// header sets APNs request header, e.g. apns-collapse-id.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// custom adds application key to payload.
+    pub fn custom(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.payload.custom.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl From<ApnsMessage> for ApnsPushNotification {
+    fn from(message: ApnsMessage) -> Self {
+        ApnsPushNotification {
+            headers: (!message.headers.is_empty()).then_some(message.headers),
+            payload: serde_json::to_value(message.payload).unwrap_or_default(),
+        }
+    }
+}
+
+/// HmsMessage is a typed HMS Push Kit message sent in PushNotification.hms,
+/// target is set by PushRecipient.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HmsMessage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification: Option<HmsNotification>,
+    /// data is a payload of data message, HMS expects it as a string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android: Option<HmsAndroidConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HmsNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HmsUrgency {
+    Normal,
+    High,
+}
+
+/// HmsAndroidConfig contains Android specific options of HMS message.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HmsAndroidConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<HmsUrgency>,
+    /// category is a message category required for high urgency, e.g. IM.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// ttl is a duration in seconds with s suffix, see ttl_string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collapse_key: Option<i32>,
+}
+
+impl HmsMessage {
+    /// new returns empty message to be filled with chainable setters.
+    pub fn new() -> Self {
+        HmsMessage::default()
+    }
+
+    /// notification sets title and body of displayed notification.
+    pub fn notification(mut self, title: impl Into<String>, body: impl Into<String>) -> Self {
+        let notification = self.notification.get_or_insert_with(Default::default);
+        notification.title = Some(title.into());
+        notification.body = Some(body.into());
+        self
+    }
+
+    /// data sets payload of data message, encoded as JSON string.
+    pub fn data(mut self, data: &Value) -> Self {
+        self.data = Some(data.to_string());
+        self
+    }
+
+    /// android sets Android specific options.
+    pub fn android(mut self, android: HmsAndroidConfig) -> Self {
+        self.android = Some(android);
+        self
+    }
+}
+
+impl From<HmsMessage> for HmsPushNotification {
+    fn from(message: HmsMessage) -> Self {
+        HmsPushNotification {
+            message: serde_json::to_value(message).unwrap_or_default(),
+        }
+    }
+}
+
+impl PushNotification {
+    /// with_fcm sets FCM payload.
+    pub fn with_fcm(mut self, message: FcmMessage) -> Self {
+        self.fcm = Some(message.into());
+        self
+    }
+
+    /// with_apns sets APNs payload.
+    pub fn with_apns(mut self, message: ApnsMessage) -> Self {
+        self.apns = Some(message.into());
+        self
+    }
+
+    /// with_hms sets HMS payload.
+    pub fn with_hms(mut self, message: HmsMessage) -> Self {
+        self.hms = Some(message.into());
+        self
+    }
+}
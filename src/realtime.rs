@@ -0,0 +1,528 @@
+use crate::client::{default_http_client_builder, ErrRes, ErrStatusCode};
+use crate::id::random_bytes;
+use crate::protocol::{self, ClientInfo, Publication};
+use base64::Engine;
+use futures_util::stream::{self, Stream};
+use reqwest::Client as ReqClient;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+
+/// DEFAULT_CLIENT_NAME is sent in connect command unless RealtimeConfig.name is set.
+pub const DEFAULT_CLIENT_NAME: &str = "rucent";
+
+/// MAX_MESSAGE_SIZE limits size of message received from server.
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+const CLOSE_NORMAL: u16 = 1000;
+
+/// RealtimeConfig configures connection to Centrifugo client protocol endpoint.
+#[derive(Clone, Debug, Default)]
+pub struct RealtimeConfig {
+    /// url is a WebSocket endpoint, e.g. ws://localhost:8000/connection/websocket.
+    pub url: String,
+    /// token is a connection JWT. Nil value means anonymous connection.
+    pub token: Option<String>,
+    /// name is a client name sent in connect command.
+    /// Nil value means DEFAULT_CLIENT_NAME.
+    pub name: Option<String>,
+    /// http_client is used to establish connection, it must use HTTP/1.1 since
+    /// WebSocket upgrade is not possible over HTTP/2. Nil value means default
+    /// http client restricted to HTTP/1.1.
+    pub http_client: Option<ReqClient>,
+}
+
+/// RealtimeEvent is a message pushed by server over realtime connection.
+#[derive(Debug)]
+pub enum RealtimeEvent {
+    /// Publication is emitted for every message published into subscribed channel.
+    Publication {
+        channel: String,
+        publication: Publication,
+    },
+    /// Join is emitted when client joined subscribed channel with join/leave enabled.
+    Join { channel: String, info: ClientInfo },
+    /// Leave is emitted when client left subscribed channel with join/leave enabled.
+    Leave { channel: String, info: ClientInfo },
+    /// Unsubscribed is emitted when server unsubscribed connection from channel.
+    Unsubscribed {
+        channel: String,
+        code: u32,
+        reason: String,
+    },
+    /// Disconnected is the last event of stream, emitted when connection is closed.
+    Disconnected { code: u32, reason: String },
+}
+
+/// ConnectResult is a result of connect command.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ConnectResult {
+    #[serde(default)]
+    pub client: String,
+    #[serde(default)]
+    pub version: String,
+    /// ping is an interval in seconds server sends pings with.
+    #[serde(default)]
+    pub ping: u32,
+    /// pong tells whether server expects pongs in reply to pings.
+    #[serde(default)]
+    pub pong: bool,
+}
+
+enum Outgoing {
+    Command {
+        method: &'static str,
+        params: Value,
+        reply: oneshot::Sender<Result<Value, ErrRes>>,
+    },
+    Close,
+}
+
+enum Incoming {
+    Text(String),
+    Ping(Vec<u8>),
+    Closed(u32, String),
+}
+
+/// RealtimeClient is a connection to Centrifugo speaking client protocol with
+/// JSON encoding over WebSocket, so backend workers can consume channels.
+/// Connection is closed by close or when all clones are dropped.
+#[derive(Clone, Debug)]
+pub struct RealtimeClient {
+    commands: mpsc::UnboundedSender<Outgoing>,
+    connect_result: ConnectResult,
+}
+
+impl RealtimeClient {
+    /// connect establishes connection and sends connect command, returns client
+    /// together with stream of events pushed by server. Stream ends with
+    /// Disconnected event.
+    pub async fn connect(
+        config: RealtimeConfig,
+    ) -> Result<(RealtimeClient, impl Stream<Item = RealtimeEvent>), ErrRes> {
+        let upgraded = handshake(&config).await?;
+        let (reader, writer) = tokio::io::split(upgraded);
+
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(read_messages(reader, incoming_tx));
+        tokio::spawn(run(writer, incoming_rx, commands_rx, events_tx));
+
+        let mut client = RealtimeClient {
+            commands: commands_tx,
+            connect_result: ConnectResult::default(),
+        };
+
+        let mut params = Map::new();
+        if let Some(token) = config.token {
+            params.insert("token".to_string(), token.into());
+        }
+        params.insert(
+            "name".to_string(),
+            config
+                .name
+                .unwrap_or_else(|| DEFAULT_CLIENT_NAME.to_string())
+                .into(),
+        );
+        let result = client.call("connect", Value::Object(params)).await?;
+        client.connect_result = serde_json::from_value(result)?;
+
+        let events = stream::unfold(events_rx, |mut events_rx| async move {
+            events_rx.recv().await.map(|event| (event, events_rx))
+        });
+        Ok((client, events))
+    }
+
+    /// connect_result returns result of connect command, e.g. client id.
+    pub fn connect_result(&self) -> &ConnectResult {
+        &self.connect_result
+    }
+
+    /// subscribe subscribes connection to channel, its publications are pushed
+    /// into events stream.
+    pub async fn subscribe(&self, channel: impl Into<String>) -> Result<(), ErrRes> {
+        let mut params = Map::new();
+        params.insert("channel".to_string(), channel.into().into());
+        self.call("subscribe", Value::Object(params)).await?;
+        Ok(())
+    }
+
+    /// unsubscribe unsubscribes connection from channel.
+    pub async fn unsubscribe(&self, channel: impl Into<String>) -> Result<(), ErrRes> {
+        let mut params = Map::new();
+        params.insert("channel".to_string(), channel.into().into());
+        self.call("unsubscribe", Value::Object(params)).await?;
+        Ok(())
+    }
+
+    /// publish publishes JSON data into channel on behalf of connection.
+    pub async fn publish(&self, channel: impl Into<String>, data: &str) -> Result<(), ErrRes> {
+        let mut params = Map::new();
+        params.insert("channel".to_string(), channel.into().into());
+        params.insert("data".to_string(), serde_json::from_str(data)?);
+        self.call("publish", Value::Object(params)).await?;
+        Ok(())
+    }
+
+    /// close closes connection, pending calls fail and events stream ends.
+    pub fn close(&self) {
+        let _ = self.commands.send(Outgoing::Close);
+    }
+
+    async fn call(&self, method: &'static str, params: Value) -> Result<Value, ErrRes> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.commands
+            .send(Outgoing::Command {
+                method,
+                params,
+                reply: reply_tx,
+            })
+            .map_err(|_| "connection closed")?;
+        reply_rx.await.map_err(|_| "connection closed")?
+    }
+}
+
+/// handshake performs WebSocket opening handshake and returns upgraded connection.
+async fn handshake(config: &RealtimeConfig) -> Result<reqwest::Upgraded, ErrRes> {
+    let url = if let Some(rest) = config.url.strip_prefix("ws://") {
+        format!("http://{rest}")
+    } else if let Some(rest) = config.url.strip_prefix("wss://") {
+        format!("https://{rest}")
+    } else {
+        config.url.clone()
+    };
+
+    let http_client = match &config.http_client {
+        Some(http_client) => http_client.clone(),
+        None => default_http_client_builder().http1_only().build()?,
+    };
+
+    let key = base64::engine::general_purpose::STANDARD.encode(random_bytes::<16>());
+    let response = http_client
+        .get(url)
+        .version(reqwest::Version::HTTP_11)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", key)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status != reqwest::StatusCode::SWITCHING_PROTOCOLS {
+        let body = response.bytes().await.unwrap_or_default();
+        return Err(Box::new(ErrStatusCode::new(status.as_u16(), &body, None)));
+    }
+
+    Ok(response.upgrade().await?)
+}
+
+/// run writes commands and dispatches messages received from server until
+/// connection is closed.
+async fn run<W: AsyncWrite + Unpin>(
+    mut writer: W,
+    mut incoming: mpsc::UnboundedReceiver<Incoming>,
+    mut commands: mpsc::UnboundedReceiver<Outgoing>,
+    events: mpsc::UnboundedSender<RealtimeEvent>,
+) {
+    let mut next_id: u32 = 1;
+    let mut pending: HashMap<u32, oneshot::Sender<Result<Value, ErrRes>>> = HashMap::new();
+
+    let (code, reason) = loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Outgoing::Command { method, params, reply }) => {
+                    let id = next_id;
+                    next_id += 1;
+
+                    let mut command = Map::new();
+                    command.insert("id".to_string(), id.into());
+                    command.insert(method.to_string(), params);
+                    let command = Value::Object(command).to_string();
+                    if let Err(err) = write_frame(&mut writer, OPCODE_TEXT, command.as_bytes()).await {
+                        let _ = reply.send(Err(err.to_string().into()));
+                        break (0, err.to_string());
+                    }
+                    pending.insert(id, reply);
+                }
+                // closed explicitly or all clients dropped.
+                Some(Outgoing::Close) | None => {
+                    let _ = write_frame(&mut writer, OPCODE_CLOSE, &CLOSE_NORMAL.to_be_bytes()).await;
+                    break (CLOSE_NORMAL as u32, "client closed".to_string());
+                }
+            },
+            message = incoming.recv() => match message {
+                Some(Incoming::Text(text)) => {
+                    let mut disconnect = None;
+                    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                        match handle_message(line, &mut pending, &events) {
+                            Ok(Action::None) => {}
+                            Ok(Action::Pong) => {
+                                let _ = write_frame(&mut writer, OPCODE_TEXT, b"{}").await;
+                            }
+                            Ok(Action::Disconnect(code, reason)) => disconnect = Some((code, reason)),
+                            Err(err) => log::warn!("skip malformed realtime message: {err}"),
+                        }
+                    }
+                    if let Some(disconnect) = disconnect {
+                        break disconnect;
+                    }
+                }
+                Some(Incoming::Ping(payload)) => {
+                    let _ = write_frame(&mut writer, OPCODE_PONG, &payload).await;
+                }
+                Some(Incoming::Closed(code, reason)) => break (code, reason),
+                None => break (0, "connection closed".to_string()),
+            }
+        }
+    };
+
+    for (_, reply) in pending.drain() {
+        let _ = reply.send(Err("connection closed".into()));
+    }
+    let _ = events.send(RealtimeEvent::Disconnected { code, reason });
+}
+
+enum Action {
+    None,
+    Pong,
+    Disconnect(u32, String),
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    #[serde(default)]
+    id: u32,
+    error: Option<protocol::Error>,
+    push: Option<RawPush>,
+    // reply result is keyed by method of command.
+    #[serde(flatten)]
+    result: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawPush {
+    #[serde(default)]
+    channel: String,
+    pub(crate) connect: Option<ConnectResult>,
+    #[serde(rename = "pub")]
+    publication: Option<Publication>,
+    join: Option<RawInfo>,
+    leave: Option<RawInfo>,
+    unsubscribe: Option<RawCodeReason>,
+    disconnect: Option<RawCodeReason>,
+}
+
+#[derive(Deserialize)]
+struct RawInfo {
+    info: ClientInfo,
+}
+
+#[derive(Deserialize)]
+struct RawCodeReason {
+    #[serde(default)]
+    code: u32,
+    #[serde(default)]
+    reason: String,
+}
+
+/// handle_message resolves pending call with reply or emits event for push.
+fn handle_message(
+    line: &str,
+    pending: &mut HashMap<u32, oneshot::Sender<Result<Value, ErrRes>>>,
+    events: &mpsc::UnboundedSender<RealtimeEvent>,
+) -> Result<Action, ErrRes> {
+    let message: RawMessage = serde_json::from_str(line)?;
+
+    if message.id > 0 {
+        if let Some(reply) = pending.remove(&message.id) {
+            let result = match message.error {
+                Some(err) => Err(Box::new(err) as ErrRes),
+                None => Ok(message
+                    .result
+                    .into_iter()
+                    .next()
+                    .map_or(Value::Null, |(_, v)| v)),
+            };
+            let _ = reply.send(result);
+        }
+        return Ok(Action::None);
+    }
+
+    let Some(push) = message.push else {
+        // empty message is a ping from server.
+        return Ok(Action::Pong);
+    };
+
+    match push_event(push) {
+        Some(RealtimeEvent::Disconnected { code, reason }) => Ok(Action::Disconnect(code, reason)),
+        Some(event) => {
+            let _ = events.send(event);
+            Ok(Action::None)
+        }
+        None => Ok(Action::None),
+    }
+}
+
+/// push_event converts push into event, None is returned for pushes not
+/// exposed as events.
+pub(crate) fn push_event(push: RawPush) -> Option<RealtimeEvent> {
+    let channel = push.channel;
+    let event = if let Some(publication) = push.publication {
+        RealtimeEvent::Publication {
+            channel,
+            publication,
+        }
+    } else if let Some(join) = push.join {
+        RealtimeEvent::Join {
+            channel,
+            info: join.info,
+        }
+    } else if let Some(leave) = push.leave {
+        RealtimeEvent::Leave {
+            channel,
+            info: leave.info,
+        }
+    } else if let Some(unsubscribe) = push.unsubscribe {
+        RealtimeEvent::Unsubscribed {
+            channel,
+            code: unsubscribe.code,
+            reason: unsubscribe.reason,
+        }
+    } else if let Some(disconnect) = push.disconnect {
+        RealtimeEvent::Disconnected {
+            code: disconnect.code,
+            reason: disconnect.reason,
+        }
+    } else {
+        return None;
+    };
+    Some(event)
+}
+
+/// read_messages reads frames from connection, assembles fragmented messages
+/// and passes them to run until connection is closed.
+async fn read_messages<R: AsyncRead + Unpin>(
+    mut reader: R,
+    incoming: mpsc::UnboundedSender<Incoming>,
+) {
+    let mut message = Vec::new();
+    loop {
+        let (fin, opcode, payload) = match read_frame(&mut reader).await {
+            Ok(frame) => frame,
+            Err(err) => {
+                let _ = incoming.send(Incoming::Closed(0, err.to_string()));
+                return;
+            }
+        };
+
+        match opcode {
+            OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                message.extend_from_slice(&payload);
+                if message.len() > MAX_MESSAGE_SIZE {
+                    let _ = incoming.send(Incoming::Closed(0, "message too large".to_string()));
+                    return;
+                }
+                if fin {
+                    let text = String::from_utf8_lossy(&message).into_owned();
+                    message.clear();
+                    let _ = incoming.send(Incoming::Text(text));
+                }
+            }
+            OPCODE_PING => {
+                let _ = incoming.send(Incoming::Ping(payload));
+            }
+            OPCODE_CLOSE => {
+                let code = match payload.get(..2) {
+                    Some(code) => u16::from_be_bytes([code[0], code[1]]) as u32,
+                    None => 0,
+                };
+                let reason = String::from_utf8_lossy(payload.get(2..).unwrap_or_default());
+                let _ = incoming.send(Incoming::Closed(code, reason.into_owned()));
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// read_frame reads a single WebSocket frame, returns fin flag, opcode and
+/// unmasked payload.
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<(bool, u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header).await?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0f;
+    let masked = header[1] & 0x80 != 0;
+
+    let len = match header[1] & 0x7f {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_MESSAGE_SIZE as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok((fin, opcode, payload))
+}
+
+/// write_frame writes a single masked WebSocket frame, as required for frames
+/// sent by client.
+async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    opcode: u8,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(0x80 | len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+
+    let mask = random_bytes::<4>();
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4]),
+    );
+
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
@@ -0,0 +1,149 @@
+use crate::client::ErrRes;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// REDACTED replaces values of redacted headers and fields in recorded exchanges.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// DEFAULT_REDACTED_HEADERS are headers Recorder redacts by default, auth header
+/// of client is redacted regardless of its name.
+pub const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "cookie",
+];
+
+/// Exchange is a request to Centrifugo API and response to it captured by
+/// Recorder, written as a single line of NDJSON file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Exchange {
+    pub endpoint: String,
+    pub headers: Vec<(String, String)>,
+    /// request is a request body, newline delimited commands.
+    pub request: String,
+    pub status: u16,
+    /// response is a response body as received from server.
+    pub response: String,
+}
+
+/// Recorder captures every request to Centrifugo API and response to it, e.g.
+/// to reproduce decode failures seen in production with testing::ReplayServer.
+/// Values of auth headers and of configured body fields are redacted.
+pub struct Recorder {
+    writer: Mutex<Box<dyn Write + Send>>,
+    redacted_headers: Vec<String>,
+    redacted_fields: Vec<String>,
+}
+
+impl Recorder {
+    /// Create a new recorder writing exchanges into writer.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Recorder {
+            writer: Mutex::new(Box::new(writer)),
+            redacted_headers: DEFAULT_REDACTED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            redacted_fields: Vec::new(),
+        }
+    }
+
+    /// create returns recorder appending exchanges to file at path.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Recorder::new(file))
+    }
+
+    /// redact_header allows to redact one more header, case insensitive.
+    pub fn redact_header(mut self, name: impl Into<String>) -> Self {
+        self.redacted_headers.push(name.into().to_lowercase());
+        self
+    }
+
+    /// redact_field allows to redact values of JSON object field with name at
+    /// any depth of request commands and response replies, e.g. token.
+    pub fn redact_field(mut self, name: impl Into<String>) -> Self {
+        self.redacted_fields.push(name.into());
+        self
+    }
+
+    /// record writes redacted exchange, failures are logged as recording must
+    /// not break API calls.
+    pub fn record(&self, mut exchange: Exchange) {
+        for (name, value) in &mut exchange.headers {
+            if self.redacted_headers.contains(&name.to_lowercase()) {
+                *value = REDACTED.to_string();
+            }
+        }
+        if !self.redacted_fields.is_empty() {
+            exchange.request = self.redact_lines(&exchange.request);
+            exchange.response = self.redact_lines(&exchange.response);
+        }
+
+        let line = match serde_json::to_string(&exchange) {
+            Ok(line) => line,
+            Err(err) => {
+                log::warn!("failed to encode recorded exchange: {}", err);
+                return;
+            }
+        };
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(err) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+            log::warn!("failed to record exchange: {}", err);
+        }
+    }
+
+    // redact_lines redacts fields of every JSON line of body, lines which are
+    // not JSON are kept as is.
+    fn redact_lines(&self, body: &str) -> String {
+        body.split('\n')
+            .map(|line| match serde_json::from_str::<Value>(line) {
+                Ok(mut value) => {
+                    self.redact_value(&mut value);
+                    value.to_string()
+                }
+                Err(_) => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    if self.redacted_fields.contains(key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_value(value)),
+            _ => {}
+        }
+    }
+}
+
+/// read_exchanges reads exchanges written by Recorder, blank lines are skipped.
+pub fn read_exchanges(reader: impl BufRead) -> Result<Vec<Exchange>, ErrRes> {
+    let mut exchanges = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        exchanges.push(serde_json::from_str(&line)?);
+    }
+    Ok(exchanges)
+}
+
+/// load_exchanges reads exchanges from file written by Recorder.
+pub fn load_exchanges(path: impl AsRef<Path>) -> Result<Vec<Exchange>, ErrRes> {
+    read_exchanges(BufReader::new(File::open(path)?))
+}
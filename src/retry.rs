@@ -0,0 +1,116 @@
+use crate::client::{ErrRequestFailed, ErrStatusCode};
+use crate::pipe::Command;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// DeadLetterHandler is invoked with every command which could not be delivered
+/// after all retries were exhausted, together with final error.
+pub type DeadLetterHandler = Arc<dyn Fn(&Command, &(dyn Error + Send + Sync)) + Send + Sync>;
+
+/// dead_letter_channel returns DeadLetterHandler pushing failed commands with
+/// final error message to sender.
+pub fn dead_letter_channel(sender: UnboundedSender<(Command, String)>) -> DeadLetterHandler {
+    Arc::new(move |command: &Command, err: &(dyn Error + Send + Sync)| {
+        let _ = sender.send((command.clone(), err.to_string()));
+    })
+}
+
+/// is_retryable reports whether request failed with error worth retrying:
+/// connection problems, timeouts, 5xx and 429 status codes.
+pub fn is_retryable(err: &(dyn Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<ErrStatusCode>() {
+        return is_retryable_status(err.code);
+    }
+    is_retryable_transport(err)
+}
+
+/// is_retryable_status reports whether status code is transient by default:
+/// 429 and 5xx.
+pub fn is_retryable_status(code: u16) -> bool {
+    code == 429 || (500..600).contains(&code)
+}
+
+fn is_retryable_transport(err: &(dyn Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<ErrRequestFailed>() {
+        if let Some(err) = err.source.downcast_ref::<reqwest::Error>() {
+            return err.is_connect() || err.is_timeout() || err.is_request();
+        }
+    }
+    false
+}
+
+/// is_retryable_code reports whether command failed with transient error code
+/// by default: 100 (internal) and 111 (too many requests).
+pub fn is_retryable_code(code: u32) -> bool {
+    code == 100 || code == 111
+}
+
+/// RetryPolicy defines how many times and how often failed requests are retried.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// max_attempts is a max number of attempts including the first one,
+    /// zero value means retrying until success.
+    pub max_attempts: u32,
+    /// min_backoff is a delay before the first retry, doubled on every next one.
+    pub min_backoff: Duration,
+    /// max_backoff caps delay between retries.
+    pub max_backoff: Duration,
+    /// retryable_status_codes are HTTP status codes considered transient, e.g.
+    /// only 502, 503 and 504 returned by proxy. Nil value means 429 and 5xx.
+    pub retryable_status_codes: Option<Vec<u16>>,
+    /// retryable_error_codes are codes of errors in replies to commands
+    /// considered transient, see Client::retry_failed. Nil value means
+    /// codes reported by is_retryable_code.
+    pub retryable_error_codes: Option<Vec<u32>>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            retryable_status_codes: None,
+            retryable_error_codes: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// backoff returns delay to wait after failed attempt (starting from 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.min_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// is_retryable is the same as is_retryable function but honours
+    /// retryable_status_codes.
+    pub fn is_retryable(&self, err: &(dyn Error + 'static)) -> bool {
+        match (
+            err.downcast_ref::<ErrStatusCode>(),
+            &self.retryable_status_codes,
+        ) {
+            (Some(err), Some(codes)) => codes.contains(&err.code),
+            _ => is_retryable(err),
+        }
+    }
+
+    /// is_retryable_code is the same as is_retryable_code function but honours
+    /// retryable_error_codes.
+    pub fn is_retryable_code(&self, code: u32) -> bool {
+        match &self.retryable_error_codes {
+            Some(codes) => codes.contains(&code),
+            None => is_retryable_code(code),
+        }
+    }
+
+    /// can_retry reports whether another attempt is allowed after failed attempt.
+    pub fn can_retry(&self, attempt: u32) -> bool {
+        self.max_attempts == 0 || attempt < self.max_attempts
+    }
+}
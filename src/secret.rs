@@ -0,0 +1,105 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// KEY_FILE_CHECK_INTERVAL is how often KeyFile checks whether file changed.
+pub const KEY_FILE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// KeyFile keeps API key read from a file, e.g. mounted Kubernetes secret,
+/// and re-reads it when file is modified, so rotated key is picked up without
+/// restart. Surrounding whitespace is trimmed.
+pub struct KeyFile {
+    path: PathBuf,
+    state: Mutex<KeyState>,
+}
+
+struct KeyState {
+    key: Option<String>,
+    modified: Option<SystemTime>,
+    checked_at: Instant,
+}
+
+impl KeyFile {
+    /// Create a new key file reading key from path. Read failure is logged and
+    /// key is read again on next check, use read to fail early instead.
+    pub fn new(path: impl Into<PathBuf>) -> Arc<Self> {
+        let path = path.into();
+        let (key, modified) = match read_key(&path) {
+            Ok((key, modified)) => (Some(key), modified),
+            Err(err) => {
+                log::warn!("failed to read key file {}: {err}", path.display());
+                (None, None)
+            }
+        };
+        Arc::new(KeyFile {
+            path,
+            state: Mutex::new(KeyState {
+                key,
+                modified,
+                checked_at: Instant::now(),
+            }),
+        })
+    }
+
+    /// read returns key stored in file at path.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<String> {
+        read_key(path.as_ref()).map(|(key, _)| key)
+    }
+
+    /// path returns path key is read from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// key returns current key, file is re-read if it was modified since last
+    /// read, checks are made at most every KEY_FILE_CHECK_INTERVAL. Nil value
+    /// means file could not be read yet.
+    pub fn key(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.key.is_none() || state.checked_at.elapsed() >= KEY_FILE_CHECK_INTERVAL {
+            state.checked_at = Instant::now();
+            let modified = fs::metadata(&self.path)
+                .and_then(|meta| meta.modified())
+                .ok();
+            if state.key.is_none() || modified != state.modified {
+                self.load(&mut state);
+            }
+        }
+        state.key.clone()
+    }
+
+    /// reload re-reads key from file regardless of modification time, e.g.
+    /// after server rejected current key. Returns true when key changed.
+    pub fn reload(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = state.key.clone();
+        state.checked_at = Instant::now();
+        self.load(&mut state);
+        state.key != previous
+    }
+
+    fn load(&self, state: &mut KeyState) {
+        match read_key(&self.path) {
+            Ok((key, modified)) => {
+                state.key = Some(key);
+                state.modified = modified;
+            }
+            // previous key is kept, file may be in the middle of rotation.
+            Err(err) => log::warn!("failed to read key file {}: {err}", self.path.display()),
+        }
+    }
+}
+
+fn read_key(path: &Path) -> io::Result<(String, Option<SystemTime>)> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let key = fs::read_to_string(path)?.trim().to_string();
+    if key.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "key file is empty",
+        ));
+    }
+    Ok((key, modified))
+}
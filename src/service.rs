@@ -0,0 +1,112 @@
+use crate::client::ErrRes;
+use bytes::Bytes;
+use futures_util::future::poll_fn;
+use reqwest::{Body, Client as ReqClient};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// HttpRequest is a request to Centrifugo API passed through HttpService.
+pub type HttpRequest = http::Request<Body>;
+
+/// HttpResponse is a response of Centrifugo API returned by ApiService.
+pub type HttpResponse = http::Response<Body>;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// ApiService is a tower service sending requests with http client of Client,
+/// the innermost service layers from Config.http_layer are applied to.
+#[derive(Clone)]
+pub struct ApiService {
+    http_client: ReqClient,
+}
+
+impl ApiService {
+    /// Create a new service sending requests with http_client.
+    pub fn new(http_client: ReqClient) -> Self {
+        ApiService { http_client }
+    }
+}
+
+impl Service<HttpRequest> for ApiService {
+    type Response = HttpResponse;
+    type Error = ErrRes;
+    type Future = BoxFuture<Result<HttpResponse, ErrRes>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: HttpRequest) -> Self::Future {
+        let http_client = self.http_client.clone();
+        Box::pin(async move {
+            let request = reqwest::Request::try_from(request)?;
+            let response = http_client.execute(request).await?;
+            Ok(HttpResponse::from(response))
+        })
+    }
+}
+
+/// HttpService is a type erased service requests of Client are sent with,
+/// usually ApiService wrapped into tower-http layers, e.g. Trace or Timeout.
+#[derive(Clone)]
+pub struct HttpService {
+    call: Arc<dyn Fn(HttpRequest) -> BoxFuture<Result<HttpResponse, ErrRes>> + Send + Sync>,
+}
+
+impl HttpService {
+    /// Create a new service from any tower service, body of its responses
+    /// may be of any type, e.g. wrapped by layer.
+    pub fn new<S, B>(service: S) -> Self
+    where
+        S: Service<HttpRequest, Response = http::Response<B>> + Clone + Send + Sync + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<ErrRes>,
+        B: http_body::Body + Send + Sync + 'static,
+        B::Data: Into<Bytes>,
+        B::Error: Into<ErrRes>,
+    {
+        HttpService {
+            call: Arc::new(move |request| {
+                let mut service = service.clone();
+                Box::pin(async move {
+                    poll_fn(|cx| service.poll_ready(cx))
+                        .await
+                        .map_err(Into::into)?;
+                    let response = service.call(request).await.map_err(Into::into)?;
+                    Ok(response.map(Body::wrap))
+                })
+            }),
+        }
+    }
+
+    /// execute sends request through service.
+    pub(crate) async fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, ErrRes> {
+        let request = HttpRequest::try_from(request)?;
+        let response = (self.call)(request).await?;
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+/// HttpLayer wraps ApiService of client into layers, see Config.http_layer.
+pub type HttpLayer = Arc<dyn Fn(ApiService) -> HttpService + Send + Sync>;
+
+/// http_layer returns HttpLayer applying layer to ApiService, e.g.
+/// `http_layer(|service| TimeoutLayer::new(timeout).layer(service))`.
+pub fn http_layer<F, S, B>(layer: F) -> HttpLayer
+where
+    F: Fn(ApiService) -> S + Send + Sync + 'static,
+    S: Service<HttpRequest, Response = http::Response<B>> + Clone + Send + Sync + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<ErrRes>,
+    B: http_body::Body + Send + Sync + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<ErrRes>,
+{
+    Arc::new(move |service| HttpService::new(layer(service)))
+}
@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+
+// ErrClientClosed is returned by calls made after Client::shutdown was started.
+#[derive(Debug)]
+pub struct ErrClientClosed {}
+
+// Implement the `std::fmt::Display` trait for `ErrClientClosed`
+impl fmt::Display for ErrClientClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "client is shut down")
+    }
+}
+
+// Implement the `Error` trait for `ErrClientClosed`
+impl Error for ErrClientClosed {}
+
+/// ShutdownReport describes work which didn't finish before shutdown deadline.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// dropped_requests is a number of HTTP requests still in flight at deadline.
+    pub dropped_requests: usize,
+    /// dropped_tasks is a number of background outbox publishers and watchers
+    /// still running at deadline.
+    pub dropped_tasks: usize,
+}
+
+impl ShutdownReport {
+    /// is_clean tells whether all work finished before deadline.
+    pub fn is_clean(&self) -> bool {
+        self.dropped_requests == 0 && self.dropped_tasks == 0
+    }
+}
+
+/// Lifecycle tracks in-flight requests and background tasks of a client and
+/// its clones, so they can be drained on shutdown.
+pub struct Lifecycle {
+    closed: watch::Sender<bool>,
+    requests: AtomicUsize,
+    tasks: AtomicUsize,
+    idle: Notify,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            closed: watch::Sender::new(false),
+            requests: AtomicUsize::new(0),
+            tasks: AtomicUsize::new(0),
+            idle: Notify::new(),
+        }
+    }
+}
+
+impl Lifecycle {
+    /// is_closed tells whether shutdown was started.
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// closed resolves once shutdown is started.
+    pub async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+
+    /// request registers in-flight request, refused after shutdown was started.
+    pub fn request(self: &Arc<Self>) -> Result<ActivityGuard, ErrClientClosed> {
+        if self.is_closed() {
+            return Err(ErrClientClosed {});
+        }
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        Ok(ActivityGuard {
+            lifecycle: self.clone(),
+            task: false,
+        })
+    }
+
+    /// task registers background task, it's expected to stop once closed resolves.
+    pub fn task(self: &Arc<Self>) -> ActivityGuard {
+        self.tasks.fetch_add(1, Ordering::SeqCst);
+        ActivityGuard {
+            lifecycle: self.clone(),
+            task: true,
+        }
+    }
+
+    /// shutdown refuses new requests, signals background tasks to stop and waits
+    /// up to timeout for registered work to finish.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        self.closed.send_replace(true);
+
+        let drained = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.report().is_clean() {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        let _ = tokio::time::timeout(timeout, drained).await;
+
+        self.report()
+    }
+
+    fn report(&self) -> ShutdownReport {
+        ShutdownReport {
+            dropped_requests: self.requests.load(Ordering::SeqCst),
+            dropped_tasks: self.tasks.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// ActivityGuard keeps request or background task registered until dropped.
+pub struct ActivityGuard {
+    lifecycle: Arc<Lifecycle>,
+    task: bool,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        let counter = if self.task {
+            &self.lifecycle.tasks
+        } else {
+            &self.lifecycle.requests
+        };
+        counter.fetch_sub(1, Ordering::SeqCst);
+        self.lifecycle.idle.notify_waiters();
+    }
+}
@@ -0,0 +1,84 @@
+use crate::pipe::Command;
+use crate::protocol::Reply;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// ClientStats is a snapshot of API usage by client and its clones, returned
+/// by Client::stats for lightweight self-monitoring.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// commands_sent is a number of commands sent by method, every retry
+    /// attempt counts.
+    pub commands_sent: HashMap<String, u64>,
+    /// errors is a number of replies with error by error code.
+    pub errors: HashMap<u32, u64>,
+    /// bytes_sent is a total size of request bodies sent.
+    pub bytes_sent: u64,
+    /// bytes_received is a total size of response bodies received.
+    pub bytes_received: u64,
+}
+
+/// StatsCounters collects ClientStats, shared by client and its clones.
+#[derive(Default)]
+pub struct StatsCounters {
+    commands_sent: Mutex<HashMap<String, u64>>,
+    errors: Mutex<HashMap<u32, u64>>,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl StatsCounters {
+    /// record_request counts commands sent in request body of size bytes.
+    pub(crate) fn record_request(&self, commands: &[Command], size: usize) {
+        self.bytes_sent.fetch_add(size as u64, Ordering::Relaxed);
+        let mut commands_sent = self.commands_sent.lock().unwrap_or_else(|e| e.into_inner());
+        for cmd in commands {
+            *commands_sent.entry(cmd.method.to_string()).or_default() += 1;
+        }
+    }
+
+    /// record_response counts response body of size bytes.
+    pub(crate) fn record_response(&self, size: usize) {
+        self.bytes_received
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// record_replies counts replies with error.
+    pub(crate) fn record_replies(&self, replies: &[Reply]) {
+        let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+        for err in replies.iter().filter_map(|reply| reply.error.as_ref()) {
+            *errors.entry(err.code).or_default() += 1;
+        }
+    }
+
+    /// snapshot returns current statistics.
+    pub fn snapshot(&self) -> ClientStats {
+        ClientStats {
+            commands_sent: self
+                .commands_sent
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            errors: self
+                .errors
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+
+    /// reset sets all counters to zero and returns statistics collected before.
+    pub fn reset(&self) -> ClientStats {
+        ClientStats {
+            commands_sent: std::mem::take(
+                &mut *self.commands_sent.lock().unwrap_or_else(|e| e.into_inner()),
+            ),
+            errors: std::mem::take(&mut *self.errors.lock().unwrap_or_else(|e| e.into_inner())),
+            bytes_sent: self.bytes_sent.swap(0, Ordering::Relaxed),
+            bytes_received: self.bytes_received.swap(0, Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,996 @@
+use crate::client::{Client, Config, ErrRes};
+use crate::pattern::ChannelPattern;
+use crate::pipe::{Command, Method, RequestKind};
+use crate::protocol;
+use crate::record::{load_exchanges, Exchange};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// DEFAULT_IMAGE is a Centrifugo image started by CentrifugoContainer.
+pub const DEFAULT_IMAGE: &str = "centrifugo/centrifugo:v5";
+
+/// CONTAINER_LABEL is set on containers started by CentrifugoContainer, so
+/// containers left by killed processes, which never drop CentrifugoContainer,
+/// can be removed with `docker rm -f $(docker ps -qf label=rucent.testing)`.
+pub const CONTAINER_LABEL: &str = "rucent.testing";
+
+/// StubReply is a canned reply served by StubServer.
+#[derive(Clone, Debug)]
+pub enum StubReply {
+    /// Result is served as successful reply to a single command.
+    Result(Value),
+    /// Error is served as error reply to a single command.
+    Error(protocol::Error),
+    /// Status fails the whole request with HTTP status code and body.
+    Status(u16, String),
+}
+
+#[derive(Default)]
+struct StubState {
+    replies: VecDeque<StubReply>,
+    received: Vec<Command>,
+}
+
+/// StubServer is a local HTTP server implementing Centrifugo API wire format,
+/// both NDJSON and batch, so tests can run against canned replies and assert
+/// commands they received. Commands without enqueued reply get empty result.
+/// Server is stopped when dropped.
+pub struct StubServer {
+    addr: String,
+    state: Arc<Mutex<StubState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl StubServer {
+    /// start starts server on a random local port.
+    pub fn start() -> StubServer {
+        let state = Arc::new(Mutex::new(StubState::default()));
+        let server_state = state.clone();
+        let (addr, stopped) = spawn_server(move |stream| {
+            if let Err(err) = handle_connection(stream, &server_state) {
+                log::warn!("stub server failed to handle request: {err}");
+            }
+        });
+
+        StubServer {
+            addr,
+            state,
+            stopped,
+        }
+    }
+
+    /// addr returns API endpoint of server.
+    pub fn addr(&self) -> String {
+        self.addr.clone()
+    }
+
+    /// config returns client config pointing at server.
+    pub fn config(&self) -> Config {
+        Config {
+            addr: Some(self.addr()),
+            key: Some("stub".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// client returns client pointing at server.
+    pub fn client(&self) -> Client {
+        Client::new(self.config())
+    }
+
+    /// enqueue adds reply served to the next command in order.
+    pub fn enqueue(&self, reply: StubReply) {
+        self.lock().replies.push_back(reply);
+    }
+
+    /// enqueue_result adds successful reply with result.
+    pub fn enqueue_result(&self, result: Value) {
+        self.enqueue(StubReply::Result(result));
+    }
+
+    /// enqueue_error adds error reply.
+    pub fn enqueue_error(&self, code: u32, message: impl Into<String>) {
+        self.enqueue(StubReply::Error(protocol::Error {
+            code,
+            message: message.into(),
+        }));
+    }
+
+    /// received returns commands received so far in order.
+    pub fn received(&self) -> Vec<Command> {
+        self.lock().received.clone()
+    }
+
+    /// received_methods returns methods of commands received so far in order.
+    pub fn received_methods(&self) -> Vec<Method> {
+        self.lock()
+            .received
+            .iter()
+            .map(|cmd| cmd.method.clone())
+            .collect()
+    }
+
+    /// assert_received panics unless methods of received commands are equal to methods.
+    pub fn assert_received(&self, methods: &[&str]) {
+        assert_eq!(self.received_methods(), methods);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StubState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        stop_server(&self.addr, &self.stopped);
+    }
+}
+
+/// spawn_server accepts connections on a random local port in background
+/// thread, every connection is handled in its own thread. Returns API endpoint
+/// and flag stopping server, see stop_server.
+fn spawn_server<F>(handle: F) -> (String, Arc<AtomicBool>)
+where
+    F: Fn(TcpStream) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind stub server");
+    let local_addr = listener.local_addr().expect("stub server address");
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let handle = Arc::new(handle);
+    let server_stopped = stopped.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if server_stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            let Ok(stream) = stream else { continue };
+            let handle = handle.clone();
+            thread::spawn(move || handle(stream));
+        }
+    });
+
+    (format!("http://{}/api", local_addr), stopped)
+}
+
+fn stop_server(addr: &str, stopped: &AtomicBool) {
+    stopped.store(true, Ordering::SeqCst);
+    // wake up accept loop so it notices server is stopped.
+    let _ = TcpStream::connect(addr.trim_start_matches("http://").trim_end_matches("/api"));
+}
+
+/// read_request reads HTTP request returning its path and body.
+fn read_request(reader: &mut BufReader<TcpStream>) -> std::io::Result<(String, Vec<u8>)> {
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line == "\r\n" || line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((path, body))
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<StubState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let (path, body) = read_request(&mut reader)?;
+    let batch = path == "/api/batch";
+
+    let (status, response) = match serve(&body, batch, state) {
+        Ok(response) => response,
+        Err(err) => (400, err.to_string()),
+    };
+    write_response(reader.get_mut(), status, &response)
+}
+
+/// DEFAULT_FAKE_HISTORY_SIZE is a number of publications FakeServer keeps in
+/// history of every channel unless FakeConfig.history_size is set.
+pub const DEFAULT_FAKE_HISTORY_SIZE: usize = 100;
+
+/// FakeConfig configures FakeServer.
+#[derive(Clone, Debug, Default)]
+pub struct FakeConfig {
+    /// history_size is a max number of publications kept in channel history.
+    /// Nil value means DEFAULT_FAKE_HISTORY_SIZE.
+    pub history_size: Option<usize>,
+}
+
+#[derive(Default)]
+struct FakeChannel {
+    epoch: String,
+    offset: u64,
+    history: VecDeque<protocol::Publication>,
+    /// presence is keyed by client id.
+    presence: HashMap<String, protocol::ClientInfo>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    history_size: usize,
+    channels: HashMap<String, FakeChannel>,
+    next_id: u64,
+    received: Vec<Command>,
+}
+
+/// FakeServer is a local HTTP server keeping Centrifugo state in memory:
+/// publications are appended to per-channel history, server-side subscriptions
+/// and disconnects maintain presence, so end-to-end flows can be tested
+/// deterministically without Docker. Only a single node without namespaces
+/// is emulated, unsupported commands get empty result. Server is stopped when
+/// dropped.
+pub struct FakeServer {
+    addr: String,
+    state: Arc<Mutex<FakeState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl FakeServer {
+    /// start starts server with default config on a random local port.
+    pub fn start() -> FakeServer {
+        FakeServer::start_with(FakeConfig::default())
+    }
+
+    /// start_with starts server with config on a random local port.
+    pub fn start_with(config: FakeConfig) -> FakeServer {
+        let state = Arc::new(Mutex::new(FakeState {
+            history_size: config.history_size.unwrap_or(DEFAULT_FAKE_HISTORY_SIZE),
+            ..Default::default()
+        }));
+        let server_state = state.clone();
+        let (addr, stopped) = spawn_server(move |stream| {
+            if let Err(err) = handle_fake(stream, &server_state) {
+                log::warn!("fake server failed to handle request: {err}");
+            }
+        });
+
+        FakeServer {
+            addr,
+            state,
+            stopped,
+        }
+    }
+
+    /// addr returns API endpoint of server.
+    pub fn addr(&self) -> String {
+        self.addr.clone()
+    }
+
+    /// client returns client pointing at server.
+    pub fn client(&self) -> Client {
+        Client::new(Config {
+            addr: Some(self.addr()),
+            key: Some("fake".to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// connect adds connection of user to channel presence as if it subscribed
+    /// from client side, returns id of connection.
+    pub fn connect(&self, channel: &str, user: &str) -> String {
+        self.lock().join(channel, user, None)
+    }
+
+    /// history returns publications kept in channel history, oldest first.
+    pub fn history(&self, channel: &str) -> Vec<protocol::Publication> {
+        self.lock()
+            .channels
+            .get(channel)
+            .map(|channel| channel.history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// received returns commands received so far in order.
+    pub fn received(&self) -> Vec<Command> {
+        self.lock().received.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        stop_server(&self.addr, &self.stopped);
+    }
+}
+
+fn handle_fake(stream: TcpStream, state: &Mutex<FakeState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let (path, body) = read_request(&mut reader)?;
+    let batch = path == "/api/batch";
+
+    let (status, response) = match decode_commands(&body, batch) {
+        Ok(commands) => {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            let replies = commands
+                .iter()
+                .map(|cmd| match state.apply(cmd) {
+                    Ok(result) => reply_result(cmd.method.as_str(), result, batch),
+                    Err(err) => json!({ "error": err }),
+                })
+                .collect();
+            state.received.extend(commands);
+            (200, encode_replies(replies, batch))
+        }
+        Err(err) => (400, err.to_string()),
+    };
+    write_response(reader.get_mut(), status, &response)
+}
+
+fn unrecoverable_position() -> protocol::Error {
+    protocol::Error {
+        code: 112,
+        message: "unrecoverable position".to_string(),
+    }
+}
+
+impl FakeState {
+    fn channel(&mut self, name: &str) -> &mut FakeChannel {
+        if !self.channels.contains_key(name) {
+            self.next_id += 1;
+            let epoch = format!("epoch-{}", self.next_id);
+            self.channels.entry(name.to_string()).or_default().epoch = epoch;
+        }
+        self.channels
+            .get_mut(name)
+            .expect("channel is created above")
+    }
+
+    fn join(&mut self, channel: &str, user: &str, client: Option<String>) -> String {
+        self.next_id += 1;
+        let client = client.unwrap_or_else(|| format!("client-{}", self.next_id));
+        let info = protocol::ClientInfo {
+            user: user.to_string(),
+            client: client.clone(),
+            conn_info: None,
+            chan_info: None,
+        };
+        self.channel(channel).presence.insert(client.clone(), info);
+        client
+    }
+
+    fn publish(&mut self, channel: &str, data: Value) -> protocol::PublishResult {
+        let history_size = self.history_size;
+        let channel = self.channel(channel);
+        channel.offset += 1;
+        channel.history.push_back(protocol::Publication {
+            offset: channel.offset,
+            data,
+            b64data: None,
+            info: None,
+        });
+        while channel.history.len() > history_size {
+            channel.history.pop_front();
+        }
+        protocol::PublishResult {
+            offset: Some(channel.offset),
+            epoch: Some(channel.epoch.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn history(
+        &mut self,
+        channel: &str,
+        options: &crate::options::HistoryOptions,
+    ) -> Result<Value, protocol::Error> {
+        let channel = self.channel(channel);
+        let reverse = options.reverse.unwrap_or(false);
+        let mut publications: Vec<_> = match &options.since {
+            Some(since) => {
+                let offset = since.offset.unwrap_or(0);
+                let oldest = channel
+                    .history
+                    .front()
+                    .map_or(channel.offset + 1, |p| p.offset);
+                let epoch_changed = since
+                    .epoch
+                    .as_ref()
+                    .is_some_and(|epoch| *epoch != channel.epoch);
+                if epoch_changed || (!reverse && offset + 1 < oldest) {
+                    return Err(unrecoverable_position());
+                }
+                channel
+                    .history
+                    .iter()
+                    .filter(|p| {
+                        if reverse {
+                            p.offset < offset
+                        } else {
+                            p.offset > offset
+                        }
+                    })
+                    .cloned()
+                    .collect()
+            }
+            None => channel.history.iter().cloned().collect(),
+        };
+        if reverse {
+            publications.reverse();
+        }
+        // like server, publications are returned only when limit is set,
+        // negative limit means no limit.
+        match options.limit {
+            None | Some(0) => publications.clear(),
+            Some(limit) if limit > 0 => publications.truncate(limit as usize),
+            Some(_) => {}
+        }
+        Ok(json!({
+            "publication": publications,
+            "offset": channel.offset,
+            "epoch": channel.epoch,
+        }))
+    }
+
+    fn info(&self) -> Value {
+        let mut users = HashSet::new();
+        let mut clients = HashSet::new();
+        for channel in self.channels.values() {
+            for (client, info) in &channel.presence {
+                clients.insert(client);
+                users.insert(&info.user);
+            }
+        }
+        let num_channels = self
+            .channels
+            .values()
+            .filter(|channel| !channel.presence.is_empty())
+            .count();
+        json!({
+            "nodes": [{
+                "uid": "fake",
+                "name": "fake",
+                "version": "fake",
+                "num_clients": clients.len(),
+                "num_users": users.len(),
+                "num_channels": num_channels,
+                "uptime": 0,
+            }]
+        })
+    }
+
+    /// apply executes command returning its result.
+    fn apply(&mut self, cmd: &Command) -> Result<Value, protocol::Error> {
+        let result = match &cmd.params {
+            RequestKind::PublishRequest(req) => json!(self.publish(&req.channel, req.data.clone())),
+            RequestKind::BroadcastRequest(req) => {
+                let responses: Vec<_> = req
+                    .channels
+                    .iter()
+                    .map(|channel| json!({ "result": self.publish(channel, req.data.clone()) }))
+                    .collect();
+                json!({ "responses": responses })
+            }
+            RequestKind::HistoryRequest(req) => self.history(&req.channel, &req.options)?,
+            RequestKind::HistoryRemoveRequest(req) => {
+                self.channel(&req.channel).history.clear();
+                json!({})
+            }
+            RequestKind::PresenceRequest(req) => {
+                json!({ "presence": self.channel(&req.channel).presence })
+            }
+            RequestKind::PresenceStatsRequest(req) => {
+                let presence = &self.channel(&req.channel).presence;
+                let users: HashSet<_> = presence.values().map(|info| &info.user).collect();
+                json!({ "num_clients": presence.len(), "num_users": users.len() })
+            }
+            RequestKind::ChannelsRequest(req) => {
+                let pattern = req
+                    .pattern
+                    .as_deref()
+                    .map(ChannelPattern::new)
+                    .transpose()
+                    .map_err(|err| protocol::Error {
+                        code: 107,
+                        message: err.to_string(),
+                    })?;
+                let channels: Map<String, Value> = self
+                    .channels
+                    .iter()
+                    .filter(|(name, channel)| {
+                        !channel.presence.is_empty()
+                            && pattern.as_ref().is_none_or(|pattern| pattern.matches(name))
+                    })
+                    .map(|(name, channel)| {
+                        (
+                            name.clone(),
+                            json!({ "num_clients": channel.presence.len() }),
+                        )
+                    })
+                    .collect();
+                json!({ "channels": channels })
+            }
+            RequestKind::SubscribeRequest(req) => {
+                self.join(&req.channel, &req.user, req.options.client_id.clone());
+                json!({})
+            }
+            RequestKind::UnsubscribeRequest(req) => {
+                let client_id = req.options.client_id.as_ref();
+                self.channel(&req.channel).presence.retain(|client, info| {
+                    info.user != req.user || client_id.is_some_and(|id| id != client)
+                });
+                json!({})
+            }
+            RequestKind::DisconnectRequest(req) => {
+                let client_id = req.options.client_id.as_ref();
+                let whitelist = req.options.client_whitelist.clone().unwrap_or_default();
+                for channel in self.channels.values_mut() {
+                    channel.presence.retain(|client, info| {
+                        info.user != req.user
+                            || whitelist.contains(client)
+                            || client_id.is_some_and(|id| id != client)
+                    });
+                }
+                json!({})
+            }
+            RequestKind::InfoRequest(_) => self.info(),
+            _ => json!({}),
+        };
+        Ok(result)
+    }
+}
+
+/// ReplayServer is a local HTTP server serving exchanges captured by
+/// record::Recorder back in order, so failures seen in production can be
+/// reproduced in tests. Requests after the last exchange get 500 status.
+/// Server is stopped when dropped.
+pub struct ReplayServer {
+    addr: String,
+    state: Arc<Mutex<ReplayState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct ReplayState {
+    exchanges: VecDeque<Exchange>,
+    received: Vec<String>,
+}
+
+impl ReplayServer {
+    /// start starts server replaying exchanges on a random local port.
+    pub fn start(exchanges: Vec<Exchange>) -> ReplayServer {
+        let state = Arc::new(Mutex::new(ReplayState {
+            exchanges: exchanges.into(),
+            received: Vec::new(),
+        }));
+        let server_state = state.clone();
+        let (addr, stopped) = spawn_server(move |stream| {
+            if let Err(err) = handle_replay(stream, &server_state) {
+                log::warn!("replay server failed to handle request: {err}");
+            }
+        });
+
+        ReplayServer {
+            addr,
+            state,
+            stopped,
+        }
+    }
+
+    /// from_file starts server replaying exchanges from file written by Recorder.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<ReplayServer, ErrRes> {
+        Ok(ReplayServer::start(load_exchanges(path)?))
+    }
+
+    /// addr returns API endpoint of server.
+    pub fn addr(&self) -> String {
+        self.addr.clone()
+    }
+
+    /// client returns client pointing at server.
+    pub fn client(&self) -> Client {
+        Client::new(Config {
+            addr: Some(self.addr()),
+            key: Some("replay".to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// received returns bodies of requests received so far in order.
+    pub fn received(&self) -> Vec<String> {
+        self.lock().received.clone()
+    }
+
+    /// remaining returns number of exchanges not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.lock().exchanges.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ReplayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        stop_server(&self.addr, &self.stopped);
+    }
+}
+
+fn handle_replay(stream: TcpStream, state: &Mutex<ReplayState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let (_, body) = read_request(&mut reader)?;
+
+    let exchange = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .received
+            .push(String::from_utf8_lossy(&body).into_owned());
+        state.exchanges.pop_front()
+    };
+    match exchange {
+        Some(exchange) => write_response(reader.get_mut(), exchange.status, &exchange.response),
+        None => write_response(reader.get_mut(), 500, "no recorded exchange left"),
+    }
+}
+
+/// decode_commands decodes commands from NDJSON or batch request body.
+fn decode_commands(body: &[u8], batch: bool) -> Result<Vec<Command>, serde_json::Error> {
+    if batch {
+        let body: Value = serde_json::from_slice(body)?;
+        body["commands"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            // batch command is an object with method as its single key.
+            .filter_map(|cmd| {
+                let (method, params) = cmd.as_object()?.iter().next()?;
+                Some(json!({ "method": method, "params": params }))
+            })
+            .map(serde_json::from_value)
+            .collect()
+    } else {
+        String::from_utf8_lossy(body)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect()
+    }
+}
+
+/// encode_replies encodes replies as NDJSON or batch response body.
+fn encode_replies(replies: Vec<Value>, batch: bool) -> String {
+    if batch {
+        json!({ "replies": replies }).to_string()
+    } else {
+        replies
+            .iter()
+            .map(|reply| reply.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// serve decodes commands from body, records them and builds response.
+fn serve(
+    body: &[u8],
+    batch: bool,
+    state: &Mutex<StubState>,
+) -> Result<(u16, String), serde_json::Error> {
+    let commands = decode_commands(body, batch)?;
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(StubReply::Status(status, body)) = state.replies.front().cloned() {
+        state.replies.pop_front();
+        return Ok((status, body));
+    }
+
+    let mut replies = Vec::new();
+    for cmd in &commands {
+        let reply = match state.replies.pop_front() {
+            Some(StubReply::Error(err)) => json!({ "error": err }),
+            Some(StubReply::Result(result)) => reply_result(cmd.method.as_str(), result, batch),
+            // status is only served at the beginning of request.
+            Some(StubReply::Status(..)) | None => {
+                reply_result(cmd.method.as_str(), json!({}), batch)
+            }
+        };
+        replies.push(reply);
+    }
+    state.received.extend(commands);
+
+    Ok((200, encode_replies(replies, batch)))
+}
+
+fn reply_result(method: &str, result: Value, batch: bool) -> Value {
+    let mut reply = Map::new();
+    // batch replies are keyed by method, NDJSON ones by result.
+    let key = if batch { method } else { "result" };
+    reply.insert(key.to_string(), result);
+    Value::Object(reply)
+}
+
+/// FIXTURE_EPOCH is an epoch of history_fixture.
+pub const FIXTURE_EPOCH: &str = "fixture-epoch";
+
+/// client_info_fixture returns info of connection of user, fields can be
+/// overridden with struct update syntax, e.g.
+/// `ClientInfo { conn_info: Some(json!({})), ..client_info_fixture("42") }`.
+pub fn client_info_fixture(user: &str) -> protocol::ClientInfo {
+    protocol::ClientInfo {
+        user: user.to_string(),
+        client: format!("client-{user}"),
+        conn_info: None,
+        chan_info: None,
+    }
+}
+
+/// publication_fixture returns publication with offset and data published by
+/// user 42.
+pub fn publication_fixture(offset: u64, data: Value) -> protocol::Publication {
+    protocol::Publication {
+        offset,
+        data,
+        b64data: None,
+        info: Some(client_info_fixture("42")),
+    }
+}
+
+/// history_fixture returns history of publications with data, offsets start
+/// from 1 and stream top offset is the offset of the last one.
+pub fn history_fixture<I>(data: I) -> protocol::HistoryResult
+where
+    I: IntoIterator<Item = Value>,
+{
+    let publication: Vec<_> = data
+        .into_iter()
+        .zip(1..)
+        .map(|(data, offset)| publication_fixture(offset, data))
+        .collect();
+    protocol::HistoryResult {
+        offset: publication.len() as u64,
+        epoch: FIXTURE_EPOCH.to_string(),
+        publication,
+    }
+}
+
+/// node_info_fixture returns info of a healthy node called name.
+pub fn node_info_fixture(name: &str) -> protocol::NodeInfo {
+    protocol::NodeInfo {
+        uid: format!("{name}-uid"),
+        name: name.to_string(),
+        version: "5.4.0".to_string(),
+        num_clients: 10,
+        num_users: 5,
+        num_channels: 3,
+        uptime: 3600,
+        num_subs: Some(12),
+        process: None,
+        metrics: None,
+    }
+}
+
+/// info_fixture returns info of cluster of nodes called names.
+pub fn info_fixture(names: &[&str]) -> protocol::InfoResult {
+    protocol::InfoResult {
+        nodes: names.iter().map(|name| node_info_fixture(name)).collect(),
+    }
+}
+
+/// result_reply_fixture returns successful reply with result encoded as JSON.
+pub fn result_reply_fixture(result: &impl serde::Serialize) -> protocol::Reply {
+    protocol::Reply {
+        error: None,
+        result: Some(serde_json::to_value(result).expect("encode fixture result")),
+    }
+}
+
+/// error_reply_fixture returns error reply.
+pub fn error_reply_fixture(code: u32, message: &str) -> protocol::Reply {
+    protocol::Reply {
+        error: Some(protocol::Error {
+            code,
+            message: message.to_string(),
+        }),
+        result: None,
+    }
+}
+
+/// response_body_fixture encodes replies as NDJSON body of API response, e.g.
+/// to test decode functions or serve replies from a hand-rolled server.
+pub fn response_body_fixture(replies: &[protocol::Reply]) -> String {
+    replies
+        .iter()
+        .map(|reply| match (&reply.error, &reply.result) {
+            (Some(error), _) => json!({ "error": error }).to_string(),
+            (None, Some(result)) => json!({ "result": result }).to_string(),
+            (None, None) => "{}".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// ContainerConfig configures Centrifugo container started for tests.
+#[derive(Clone, Debug, Default)]
+pub struct ContainerConfig {
+    /// image is a Centrifugo image to run.
+    /// Nil value means DEFAULT_IMAGE.
+    pub image: Option<String>,
+    /// api_key is an API key server is configured with.
+    /// Nil value means key generated for container.
+    pub api_key: Option<String>,
+    /// startup_timeout limits waiting for server to become ready.
+    /// Nil value means 30 seconds.
+    pub startup_timeout: Option<Duration>,
+    /// docker is a path to docker executable.
+    /// Nil value means docker found in PATH.
+    pub docker: Option<String>,
+}
+
+/// CentrifugoContainer is a real Centrifugo server running in docker container,
+/// for integration tests. Container is removed when dropped, statics are never
+/// dropped, so share it between tests through Arc held weakly instead.
+#[derive(Debug)]
+pub struct CentrifugoContainer {
+    id: String,
+    docker: String,
+    addr: String,
+    api_key: String,
+}
+
+impl CentrifugoContainer {
+    /// start runs container and waits until server is ready.
+    pub fn start(config: ContainerConfig) -> Result<CentrifugoContainer, ErrRes> {
+        let docker = config.docker.unwrap_or_else(|| "docker".to_string());
+        let api_key = config.api_key.unwrap_or_else(generate_api_key);
+        let image = config.image.unwrap_or_else(|| DEFAULT_IMAGE.to_string());
+
+        let id = run_docker(
+            &docker,
+            &[
+                "run",
+                "-d",
+                "--label",
+                CONTAINER_LABEL,
+                "-p",
+                "127.0.0.1::8000",
+                "-e",
+                &format!("CENTRIFUGO_API_KEY={api_key}"),
+                "-e",
+                "CENTRIFUGO_HEALTH=true",
+                &image,
+                "centrifugo",
+            ],
+        )?;
+        // container is removed on drop from now on, even if it never gets ready.
+        let mut container = CentrifugoContainer {
+            id,
+            docker,
+            addr: String::new(),
+            api_key,
+        };
+
+        // docker port prints a line per address family, e.g. 127.0.0.1:49153.
+        let port = run_docker(&container.docker, &["port", &container.id, "8000/tcp"])?;
+        let host = port
+            .lines()
+            .next()
+            .ok_or("container port 8000 is not published")?
+            .trim()
+            .to_string();
+
+        wait_healthy(
+            &host,
+            config.startup_timeout.unwrap_or(Duration::from_secs(30)),
+        )?;
+        container.addr = format!("http://{host}/api");
+        Ok(container)
+    }
+
+    /// addr returns API endpoint of server.
+    pub fn addr(&self) -> String {
+        self.addr.clone()
+    }
+
+    /// api_key returns API key server is configured with.
+    pub fn api_key(&self) -> String {
+        self.api_key.clone()
+    }
+
+    /// config returns client config pointing at server.
+    pub fn config(&self) -> Config {
+        Config {
+            addr: Some(self.addr()),
+            key: Some(self.api_key()),
+            ..Default::default()
+        }
+    }
+
+    /// client returns client pointing at server.
+    pub fn client(&self) -> Client {
+        Client::new(self.config())
+    }
+}
+
+impl Drop for CentrifugoContainer {
+    fn drop(&mut self) {
+        if let Err(err) = run_docker(&self.docker, &["rm", "-f", &self.id]) {
+            log::warn!("failed to remove container {}: {err}", self.id);
+        }
+    }
+}
+
+/// run_docker runs docker command and returns its trimmed output.
+fn run_docker(docker: &str, args: &[&str]) -> Result<String, ErrRes> {
+    let output = process::Command::new(docker)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run {docker}: {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{docker} {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// wait_healthy polls health endpoint of server until it responds with 200.
+fn wait_healthy(host: &str, timeout: Duration) -> Result<(), ErrRes> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if health_check(host).unwrap_or(false) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("server at {host} is not ready after {timeout:?}").into());
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn health_check(host: &str) -> std::io::Result<bool> {
+    let mut stream = TcpStream::connect(host)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    write!(
+        stream,
+        "GET /health HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    Ok(status_line.split_whitespace().nth(1) == Some("200"))
+}
+
+/// generate_api_key returns key unique enough for a test container.
+fn generate_api_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("rucent-{:x}-{:x}", process::id(), nanos)
+}
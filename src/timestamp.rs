@@ -0,0 +1,99 @@
+use std::time::SystemTime;
+
+/// Timestamp is a moment server takes and returns as unix seconds, e.g.
+/// expire_at of refresh or last activity of user. It's time::OffsetDateTime
+/// with time feature and raw unix seconds otherwise, sent the same way.
+#[cfg(feature = "time")]
+pub type Timestamp = time::OffsetDateTime;
+
+/// Timestamp is a moment server takes and returns as unix seconds, e.g.
+/// expire_at of refresh or last activity of user. It's time::OffsetDateTime
+/// with time feature and raw unix seconds otherwise, sent the same way.
+#[cfg(not(feature = "time"))]
+pub type Timestamp = i64;
+
+/// from_unix returns timestamp of unix seconds, values out of range supported
+/// by time are clamped.
+#[cfg(feature = "time")]
+pub fn from_unix(seconds: i64) -> Timestamp {
+    time::OffsetDateTime::from_unix_timestamp(seconds).unwrap_or_else(|_| {
+        let bound = if seconds < 0 {
+            time::PrimitiveDateTime::MIN
+        } else {
+            time::PrimitiveDateTime::MAX
+        };
+        bound.assume_utc()
+    })
+}
+
+/// from_unix returns timestamp of unix seconds.
+#[cfg(not(feature = "time"))]
+pub fn from_unix(seconds: i64) -> Timestamp {
+    seconds
+}
+
+/// to_unix returns unix seconds of timestamp.
+#[cfg(feature = "time")]
+pub fn to_unix(timestamp: Timestamp) -> i64 {
+    timestamp.unix_timestamp()
+}
+
+/// to_unix returns unix seconds of timestamp.
+#[cfg(not(feature = "time"))]
+pub fn to_unix(timestamp: Timestamp) -> i64 {
+    timestamp
+}
+
+/// from_system_time returns timestamp of time.
+pub fn from_system_time(time: SystemTime) -> Timestamp {
+    from_unix(crate::options::unix_time(time))
+}
+
+/// unix_epoch returns timestamp of zero unix seconds, default of timestamps
+/// missing in server replies.
+pub(crate) fn unix_epoch() -> Timestamp {
+    from_unix(0)
+}
+
+/// unix_seconds (de)serializes Timestamp as unix seconds, used with
+/// serde(with).
+pub(crate) mod unix_seconds {
+    #[cfg(feature = "time")]
+    pub use time::serde::timestamp::{deserialize, serialize};
+
+    #[cfg(not(feature = "time"))]
+    pub fn serialize<S: serde::Serializer>(
+        timestamp: &super::Timestamp,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*timestamp)
+    }
+
+    #[cfg(not(feature = "time"))]
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<super::Timestamp, D::Error> {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    /// option is the same as unix_seconds for optional timestamps.
+    pub mod option {
+        #[cfg(feature = "time")]
+        pub use time::serde::timestamp::option::{deserialize, serialize};
+
+        #[cfg(not(feature = "time"))]
+        pub fn serialize<S: serde::Serializer>(
+            timestamp: &Option<super::super::Timestamp>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serde::Serialize::serialize(timestamp, serializer)
+        }
+
+        #[cfg(not(feature = "time"))]
+        pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<super::super::Timestamp>, D::Error> {
+            serde::Deserialize::deserialize(deserializer)
+        }
+    }
+}
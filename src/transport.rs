@@ -0,0 +1,122 @@
+use hyper_util::client::legacy::connect::HttpInfo;
+use std::collections::{HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// TRACKED_CONNECTIONS limits number of connections remembered to tell reused
+/// connections from new ones, oldest are forgotten first.
+const TRACKED_CONNECTIONS: usize = 1024;
+
+/// TransportStats is a snapshot of HTTP transport statistics of client and its
+/// clones, returned by Client::transport_stats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransportStats {
+    /// requests_sent is a number of HTTP requests which got response.
+    pub requests_sent: u64,
+    /// requests_in_flight is a number of HTTP requests waiting for response.
+    pub requests_in_flight: u64,
+    /// connections_opened is a number of requests sent over a new connection.
+    pub connections_opened: u64,
+    /// connections_reused is a number of requests sent over pooled connection.
+    pub connections_reused: u64,
+    /// dns_lookups is a number of new connections to endpoints given by host
+    /// name, every such connection resolves it.
+    pub dns_lookups: u64,
+    /// tls_handshakes is a number of new connections to https endpoints.
+    pub tls_handshakes: u64,
+}
+
+/// TransportCounters collects TransportStats from responses. Connections are
+/// told apart by local and remote addresses reported by connector, responses
+/// without them (e.g. from custom connectors) only count as sent requests.
+#[derive(Default)]
+pub struct TransportCounters {
+    requests_sent: AtomicU64,
+    requests_in_flight: AtomicU64,
+    connections_opened: AtomicU64,
+    connections_reused: AtomicU64,
+    dns_lookups: AtomicU64,
+    tls_handshakes: AtomicU64,
+    connections: Mutex<Connections>,
+}
+
+#[derive(Default)]
+struct Connections {
+    seen: HashSet<(SocketAddr, SocketAddr)>,
+    order: VecDeque<(SocketAddr, SocketAddr)>,
+}
+
+/// InFlightGuard keeps request counted as in flight until dropped.
+pub(crate) struct InFlightGuard(Arc<TransportCounters>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.requests_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl TransportCounters {
+    /// start counts request as in flight until returned guard is dropped.
+    pub(crate) fn start(self: &Arc<Self>) -> InFlightGuard {
+        self.requests_in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(self.clone())
+    }
+
+    /// record counts response received from url, returns true when it came
+    /// over a new connection.
+    pub(crate) fn record(&self, url: &reqwest::Url, response: &reqwest::Response) -> bool {
+        self.requests_sent.fetch_add(1, Ordering::Relaxed);
+
+        let info = match response.extensions().get::<HttpInfo>() {
+            Some(info) => info,
+            None => return false,
+        };
+        let key = (info.local_addr(), info.remote_addr());
+
+        let opened = {
+            let mut connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+            let opened = connections.seen.insert(key);
+            if opened {
+                connections.order.push_back(key);
+                if connections.order.len() > TRACKED_CONNECTIONS {
+                    if let Some(oldest) = connections.order.pop_front() {
+                        connections.seen.remove(&oldest);
+                    }
+                }
+            }
+            opened
+        };
+
+        if !opened {
+            self.connections_reused.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+        let host = url.host_str().unwrap_or_default();
+        let is_ip = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok();
+        if !is_ip {
+            self.dns_lookups.fetch_add(1, Ordering::Relaxed);
+        }
+        if url.scheme() == "https" {
+            self.tls_handshakes.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+
+    /// snapshot returns current statistics.
+    pub fn snapshot(&self) -> TransportStats {
+        TransportStats {
+            requests_sent: self.requests_sent.load(Ordering::Relaxed),
+            requests_in_flight: self.requests_in_flight.load(Ordering::Relaxed),
+            connections_opened: self.connections_opened.load(Ordering::Relaxed),
+            connections_reused: self.connections_reused.load(Ordering::Relaxed),
+            dns_lookups: self.dns_lookups.load(Ordering::Relaxed),
+            tls_handshakes: self.tls_handshakes.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::client::{ErrRes, ErrStatusCode};
+use crate::realtime::{push_event, ConnectResult, RawPush, RealtimeEvent, DEFAULT_CLIENT_NAME};
+use futures_util::stream::{self, Stream};
+use reqwest::Client as ReqClient;
+use serde_json::{Map, Value};
+
+/// UniTransport is a unidirectional transport of Centrifugo.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UniTransport {
+    /// Sse uses Server-Sent Events endpoint, e.g. /connection/uni_sse.
+    #[default]
+    Sse,
+    /// HttpStream uses HTTP streaming endpoint, e.g. /connection/uni_http_stream.
+    HttpStream,
+}
+
+/// UniConfig configures unidirectional connection to Centrifugo.
+#[derive(Clone, Debug, Default)]
+pub struct UniConfig {
+    /// url is an endpoint of chosen transport.
+    pub url: String,
+    pub transport: UniTransport,
+    /// token is a connection JWT, channels connection is subscribed to are
+    /// defined by its claims or by server-side subscriptions.
+    /// Nil value means anonymous connection.
+    pub token: Option<String>,
+    /// name is a client name sent in connect request.
+    /// Nil value means DEFAULT_CLIENT_NAME.
+    pub name: Option<String>,
+    /// channels when not empty limits events to these channels, events of
+    /// other server-side subscriptions are skipped.
+    pub channels: Vec<String>,
+    /// http_client is used to establish connection, it should have no request
+    /// timeout since connection is long-lived. Nil value means default http
+    /// client without timeout.
+    pub http_client: Option<ReqClient>,
+}
+
+/// UniConsumer consumes publications over unidirectional transport, for
+/// environments where WebSocket is not available.
+pub struct UniConsumer {
+    response: reqwest::Response,
+    transport: UniTransport,
+    channels: Vec<String>,
+    buffer: Vec<u8>,
+    data: String,
+    done: bool,
+}
+
+impl UniConsumer {
+    /// connect establishes connection and waits for connect push, returns its
+    /// result together with stream of events. Stream ends with Disconnected event.
+    pub async fn connect(
+        config: UniConfig,
+    ) -> Result<(ConnectResult, impl Stream<Item = RealtimeEvent>), ErrRes> {
+        let http_client = match config.http_client {
+            Some(http_client) => http_client,
+            None => {
+                // default http client has request timeout, so it can't be used.
+                let builder = ReqClient::builder();
+                #[cfg(feature = "rustls")]
+                let builder = builder.use_rustls_tls();
+                builder.build()?
+            }
+        };
+
+        let mut params = Map::new();
+        if let Some(token) = config.token {
+            params.insert("token".to_string(), token.into());
+        }
+        params.insert(
+            "name".to_string(),
+            config
+                .name
+                .unwrap_or_else(|| DEFAULT_CLIENT_NAME.to_string())
+                .into(),
+        );
+        let params = Value::Object(params).to_string();
+
+        let request = match config.transport {
+            UniTransport::Sse => http_client
+                .get(&config.url)
+                .query(&[("cf_connect", params)])
+                .header("Accept", "text/event-stream"),
+            UniTransport::HttpStream => http_client
+                .post(&config.url)
+                .header("Content-Type", "application/json")
+                .body(params),
+        };
+        let response = request.send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.bytes().await.unwrap_or_default();
+            return Err(Box::new(ErrStatusCode::new(status.as_u16(), &body, None)));
+        }
+
+        let mut consumer = UniConsumer {
+            response,
+            transport: config.transport,
+            channels: config.channels,
+            buffer: Vec::new(),
+            data: String::new(),
+            done: false,
+        };
+
+        let connect_result = loop {
+            match consumer.next_push().await? {
+                Some(push) => {
+                    if let Some(connect) = push.connect {
+                        break connect;
+                    }
+                }
+                None => return Err("connection closed before connect push".into()),
+            }
+        };
+
+        Ok((connect_result, consumer.into_stream()))
+    }
+
+    fn into_stream(self) -> impl Stream<Item = RealtimeEvent> {
+        stream::unfold(self, |mut consumer| async move {
+            loop {
+                if consumer.done {
+                    return None;
+                }
+
+                let push = match consumer.next_push().await {
+                    Ok(Some(push)) => push,
+                    Ok(None) => {
+                        consumer.done = true;
+                        return Some((
+                            RealtimeEvent::Disconnected {
+                                code: 0,
+                                reason: "connection closed".to_string(),
+                            },
+                            consumer,
+                        ));
+                    }
+                    Err(err) => {
+                        consumer.done = true;
+                        return Some((
+                            RealtimeEvent::Disconnected {
+                                code: 0,
+                                reason: err.to_string(),
+                            },
+                            consumer,
+                        ));
+                    }
+                };
+
+                match push_event(push) {
+                    Some(event @ RealtimeEvent::Disconnected { .. }) => {
+                        consumer.done = true;
+                        return Some((event, consumer));
+                    }
+                    Some(event) if consumer.wanted(&event) => return Some((event, consumer)),
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    /// wanted tells whether event belongs to one of configured channels.
+    fn wanted(&self, event: &RealtimeEvent) -> bool {
+        let channel = match event {
+            RealtimeEvent::Publication { channel, .. }
+            | RealtimeEvent::Join { channel, .. }
+            | RealtimeEvent::Leave { channel, .. }
+            | RealtimeEvent::Unsubscribed { channel, .. } => channel,
+            RealtimeEvent::Disconnected { .. } => return true,
+        };
+        self.channels.is_empty() || self.channels.contains(channel)
+    }
+
+    /// next_push returns next push received from server, pings are skipped.
+    /// None is returned when response ended.
+    async fn next_push(&mut self) -> Result<Option<RawPush>, ErrRes> {
+        loop {
+            let message = match self.next_message().await? {
+                Some(message) => message,
+                None => return Ok(None),
+            };
+            let value: Value = serde_json::from_str(&message)?;
+            // empty message is a ping from server.
+            if value.as_object().is_some_and(|object| object.is_empty()) {
+                continue;
+            }
+            return Ok(Some(serde_json::from_value(value)?));
+        }
+    }
+
+    /// next_message returns next message according to transport framing: data
+    /// of SSE event or a line of HTTP stream.
+    async fn next_message(&mut self) -> Result<Option<String>, ErrRes> {
+        loop {
+            while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches(['\r', '\n']);
+
+                match self.transport {
+                    UniTransport::HttpStream => {
+                        if !line.trim().is_empty() {
+                            return Ok(Some(line.to_string()));
+                        }
+                    }
+                    UniTransport::Sse => {
+                        if line.is_empty() {
+                            if !self.data.is_empty() {
+                                return Ok(Some(std::mem::take(&mut self.data)));
+                            }
+                        } else if let Some(data) = line.strip_prefix("data:") {
+                            if !self.data.is_empty() {
+                                self.data.push('\n');
+                            }
+                            self.data.push_str(data.strip_prefix(' ').unwrap_or(data));
+                        }
+                        // comments, event names and ids are not used by Centrifugo.
+                    }
+                }
+            }
+
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::client::{Client, ErrRes};
+use crate::options::{
+    DisconnectOption, DisconnectOptions, RefreshOption, RefreshOptions, SubscribeOption,
+    SubscribeOptions, UnsubscribeOption, UnsubscribeOptions,
+};
+use crate::protocol::UserStatus;
+
+/// UserHandle is a client bound to a single user, returned by Client::user.
+/// It's cheap to clone and shares client state.
+#[derive(Clone)]
+pub struct UserHandle {
+    client: Client,
+    user: String,
+}
+
+impl UserHandle {
+    pub(crate) fn new(client: Client, user: String) -> Self {
+        UserHandle { client, user }
+    }
+
+    /// id returns user handle is bound to.
+    pub fn id(&self) -> &str {
+        &self.user
+    }
+
+    /// Subscribe subscribes user to channel (using server-side subscriptions).
+    pub async fn subscribe(&self, channel: String, opts: &[SubscribeOption]) -> Result<(), ErrRes> {
+        self.client
+            .subscribe(channel, self.user.clone(), opts)
+            .await
+    }
+
+    /// SubscribeWithOptions is the same as subscribe but takes options built
+    /// with SubscribeOptions::new() instead of closures.
+    pub async fn subscribe_with_options(
+        &self,
+        channel: String,
+        options: SubscribeOptions,
+    ) -> Result<(), ErrRes> {
+        self.client
+            .subscribe_with_options(channel, self.user.clone(), options)
+            .await
+    }
+
+    /// Unsubscribe unsubscribes user from channel.
+    pub async fn unsubscribe(
+        &self,
+        channel: String,
+        opts: &[UnsubscribeOption],
+    ) -> Result<(), ErrRes> {
+        self.client
+            .unsubscribe(channel, self.user.clone(), opts)
+            .await
+    }
+
+    /// UnsubscribeWithOptions is the same as unsubscribe but takes options
+    /// built with UnsubscribeOptions::new() instead of closures.
+    pub async fn unsubscribe_with_options(
+        &self,
+        channel: String,
+        options: UnsubscribeOptions,
+    ) -> Result<(), ErrRes> {
+        self.client
+            .unsubscribe_with_options(channel, self.user.clone(), options)
+            .await
+    }
+
+    /// Disconnect disconnects user connections.
+    pub async fn disconnect(&self, opts: &[DisconnectOption]) -> Result<(), ErrRes> {
+        self.client.disconnect(self.user.clone(), opts).await
+    }
+
+    /// DisconnectWithOptions is the same as disconnect but takes options built
+    /// with DisconnectOptions::new() instead of closures.
+    pub async fn disconnect_with_options(&self, options: DisconnectOptions) -> Result<(), ErrRes> {
+        self.client
+            .disconnect_with_options(self.user.clone(), options)
+            .await
+    }
+
+    /// Refresh refreshes user connections, e.g. to prolong their expiration.
+    pub async fn refresh(&self, opts: &[RefreshOption]) -> Result<(), ErrRes> {
+        self.client.refresh(self.user.clone(), opts).await
+    }
+
+    /// RefreshWithOptions is the same as refresh but takes options built with
+    /// RefreshOptions::new() instead of closures.
+    pub async fn refresh_with_options(&self, options: RefreshOptions) -> Result<(), ErrRes> {
+        self.client
+            .refresh_with_options(self.user.clone(), options)
+            .await
+    }
+
+    /// UpdateStatus updates user status (Centrifugo PRO).
+    pub async fn update_status(&self, state: Option<String>) -> Result<(), ErrRes> {
+        self.client
+            .update_user_status(vec![self.user.clone()], state)
+            .await
+    }
+
+    /// Status returns user status, None if server knows nothing about user
+    /// (Centrifugo PRO).
+    pub async fn status(&self) -> Result<Option<UserStatus>, ErrRes> {
+        let result = self.client.get_user_status(vec![self.user.clone()]).await?;
+        Ok(result
+            .statuses
+            .into_iter()
+            .find(|status| status.user == self.user))
+    }
+
+    /// DeleteStatus deletes user status (Centrifugo PRO).
+    pub async fn delete_status(&self) -> Result<(), ErrRes> {
+        self.client
+            .delete_user_status(vec![self.user.clone()])
+            .await
+    }
+}
@@ -0,0 +1,78 @@
+use crate::pattern::namespace;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// DataValidator checks publication data, returning description of violation,
+/// e.g. a closure around compiled JSON Schema.
+pub type DataValidator = Arc<dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync>;
+
+// ErrInvalidData is returned before sending when publication data is rejected
+// by validator registered in Config.data_validators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrInvalidData {
+    pub channel: String,
+    pub message: String,
+}
+
+// Implement the `std::fmt::Display` trait for `ErrInvalidData`
+impl fmt::Display for ErrInvalidData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid data for channel {}: {}",
+            self.channel, self.message
+        )
+    }
+}
+
+// Implement the `Error` trait for `ErrInvalidData`
+impl Error for ErrInvalidData {}
+
+/// DataValidators keeps validators of publication data registered per channel
+/// and per namespace. Validator of channel takes precedence over validator of
+/// its namespace, data of channels without validators isn't checked.
+#[derive(Clone, Default)]
+pub struct DataValidators {
+    channels: HashMap<String, DataValidator>,
+    namespaces: HashMap<String, DataValidator>,
+}
+
+impl DataValidators {
+    /// Create an empty set of validators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// with_channel registers validator of channel.
+    pub fn with_channel(mut self, channel: impl Into<String>, validator: DataValidator) -> Self {
+        self.channels.insert(channel.into(), validator);
+        self
+    }
+
+    /// with_namespace registers validator of all channels in namespace.
+    pub fn with_namespace(
+        mut self,
+        namespace: impl Into<String>,
+        validator: DataValidator,
+    ) -> Self {
+        self.namespaces.insert(namespace.into(), validator);
+        self
+    }
+
+    /// validate checks data published into channel.
+    pub fn validate(&self, channel: &str, data: &serde_json::Value) -> Result<(), ErrInvalidData> {
+        let validator = self
+            .channels
+            .get(channel)
+            .or_else(|| namespace(channel).and_then(|namespace| self.namespaces.get(namespace)));
+        match validator {
+            Some(validator) => validator(data).map_err(|message| ErrInvalidData {
+                channel: channel.to_string(),
+                message,
+            }),
+            None => Ok(()),
+        }
+    }
+}
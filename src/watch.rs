@@ -0,0 +1,257 @@
+use crate::client::{CallOptions, Client, ErrRes};
+use crate::options::ChannelsOptions;
+use crate::protocol::ClientInfo;
+use crate::shutdown::{ActivityGuard, Lifecycle};
+use futures_util::stream::{self, Stream};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// ChannelEvent is a change in active channels noticed by ChannelsWatcher.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChannelEvent {
+    /// Created is emitted when channel got its first subscriber.
+    Created { channel: String, num_clients: u32 },
+    /// Removed is emitted when channel has no subscribers anymore.
+    Removed { channel: String },
+    /// CountChanged is emitted when number of channel subscribers changed.
+    CountChanged { channel: String, old: u32, new: u32 },
+}
+
+/// ChannelsWatcher polls channels on an interval and emits differences
+/// between consecutive results as ChannelEvent stream.
+pub struct ChannelsWatcher {
+    client: Client,
+    interval: Duration,
+    options: ChannelsOptions,
+}
+
+impl ChannelsWatcher {
+    /// Create a new watcher polling channels through client every interval,
+    /// results cache of client is bypassed.
+    pub fn new(client: &Client, interval: Duration) -> Self {
+        ChannelsWatcher {
+            client: client.with_call_options(CallOptions::default().force_refresh(true)),
+            interval,
+            options: ChannelsOptions::default(),
+        }
+    }
+
+    /// with_pattern allows to watch only channels matching pattern.
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.options.pattern = Some(pattern.into());
+        self
+    }
+
+    /// stream starts polling and returns stream of events. Channels active at the
+    /// first poll are emitted as Created, failed polls are yielded as errors and
+    /// polling goes on until client is shut down.
+    pub fn stream(self) -> impl Stream<Item = Result<ChannelEvent, ErrRes>> {
+        let ChannelsWatcher {
+            client,
+            interval,
+            options,
+        } = self;
+
+        poll_stream(
+            client.lifecycle.clone(),
+            interval,
+            move || {
+                let client = client.clone();
+                let options = options.clone();
+                async move {
+                    let result = client.channels_with_options(options).await?;
+                    Ok(result
+                        .channels
+                        .into_iter()
+                        .map(|(channel, info)| (channel, info.num_clients))
+                        .collect())
+                }
+            },
+            diff_channels,
+        )
+    }
+}
+
+/// diff_channels returns events turning previous channel counts into current,
+/// ordered by channel name.
+pub fn diff_channels(
+    previous: &HashMap<String, u32>,
+    current: &HashMap<String, u32>,
+) -> Vec<ChannelEvent> {
+    let mut events = Vec::new();
+    for (channel, &num_clients) in current {
+        match previous.get(channel) {
+            None => events.push(ChannelEvent::Created {
+                channel: channel.clone(),
+                num_clients,
+            }),
+            Some(&old) if old != num_clients => events.push(ChannelEvent::CountChanged {
+                channel: channel.clone(),
+                old,
+                new: num_clients,
+            }),
+            _ => {}
+        }
+    }
+    for channel in previous.keys() {
+        if !current.contains_key(channel) {
+            events.push(ChannelEvent::Removed {
+                channel: channel.clone(),
+            });
+        }
+    }
+
+    events.sort_by(|a, b| event_channel(a).cmp(event_channel(b)));
+    events
+}
+
+fn event_channel(event: &ChannelEvent) -> &str {
+    match event {
+        ChannelEvent::Created { channel, .. }
+        | ChannelEvent::Removed { channel }
+        | ChannelEvent::CountChanged { channel, .. } => channel,
+    }
+}
+
+/// PresenceEvent is a change in channel presence noticed by PresenceWatcher.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PresenceEvent {
+    /// Joined is emitted when client connection appeared in channel presence.
+    Joined(ClientInfo),
+    /// Left is emitted when client connection disappeared from channel presence.
+    Left(ClientInfo),
+}
+
+/// PresenceWatcher polls presence of channel on an interval and emits clients
+/// joined and left between consecutive results as PresenceEvent stream.
+pub struct PresenceWatcher {
+    client: Client,
+    channel: String,
+    interval: Duration,
+}
+
+impl PresenceWatcher {
+    /// Create a new watcher polling presence of channel through client every interval.
+    pub fn new(client: &Client, channel: impl Into<String>, interval: Duration) -> Self {
+        PresenceWatcher {
+            client: client.clone(),
+            channel: channel.into(),
+            interval,
+        }
+    }
+
+    /// stream starts polling and returns stream of events. Clients present at the
+    /// first poll are emitted as Joined, failed polls are yielded as errors and
+    /// polling goes on until client is shut down.
+    pub fn stream(self) -> impl Stream<Item = Result<PresenceEvent, ErrRes>> {
+        let PresenceWatcher {
+            client,
+            channel,
+            interval,
+        } = self;
+
+        poll_stream(
+            client.lifecycle.clone(),
+            interval,
+            move || {
+                let client = client.clone();
+                let channel = channel.clone();
+                async move { Ok(client.presence(channel).await?.presence) }
+            },
+            diff_presence,
+        )
+    }
+}
+
+/// diff_presence returns events turning previous presence into current, keyed
+/// by client id, ordered by client id.
+pub fn diff_presence(
+    previous: &HashMap<String, ClientInfo>,
+    current: &HashMap<String, ClientInfo>,
+) -> Vec<PresenceEvent> {
+    let mut left: Vec<&ClientInfo> = previous
+        .iter()
+        .filter(|(client, _)| !current.contains_key(*client))
+        .map(|(_, info)| info)
+        .collect();
+    left.sort_by(|a, b| a.client.cmp(&b.client));
+
+    let mut joined: Vec<&ClientInfo> = current
+        .iter()
+        .filter(|(client, _)| !previous.contains_key(*client))
+        .map(|(_, info)| info)
+        .collect();
+    joined.sort_by(|a, b| a.client.cmp(&b.client));
+
+    left.into_iter()
+        .map(|info| PresenceEvent::Left(info.clone()))
+        .chain(
+            joined
+                .into_iter()
+                .map(|info| PresenceEvent::Joined(info.clone())),
+        )
+        .collect()
+}
+
+struct PollState<S, E, P> {
+    lifecycle: Arc<Lifecycle>,
+    _guard: ActivityGuard,
+    poll: P,
+    interval: Duration,
+    polled: bool,
+    known: S,
+    pending: VecDeque<E>,
+}
+
+/// poll_stream calls poll every interval and yields events returned by diff
+/// for consecutive results, starting from default state. Stream ends once
+/// lifecycle is closed.
+fn poll_stream<S, E, P, F>(
+    lifecycle: Arc<Lifecycle>,
+    interval: Duration,
+    poll: P,
+    diff: fn(&S, &S) -> Vec<E>,
+) -> impl Stream<Item = Result<E, ErrRes>>
+where
+    S: Default,
+    P: FnMut() -> F,
+    F: Future<Output = Result<S, ErrRes>>,
+{
+    let state = PollState {
+        _guard: lifecycle.task(),
+        lifecycle,
+        poll,
+        interval,
+        polled: false,
+        known: S::default(),
+        pending: VecDeque::new(),
+    };
+
+    stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((Ok(event), state));
+            }
+
+            if state.polled {
+                tokio::select! {
+                    _ = tokio::time::sleep(state.interval) => {}
+                    _ = state.lifecycle.closed() => return None,
+                }
+            }
+            if state.lifecycle.is_closed() {
+                return None;
+            }
+            state.polled = true;
+
+            let current = match (state.poll)().await {
+                Ok(current) => current,
+                Err(err) => return Some((Err(err), state)),
+            };
+            state.pending.extend(diff(&state.known, &current));
+            state.known = current;
+        }
+    })
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::serve;
+use rucent::admin::{AdminClient, AdminConfig};
+use rucent::client::ErrStatusCode;
+use rucent::options::DisconnectOptions;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn base_addr(addr: &str) -> String {
+        addr.trim_end_matches("/api").to_string()
+    }
+
+    #[test]
+    fn test_admin_login_and_info() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"token":"t1"}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let client = AdminClient::new(AdminConfig {
+            addr: base_addr(&addr),
+            password: Some("secret".to_string()),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let result = rt.block_on(client.info()).unwrap();
+        assert!(result.nodes.is_empty());
+
+        let requests = requests.join().unwrap();
+        assert!(requests[0].0.starts_with("POST /admin/auth"));
+        assert_eq!(requests[0].1, b"password=secret");
+        assert!(requests[1].0.starts_with("POST /admin/api"));
+        assert!(requests[1]
+            .0
+            .to_lowercase()
+            .contains("authorization: token t1"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].1).unwrap();
+        assert_eq!(body["method"], "info");
+    }
+
+    #[test]
+    fn test_admin_relogin_on_unauthorized() {
+        let (addr, requests) = serve(vec![
+            (401, String::new(), Vec::new()),
+            (200, String::new(), br#"{"token":"t2"}"#.to_vec()),
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+        ]);
+        let client = AdminClient::new(AdminConfig {
+            addr: base_addr(&addr),
+            password: Some("secret".to_string()),
+            token: Some("expired".to_string()),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.disconnect("42".to_string(), DisconnectOptions::new()))
+            .unwrap();
+
+        let requests = requests.join().unwrap();
+        assert!(requests[0]
+            .0
+            .to_lowercase()
+            .contains("authorization: token expired"));
+        assert!(requests[2]
+            .0
+            .to_lowercase()
+            .contains("authorization: token t2"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[2].1).unwrap();
+        assert_eq!(body["method"], "disconnect");
+        assert_eq!(body["params"]["user"], "42");
+    }
+
+    #[test]
+    fn test_admin_token_without_password() {
+        let (addr, _requests) = serve(vec![(401, String::new(), Vec::new())]);
+        let client = AdminClient::new(AdminConfig {
+            addr: base_addr(&addr),
+            token: Some("expired".to_string()),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 401);
+    }
+}
@@ -0,0 +1,55 @@
+use rucent::caps::{Capabilities, Match, Permission, CAPS_CLAIM};
+use serde_json::json;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_capabilities_claims() {
+        let caps = Capabilities::new()
+            .allow(["news", "user_42"], &[Permission::Subscribe])
+            .allow_wildcard(
+                ["chat:*"],
+                &[
+                    Permission::Subscribe,
+                    Permission::Publish,
+                    Permission::Presence,
+                    Permission::History,
+                ],
+            )
+            .allow_regex([r"^room:\d+$"], &[Permission::Subscribe]);
+
+        let claims = caps.claims().unwrap();
+        assert_eq!(
+            claims[CAPS_CLAIM],
+            json!([
+                {"channels": ["news", "user_42"], "allow": ["sub"]},
+                {"channels": ["chat:*"], "allow": ["sub", "pub", "prs", "hst"], "match": "wildcard"},
+                {"channels": [r"^room:\d+$"], "allow": ["sub"], "match": "regex"},
+            ])
+        );
+
+        let decoded: Capabilities = serde_json::from_value(claims[CAPS_CLAIM].clone()).unwrap();
+        assert_eq!(decoded, caps);
+        assert_eq!(decoded.caps[0].match_, Match::Exact);
+    }
+
+    #[test]
+    fn test_capabilities_validate() {
+        assert!(Capabilities::new().is_empty());
+
+        let err = Capabilities::new()
+            .allow_wildcard(["chat:[0-9"], &[Permission::Subscribe])
+            .claims()
+            .unwrap_err();
+        assert_eq!(err.pattern, "chat:[0-9");
+
+        // exact channels are never compiled as patterns.
+        assert!(Capabilities::new()
+            .allow(["chat:[0-9"], &[Permission::Subscribe])
+            .validate()
+            .is_ok());
+    }
+}
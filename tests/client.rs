@@ -1,84 +1,2741 @@
 #![allow(unused_imports)]
+mod common;
+
+use common::{serve, serve_keep_alive, serve_once, serve_once_raw};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_util::StreamExt;
 use lazy_static::lazy_static;
-use rucent::client::{decode_publish, Client, Config};
-use rucent::options::{with_disconnect, with_skip_history, Disconnect};
-use std::env;
+use rucent::bearer::{BearerToken, TokenProvider};
+use rucent::client::{
+    decode_publish, decode_replies, normalize_addr, AuthScheme, BasicAuth, CallOptions, Client,
+    Config, ErrPayloadTooLarge, ErrRequestFailed, ErrRes, ErrResponseTooLarge, ErrStatusCode,
+    DEFAULT_USER_AGENT, MAX_RETRY_FAILED_ATTEMPTS, USER_STATUS_CHUNK_SIZE,
+};
+use rucent::discovery::StaticDiscovery;
+use rucent::endpoint::GetAddr;
+use rucent::error::ErrorExt;
+use rucent::hooks::{
+    ConnectionEvent, ErrorEvent, FallbackEvent, Hook, RequestEvent, ResponseEvent, RetryEvent,
+};
+use rucent::middleware::{CommandMiddleware, Middleware, COMMAND_FILTERED_CODE};
+use rucent::options::{
+    with_auto_idempotency_key, with_disconnect, with_idempotency_key, with_limit,
+    with_skip_history, Disconnect, PublishOptions,
+};
+use rucent::pipe::{
+    Command, DeviceListRequest, Method, PipeCommandError, RateLimitRequest, RequestKind,
+    UserTopicListRequest,
+};
+use rucent::protocol::{Error as ProtocolError, TypedResult};
+use rucent::retry::{dead_letter_channel, RetryPolicy};
+use rucent::shutdown::{ErrClientClosed, ShutdownReport};
+#[cfg(feature = "with_local_server")]
+use rucent::testing::{CentrifugoContainer, ContainerConfig};
+use rucent::timestamp::{from_unix, to_unix};
+use rucent::transport::TransportStats;
+use rucent::validation::{DataValidators, ErrInvalidData};
+use std::io::Write;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
-#[cfg(test)]
-mod tests {
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    lazy_static! {
+        static ref ADDR: String = "http://127.0.0.1:8000/api".to_string();
+        static ref API_KEY: String = "default_api_key_hex".to_string();
+    }
+
+    // Centrifugo container is shared by tests running at the same time, lazy
+    // statics are never dropped, so it's held weakly.
+    #[cfg(feature = "with_local_server")]
+    lazy_static! {
+        static ref SERVER: Mutex<std::sync::Weak<CentrifugoContainer>> =
+            Mutex::new(std::sync::Weak::new());
+    }
+
+    /// server returns running Centrifugo container, it's removed once the last
+    /// test holding it finishes.
+    #[cfg(feature = "with_local_server")]
+    fn server() -> Arc<CentrifugoContainer> {
+        let mut shared = SERVER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(server) = shared.upgrade() {
+            return server;
+        }
+        let server = Arc::new(
+            CentrifugoContainer::start(ContainerConfig::default())
+                .expect("failed to start Centrifugo container"),
+        );
+        *shared = Arc::downgrade(&server);
+        server
+    }
+
+    #[test]
+    #[cfg(feature = "with_local_server")]
+    fn test_with_local_server() {
+        let rt = Runtime::new().unwrap();
+        let result = rt.block_on(server().client().info());
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[test]
+    fn test_decode_publish_valid_json() {
+        let json_data = r#"
+        {
+            "epoch": "1789378957", 
+            "offset": 42
+        }"#;
+
+        let result = decode_publish(json_data.as_bytes()).unwrap();
+        assert_eq!(result.offset, Some(42));
+    }
+
+    #[test]
+    fn test_decode_publish_empty_slice() {
+        let empty_slice: &[u8] = &[];
+        let result = decode_publish(empty_slice);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_publish_malformed_json() {
+        let malformed_json = b"{\"channel\": \"test_channel\", \"offset\": 42";
+        let result = decode_publish(malformed_json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_client_new() {
+        let config = Config {
+            addr: Some(ADDR.to_string()),
+            get_addr: None,
+            key: Some(API_KEY.to_string()),
+            http_client: None,
+            ..Default::default()
+        };
+        let client = Client::new(config);
+        assert_eq!(client.endpoint, Some(ADDR.to_string()));
+        assert_eq!(client.api_key, Some(API_KEY.to_string()));
+    }
+
+    #[test]
+    fn test_transport_error_has_context() {
+        let config = Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+        pipe.add_presence("test_channel".to_string()).unwrap();
+
+        let err = rt.block_on(client.send_pipe(&pipe)).unwrap_err();
+        let err = err.downcast_ref::<ErrRequestFailed>().unwrap();
+        assert_eq!(err.methods, vec!["info", "presence"]);
+        assert_eq!(err.endpoint, "http://127.0.0.1:1/api");
+        assert_eq!(err.attempt, 1);
+    }
+
+    #[test]
+    fn test_response_too_large() {
+        let addr = serve_once(format!(r#"{{"result":{{"data":"{}"}}}}"#, "x".repeat(1024)));
+        let config = Config {
+            addr: Some(addr),
+            max_response_size: Some(512),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+
+        let err = rt.block_on(client.send_pipe(&pipe)).unwrap_err();
+        let err = err.downcast_ref::<ErrResponseTooLarge>().unwrap();
+        assert_eq!(err.limit, 512);
+        assert!(err.size > 512);
+    }
+
+    #[test]
+    fn test_publication_too_large() {
+        let config = Config {
+            // nothing listens there, command must fail before sending.
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            max_publication_size: Some(16),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let data = format!(r#"{{"text":"{}"}}"#, "x".repeat(32));
+        let err = rt
+            .block_on(client.publish("chat".to_string(), &data, &[]))
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrPayloadTooLarge>().unwrap(),
+            &ErrPayloadTooLarge {
+                size: data.len(),
+                limit: 16,
+                method: Some("publish".to_string()),
+            }
+        );
+
+        let err = rt
+            .block_on(client.broadcast(vec!["a".to_string(), "b".to_string()], &data, &[]))
+            .unwrap_err();
+        let err = err.downcast_ref::<ErrPayloadTooLarge>().unwrap();
+        assert_eq!(err.method.as_deref(), Some("broadcast"));
+    }
+
+    #[test]
+    fn test_data_validators() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            data_validators: Some(DataValidators::new().with_namespace(
+                "chat",
+                Arc::new(|data| match data.get("text") {
+                    Some(_) => Ok(()),
+                    None => Err("missing text".to_string()),
+                }),
+            )),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt
+            .block_on(client.broadcast(
+                vec!["news".to_string(), "chat:index".to_string()],
+                r#"{"title":"hi"}"#,
+                &[],
+            ))
+            .unwrap_err();
+        let err = err.downcast_ref::<ErrInvalidData>().unwrap();
+        assert_eq!(err.channel, "chat:index");
+
+        rt.block_on(client.publish("chat:index".to_string(), r#"{"text":"hi"}"#, &[]))
+            .unwrap();
+        assert_eq!(requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_request_too_large() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            max_publication_size: Some(64),
+            max_request_size: Some(128),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        for _ in 0..4 {
+            pipe.add_publish("chat".to_string(), r#"{"text":"hello"}"#, &[])
+                .unwrap();
+        }
+        let err = rt.block_on(client.send_pipe(&pipe)).unwrap_err();
+        let err = err.downcast_ref::<ErrPayloadTooLarge>().unwrap();
+        assert_eq!(err.limit, 128);
+        assert!(err.size > 128);
+        assert_eq!(err.method, None);
+        assert!(err.to_string().starts_with("request too large"));
+
+        rt.block_on(client.publish("chat".to_string(), r#"{"text":"hello"}"#, &[]))
+            .unwrap();
+        assert_eq!(requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_response_within_limit() {
+        let addr = serve_once(r#"{"result":{}}"#.to_string());
+        let config = Config {
+            addr: Some(addr),
+            max_response_size: Some(512),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+
+        let replies = rt.block_on(client.send_pipe(&pipe)).unwrap();
+        assert_eq!(replies.len(), 1);
+    }
+
+    #[test]
+    fn test_gzip_response_decoded() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(br#"{"result":{"nodes":[]}}"#).unwrap();
+        let (addr, request) =
+            serve_once_raw("Content-Encoding: gzip\r\n", encoder.finish().unwrap());
+
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let result = rt.block_on(client.info()).unwrap();
+        assert!(result.nodes.is_empty());
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("accept-encoding: gzip, br, deflate"));
+    }
+
+    #[test]
+    fn test_decompression_disabled() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+
+        let config = Config {
+            addr: Some(addr),
+            decompression: Some(false),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(!request_headers.contains("accept-encoding"));
+    }
+
+    #[test]
+    fn test_default_user_agent() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains(&format!("user-agent: {}", DEFAULT_USER_AGENT)));
+        assert!(!request_headers.contains("x-client-name"));
+    }
+
+    #[test]
+    fn test_custom_user_agent_and_client_name() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            user_agent: Some("billing/2.1".to_string()),
+            client_name: Some("billing".to_string()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("user-agent: billing/2.1"));
+        assert!(request_headers.contains("x-client-name: billing"));
+    }
+
+    #[test]
+    fn test_default_auth_scheme() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            key: Some("secret".to_string()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("authorization: apikey secret"));
+    }
+
+    #[test]
+    fn test_auth_schemes() {
+        let cases = vec![
+            (AuthScheme::ApiKeyHeader, Some("x-api-key: secret")),
+            (
+                AuthScheme::CustomHeader("X-Gateway-Auth".to_string(), "Token {key}".to_string()),
+                Some("x-gateway-auth: token secret"),
+            ),
+            (AuthScheme::None, None),
+        ];
+
+        for (auth_scheme, expected) in cases {
+            let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+            let config = Config {
+                addr: Some(addr),
+                key: Some("secret".to_string()),
+                auth_scheme: Some(auth_scheme),
+                ..Default::default()
+            };
+
+            let rt = Runtime::new().unwrap();
+            let client = Client::new(config);
+            rt.block_on(client.info()).unwrap();
+
+            let request_headers = request.join().unwrap().to_lowercase();
+            assert!(!request_headers.contains("authorization"));
+            match expected {
+                Some(header) => assert!(request_headers.contains(header)),
+                None => assert!(!request_headers.contains("secret")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            key: Some("secret".to_string()),
+            auth_scheme: Some(AuthScheme::ApiKeyHeader),
+            basic_auth: Some(BasicAuth {
+                username: "proxy".to_string(),
+                password: Some("pass".to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        // base64 of "proxy:pass".
+        assert!(request_headers.contains("authorization: basic chjvehk6cgfzcw=="));
+        assert!(request_headers.contains("x-api-key: secret"));
+    }
+
+    #[test]
+    fn test_basic_auth_conflicts_with_api_key() {
+        let basic_auth = BasicAuth {
+            username: "proxy".to_string(),
+            password: Some("pass".to_string()),
+        };
+        let err = Client::try_new(Config {
+            addr: Some("http://localhost:8000".to_string()),
+            key: Some("secret".to_string()),
+            basic_auth: Some(basic_auth.clone()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.field, "basic_auth");
+
+        let err = Client::try_new(Config {
+            addr: Some("http://localhost:8000".to_string()),
+            key: Some("secret".to_string()),
+            auth_scheme: Some(AuthScheme::Authorization),
+            token_provider: Some(counting_provider(
+                Arc::new(AtomicUsize::new(0)),
+                Duration::from_secs(60),
+            )),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.field, "token_provider");
+
+        // client built without validation sends a single Authorization header.
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let client = Client::new(Config {
+            addr: Some(addr),
+            key: Some("secret".to_string()),
+            basic_auth: Some(basic_auth),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert_eq!(request_headers.matches("authorization:").count(), 1);
+        assert!(request_headers.contains("authorization: basic chjvehk6cgfzcw=="));
+    }
+
+    fn counting_provider(calls: Arc<AtomicUsize>, ttl: Duration) -> TokenProvider {
+        Arc::new(move || {
+            let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move {
+                Ok(BearerToken {
+                    token: format!("t{n}"),
+                    expires_at: Some(Instant::now() + ttl),
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn test_bearer_token_refreshed_on_unauthorized() {
+        let (addr, requests) = serve(vec![
+            (401, String::new(), b"unauthorized".to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let config = Config {
+            addr: Some(addr),
+            token_provider: Some(counting_provider(calls.clone(), Duration::from_secs(3600))),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let requests = requests.join().unwrap();
+        let headers: Vec<String> = requests.iter().map(|(h, _)| h.to_lowercase()).collect();
+        assert!(headers[0].contains("authorization: bearer t1"));
+        assert!(headers[1].contains("authorization: bearer t2"));
+        assert!(headers[2].contains("authorization: bearer t2"));
+    }
+
+    #[test]
+    fn test_bearer_token_refreshed_before_expiry() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let config = Config {
+            addr: Some(addr),
+            token_provider: Some(counting_provider(calls.clone(), Duration::from_secs(10))),
+            token_refresh_before: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_send_pipe_with_call_options() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+
+        let call_opts = CallOptions::default().header("X-Tenant", "acme");
+        rt.block_on(client.send_pipe_with_options(&pipe, &call_opts))
+            .unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("x-tenant: acme"));
+    }
+
+    #[test]
+    fn test_client_with_call_options() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let tenant_client =
+            client.with_call_options(CallOptions::default().header("X-Tenant", "acme"));
+        rt.block_on(tenant_client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("x-tenant: acme"));
+        assert!(client.call_options.headers.is_empty());
+    }
+
+    #[test]
+    fn test_call_options_api_key() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            key: Some("default".to_string()),
+            results_cache_ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        // info of another project is not served from cache of default one.
+        let project = client.with_call_options(CallOptions::default().api_key("project"));
+        rt.block_on(project.info()).unwrap();
+        rt.block_on(project.info()).unwrap();
+
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+        let call_opts = CallOptions::default().api_key("single");
+        rt.block_on(client.send_pipe_with_options(&pipe, &call_opts))
+            .unwrap();
+
+        let headers: Vec<String> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(headers, _)| headers.to_lowercase())
+            .collect();
+        assert_eq!(headers.len(), 3);
+        assert!(headers[0].contains("authorization: apikey default"));
+        assert!(headers[1].contains("authorization: apikey project"));
+        assert!(headers[2].contains("authorization: apikey single"));
+        assert!(client.call_options.api_key.is_none());
+    }
+
+    #[test]
+    fn test_normalize_addr() {
+        for (addr, expected) in [
+            ("http://localhost:8000", "http://localhost:8000/api"),
+            (" http://localhost:8000/ ", "http://localhost:8000/api"),
+            (
+                "http://example.com/centrifugo/api/",
+                "http://example.com/centrifugo/api",
+            ),
+        ] {
+            assert_eq!(normalize_addr(addr).unwrap(), expected);
+        }
+
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        assert_eq!(
+            normalize_addr("https://example.com/api/").unwrap(),
+            "https://example.com/api"
+        );
+        // client without TLS stack speaks plain HTTP only.
+        #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+        assert!(normalize_addr("https://example.com/api")
+            .unwrap_err()
+            .message
+            .contains("requires TLS"));
+
+        for addr in ["localhost:8000", "ftp://example.com/api", "http://", ""] {
+            let err = normalize_addr(addr).unwrap_err();
+            assert_eq!(err.field, "addr", "{addr}");
+        }
+    }
+
+    #[test]
+    fn test_client_try_new() {
+        let client = Client::try_new(Config {
+            addr: Some("http://localhost:8000/".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            client.endpoint,
+            Some("http://localhost:8000/api".to_string())
+        );
+
+        let err = Client::try_new(Config::default()).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("either addr, addrs, discovery or get_addr"));
+
+        let err = Client::try_new(Config {
+            addr: Some("localhost:8000".to_string()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert!(err.message.contains("no scheme"));
+
+        let err = Client::try_new(Config {
+            addr: Some("http://localhost:8000".to_string()),
+            key: Some("secret\n".to_string()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.field, "key");
+
+        let err = Client::try_new(Config {
+            get_addr: Some(Arc::new(|| Ok("http://localhost:8000".to_string()))),
+            client_name: Some("svc\r\nX-Injected: 1".to_string()),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.field, "client_name");
+    }
+
+    #[test]
+    fn test_transport_stats() {
+        let body = br#"{"result":{"nodes":[]}}"#.to_vec();
+        let addr = serve_keep_alive(vec![body.clone(), body.clone()]);
+        let config = Config {
+            addr: Some(addr.replace("127.0.0.1", "localhost")),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        assert_eq!(client.transport_stats(), TransportStats::default());
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.clone().info()).unwrap();
+
+        assert_eq!(
+            client.transport_stats(),
+            TransportStats {
+                requests_sent: 2,
+                requests_in_flight: 0,
+                connections_opened: 1,
+                connections_reused: 1,
+                dns_lookups: 1,
+                tls_handshakes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_transport_stats_new_connections() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.info()).unwrap();
+        requests.join().unwrap();
+
+        let stats = client.transport_stats();
+        assert_eq!(stats.connections_opened, 2);
+        assert_eq!(stats.connections_reused, 0);
+        // address is an IP, nothing to resolve.
+        assert_eq!(stats.dns_lookups, 0);
+    }
+
+    #[test]
+    fn test_channel_handle() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{"offset":1}}"#.to_vec()),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"presence":{}}}"#.to_vec(),
+            ),
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let chat = Client::new(config).channel("chat:index");
+        assert_eq!(chat.name(), "chat:index");
+        rt.block_on(async {
+            chat.publish(r#"{"text":"hi"}"#, &[]).await.unwrap();
+            chat.presence().await.unwrap();
+            chat.subscribe("42".to_string(), &[]).await.unwrap();
+        });
+
+        for (_, body) in requests.join().unwrap() {
+            let command: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(command["params"]["channel"], "chat:index");
+        }
+    }
+
+    #[test]
+    fn test_user_handle() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"statuses":[{"user":"user-42","active":10}]}}"#.to_vec(),
+            ),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let user = Client::new(config).user("user-42");
+        assert_eq!(user.id(), "user-42");
+        let status = rt.block_on(async {
+            user.subscribe("news".to_string(), &[]).await.unwrap();
+            user.disconnect(&[]).await.unwrap();
+            user.status().await.unwrap()
+        });
+        assert_eq!(to_unix(status.unwrap().active), 10);
+
+        let bodies: Vec<serde_json::Value> = requests
+            .join()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_slice(body).unwrap())
+            .collect();
+        assert_eq!(bodies[0]["params"]["user"], "user-42");
+        assert_eq!(bodies[0]["params"]["channel"], "news");
+        assert_eq!(bodies[1]["method"], "disconnect");
+        assert_eq!(bodies[1]["params"]["user"], "user-42");
+        assert_eq!(bodies[2]["params"]["users"], serde_json::json!(["user-42"]));
+    }
+
+    fn request_id(headers: &str) -> String {
+        headers
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("x-request-id: ")
+                    .map(str::to_string)
+            })
+            .unwrap()
+            .trim()
+            .to_string()
+    }
+
+    #[test]
+    fn test_request_id() {
+        let (addr, requests) = serve(vec![
+            (500, String::new(), b"internal".to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt.block_on(client.info()).unwrap_err();
+        rt.block_on(client.info()).unwrap();
+
+        let requests = requests.join().unwrap();
+        let failed_id = request_id(&requests[0].0);
+        assert_eq!(failed_id.len(), 36);
+        assert_eq!(&failed_id[14..15], "4");
+        assert_ne!(failed_id, request_id(&requests[1].0));
+
+        let err = err.downcast_ref::<ErrStatusCode>().unwrap();
+        assert_eq!(err.request_id.as_deref(), Some(failed_id.as_str()));
+        assert!(err.to_string().contains(&failed_id));
+    }
+
+    #[test]
+    fn test_status_code_error_body() {
+        let (addr, _requests) = serve(vec![(
+            401,
+            String::new(),
+            br#"{"error":{"code":101,"message":"unauthorized"}}"#.to_vec(),
+        )]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+        let err = rt.block_on(client.info()).unwrap_err();
+        let err = err.downcast_ref::<ErrStatusCode>().unwrap();
+        assert_eq!(err.code, 401);
+        assert_eq!(err.message.as_deref(), Some("unauthorized"));
+        assert_eq!(err.error_code, Some(101));
+        assert!(err
+            .to_string()
+            .starts_with("wrong status code: 401, error 101: unauthorized"));
+
+        let err = ErrStatusCode::new(404, br#"{"code":3,"message":"not found"}"#, None);
+        assert_eq!(
+            (err.message.as_deref(), err.error_code),
+            (Some("not found"), Some(3))
+        );
+        assert_eq!(
+            err.to_string(),
+            "wrong status code: 404, error 3: not found"
+        );
+        let err = ErrStatusCode::new(403, br#"{"error":"forbidden"}"#, None);
+        assert_eq!(
+            (err.message.as_deref(), err.error_code),
+            (Some("forbidden"), None)
+        );
+        assert_eq!(err.to_string(), "wrong status code: 403, error forbidden");
+
+        // raw body is kept when it's not JSON.
+        let err = ErrStatusCode::new(502, b"Bad Gateway", None);
+        assert_eq!((err.message.as_deref(), err.error_code), (None, None));
+        assert_eq!(err.to_string(), "wrong status code: 502, body Bad Gateway");
+    }
+
+    #[test]
+    fn test_request_id_propagated() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config)
+            .with_call_options(CallOptions::default().header("X-Request-ID", "upstream-1"));
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert_eq!(request_headers.matches("x-request-id").count(), 1);
+        assert_eq!(request_id(&request_headers), "upstream-1");
+    }
+
+    #[test]
+    fn test_malformed_response_has_request_id() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"nodes":[]}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+        pipe.add_presence("test_channel".to_string()).unwrap();
+        let err = rt.block_on(client.send_pipe(&pipe)).unwrap_err();
+
+        let requests = requests.join().unwrap();
+        let err = err.downcast_ref::<ErrRequestFailed>().unwrap();
+        assert_eq!(err.request_id, request_id(&requests[0].0));
+        assert_eq!(err.methods, vec!["info", "presence"]);
+        assert!(err
+            .to_string()
+            .ends_with("malformed response returned from server"));
+    }
+
+    struct TraceHeader;
+
+    impl Middleware for TraceHeader {
+        fn on_request(&self, request: &mut reqwest::Request) -> Result<(), ErrRes> {
+            request
+                .headers_mut()
+                .insert("X-Trace", "trace-1".parse().unwrap());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_middleware_modifies_request() {
+        let (addr, request) = serve_once_raw("", br#"{"result":{"nodes":[]}}"#.to_vec());
+        let config = Config {
+            addr: Some(addr),
+            middlewares: vec![Arc::new(TraceHeader)],
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        let request_headers = request.join().unwrap().to_lowercase();
+        assert!(request_headers.contains("x-trace: trace-1"));
+    }
+
+    struct TenantPrefix;
+
+    impl CommandMiddleware for TenantPrefix {
+        fn on_commands(&self, commands: &mut [Command]) -> Result<(), ErrRes> {
+            for cmd in commands {
+                if let RequestKind::PublishRequest(request) = &mut cmd.params {
+                    request.channel = format!("tenant-a:{}", request.channel);
+                }
+            }
+            Ok(())
+        }
+
+        fn keep(&self, command: &Command) -> bool {
+            command.method != Method::Info
+        }
+    }
+
+    #[test]
+    fn test_command_middleware_rewrites_commands() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"offset\":1}}\n{\"result\":{\"offset\":2}}".to_vec(),
+        )]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            command_middlewares: vec![Arc::new(TenantPrefix)],
+            ..Default::default()
+        });
+
+        let pipe = client.pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_info().unwrap();
+        pipe.add_publish("news".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let replies = rt.block_on(client.send_pipe(&pipe)).unwrap();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0].result.as_ref().unwrap()["offset"], 1);
+        assert_eq!(
+            replies[1].error.as_ref().unwrap().code,
+            COMMAND_FILTERED_CODE
+        );
+        assert!(replies[1].result.is_none());
+        assert_eq!(replies[2].result.as_ref().unwrap()["offset"], 2);
+
+        // filtered out command isn't sent, pipe itself is not modified.
+        let (_, body) = requests.join().unwrap().remove(0);
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body.lines().count(), 2);
+        assert!(body.contains("tenant-a:chat") && body.contains("tenant-a:news"));
+        assert!(!body.contains("info"));
+        assert_eq!(pipe.len(), 3);
+
+        // typed calls of filtered out commands fail instead of decoding empty result.
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.as_api_error().unwrap().code, COMMAND_FILTERED_CODE);
+    }
+
+    #[derive(Default)]
+    struct RecordingHook {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Hook for RecordingHook {
+        fn on_request(&self, event: &RequestEvent) {
+            self.events.lock().unwrap().push(format!(
+                "request {} {} attempt {}",
+                event.methods.join(","),
+                event.commands(),
+                event.attempt
+            ));
+        }
+
+        fn on_response(&self, event: &ResponseEvent) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("response errors {}", event.errors));
+        }
+
+        fn on_error(&self, event: &ErrorEvent) {
+            let code = event
+                .error
+                .downcast_ref::<ErrStatusCode>()
+                .map(|err| err.code);
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("error {:?}", code));
+        }
+
+        fn on_retry(&self, event: &RetryEvent) {
+            self.events.lock().unwrap().push(format!(
+                "retry {} after attempt {} in {:?}",
+                event.methods.join(","),
+                event.attempt,
+                event.delay
+            ));
+        }
+    }
+
+    #[test]
+    fn test_hooks() {
+        let (addr, _requests) = serve(vec![
+            (503, String::new(), b"unavailable".to_vec()),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"offset":1,"epoch":"e"}}
+{"error":{"code":102,"message":"unknown channel"}}"#
+                    .to_vec(),
+            ),
+        ]);
+        let hook = Arc::new(RecordingHook::default());
+        let config = Config {
+            addr: Some(addr),
+            hooks: vec![hook.clone()],
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+
+        assert_eq!(
+            *hook.events.lock().unwrap(),
+            vec![
+                "request publish,presence 2 attempt 1",
+                "error Some(503)",
+                "retry publish,presence after attempt 1 in 1ms",
+                "request publish,presence 2 attempt 2",
+                "response errors 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_on_unavailable() {
+        let (addr, requests) = serve(vec![
+            (503, String::new(), b"unavailable".to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_dead_letter_after_retries_exhausted() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let config = Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            retry_policy: Some(RetryPolicy {
+                max_attempts: 2,
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            dead_letter: Some(dead_letter_channel(sender)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+
+        let err = rt.block_on(client.send_pipe(&pipe)).unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrRequestFailed>().unwrap().attempt, 2);
+
+        let (command, _) = receiver.try_recv().unwrap();
+        assert_eq!(command.method, "publish");
+        let (command, _) = receiver.try_recv().unwrap();
+        assert_eq!(command.method, "presence");
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_retryable_status_codes() {
+        let (addr, requests) = serve(vec![
+            (504, String::new(), b"gateway timeout".to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (500, String::new(), b"internal".to_vec()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                retryable_status_codes: Some(vec![502, 503, 504]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 500);
+        assert_eq!(requests.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_retry_goes_to_another_endpoint() {
+        let (failing, failing_requests) = serve(vec![
+            (503, String::new(), b"unavailable".to_vec()),
+            (503, String::new(), b"unavailable".to_vec()),
+        ]);
+        let (healthy, healthy_requests) = serve(vec![
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+            (200, String::new(), br#"{"result":{"nodes":[]}}"#.to_vec()),
+        ]);
+        let config = Config {
+            addrs: Some(vec![failing.clone(), healthy]),
+            endpoint_probe_interval: Some(Duration::from_secs(60)),
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.info()).unwrap();
+
+        assert_eq!(failing_requests.join().unwrap().len(), 2);
+        assert_eq!(healthy_requests.join().unwrap().len(), 2);
+        let stats = client.endpoint_selector.unwrap().stats();
+        assert_eq!(stats[0].addr, failing);
+        assert!(!stats[0].healthy);
+        assert!(stats[1].healthy);
+    }
+
+    #[test]
+    fn test_discovery() {
+        let reply = br#"{"result":{"nodes":[]}}"#.to_vec();
+        let (first, first_requests) = serve(vec![(200, String::new(), reply.clone())]);
+        let (second, second_requests) = serve(vec![(200, String::new(), reply)]);
+        let discovery = Arc::new(StaticDiscovery::new([first]));
+        let config = Config {
+            discovery: Some(discovery.clone()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::try_new(config).unwrap();
+        rt.block_on(client.info()).unwrap();
+        discovery.set([second.clone()]);
+        rt.block_on(client.info()).unwrap();
+
+        assert_eq!(first_requests.join().unwrap().len(), 1);
+        assert_eq!(second_requests.join().unwrap().len(), 1);
+        let stats = client.endpoint_selector.unwrap().stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].addr, second);
+    }
+
+    struct FallbackHook {
+        fallbacks: Mutex<Vec<String>>,
+    }
+
+    impl Hook for FallbackHook {
+        fn on_endpoint_fallback(&self, event: &FallbackEvent) {
+            self.fallbacks
+                .lock()
+                .unwrap()
+                .push(format!("{} after {}", event.endpoint, event.error));
+        }
+    }
+
+    #[test]
+    fn test_get_addr_fallback() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"nodes":[]}}"#.to_vec(),
+        )]);
+        let hook = Arc::new(FallbackHook {
+            fallbacks: Mutex::new(Vec::new()),
+        });
+        let failing_get_addr: GetAddr = Arc::new(|| Err("discovery unavailable".into()));
+        let config = Config {
+            addr: Some(addr.clone()),
+            get_addr: Some(failing_get_addr.clone()),
+            get_addr_fallback: Some(true),
+            hooks: vec![hook.clone()],
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(requests.join().unwrap().len(), 1);
+        assert_eq!(
+            *hook.fallbacks.lock().unwrap(),
+            vec![format!("{addr} after discovery unavailable")]
+        );
+
+        let config = Config {
+            addr: Some(addr),
+            get_addr: Some(failing_get_addr),
+            ..Default::default()
+        };
+        let client = Client::new(config);
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.to_string(), "discovery unavailable");
+    }
+
+    #[derive(Default)]
+    struct ConnectionHook {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Hook for ConnectionHook {
+        fn on_connect(&self, event: &ConnectionEvent) {
+            assert!(event.peer_certificate.is_none());
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("connect {}", event.remote_addr));
+        }
+
+        fn on_disconnect(&self, event: &ConnectionEvent) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("disconnect {}", event.remote_addr));
+        }
+    }
+
+    #[test]
+    fn test_connection_hooks() {
+        let body = br#"{"result":{"nodes":[]}}"#.to_vec();
+        let addr = serve_keep_alive(vec![body.clone(), body.clone()]);
+        let remote = addr
+            .trim_start_matches("http://")
+            .trim_end_matches("/api")
+            .to_string();
+        let hook = Arc::new(ConnectionHook::default());
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(Config {
+            addr: Some(addr),
+            hooks: vec![hook.clone()],
+            ..Default::default()
+        });
+        rt.block_on(client.info()).unwrap();
+        rt.block_on(client.info()).unwrap();
+        // pooled connection is reported once.
+        assert_eq!(
+            *hook.events.lock().unwrap(),
+            vec![format!("connect {remote}")]
+        );
+
+        let (addr, _requests) = serve(vec![(200, String::new(), body)]);
+        let remote = addr
+            .trim_start_matches("http://")
+            .trim_end_matches("/api")
+            .to_string();
+        let hook = Arc::new(ConnectionHook::default());
+        let client = Client::new(Config {
+            addr: Some(addr),
+            hooks: vec![hook.clone()],
+            ..Default::default()
+        });
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(
+            *hook.events.lock().unwrap(),
+            vec![format!("connect {remote}"), format!("disconnect {remote}")]
+        );
+    }
+
+    #[test]
+    fn test_endpoint_refresher_get_addr() {
+        let reply = br#"{"result":{"nodes":[]}}"#.to_vec();
+        let (addr, requests) = serve(vec![
+            (200, String::new(), reply.clone()),
+            (200, String::new(), reply),
+        ]);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let config = Config {
+            get_addr: Some(Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(addr.clone())
+            })),
+            endpoint_refresh_interval: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        std::thread::sleep(Duration::from_millis(100));
+        let refreshed = calls.load(Ordering::SeqCst);
+        assert!(refreshed > 1, "{refreshed}");
+        assert!(client.start_endpoint_refresher().is_none());
+
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(requests.join().unwrap().len(), 2);
+        assert!(rt
+            .block_on(client.shutdown(Duration::from_secs(1)))
+            .is_clean());
+    }
+
+    #[test]
+    fn test_endpoint_refresher_discovery() {
+        let reply = br#"{"result":{"nodes":[]}}"#.to_vec();
+        let (first, first_requests) = serve(vec![(200, String::new(), reply.clone())]);
+        let (second, second_requests) = serve(vec![(200, String::new(), reply)]);
+        let discovery = Arc::new(StaticDiscovery::new([first]));
+        let config = Config {
+            discovery: Some(discovery.clone()),
+            endpoint_refresh_interval: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.info()).unwrap();
+
+        // change notification is handled by refresher, not by the next request.
+        discovery.set([second.clone()]);
+        std::thread::sleep(Duration::from_millis(50));
+        let stats = client.endpoint_selector.as_ref().unwrap().stats();
+        assert_eq!(stats[0].addr, second);
+
+        rt.block_on(client.info()).unwrap();
+        assert_eq!(first_requests.join().unwrap().len(), 1);
+        assert_eq!(second_requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_discovery_without_endpoints() {
+        let config = Config {
+            discovery: Some(Arc::new(StaticDiscovery::new(Vec::<String>::new()))),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert!(err.to_string().contains("no API endpoint available"));
+    }
+
+    #[test]
+    fn test_no_retry_on_client_error() {
+        let (addr, requests) = serve(vec![(400, String::new(), b"bad request".to_vec())]);
+        let config = Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy::default()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        assert!(rt.block_on(client.info()).is_err());
+        assert_eq!(requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_warmup() {
+        let (addr, requests) = serve(vec![
+            (405, String::new(), Vec::new()),
+            (405, String::new(), Vec::new()),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.warmup(2)).unwrap();
+
+        let requests = requests.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].0.starts_with("HEAD /api"));
+    }
+
+    #[test]
+    fn test_warmup_unreachable() {
+        let config = Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt.block_on(client.warmup(1)).unwrap_err();
+        assert!(err.downcast_ref::<ErrRequestFailed>().is_some());
+    }
+
+    #[test]
+    fn test_publish_many() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"offset\":1,\"epoch\":\"e\"}}\n{\"error\":{\"code\":108,\"message\":\"not available\"}}\n{\"result\":{\"offset\":2,\"epoch\":\"e\"}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let results = rt
+            .block_on(client.publish_many(
+                "chat".to_string(),
+                vec![r#"{"n":1}"#, r#"{"n":2}"#, r#"{"n":3}"#],
+                &[],
+            ))
+            .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().offset, Some(1));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.downcast_ref::<PipeCommandError>().unwrap().index, 1);
+        assert_eq!(results[2].as_ref().unwrap().offset, Some(2));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(String::from_utf8(body).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_presence_stats_many() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"num_clients\":3,\"num_users\":2}}\n{\"result\":{\"num_clients\":0,\"num_users\":0}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let stats = rt
+            .block_on(client.presence_stats_many(["chat", "news", "chat"]))
+            .unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["chat"].num_clients, 3);
+        assert_eq!(stats["chat"].num_users, 2);
+        assert_eq!(stats["news"].num_clients, 0);
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"method\":\"presence_stats\",\"params\":{\"channel\":\"chat\"}}\n{\"method\":\"presence_stats\",\"params\":{\"channel\":\"news\"}}"
+        );
+
+        let empty = rt
+            .block_on(client.presence_stats_many(Vec::<String>::new()))
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_get_user_status_many() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"statuses\":[{\"user\":\"u0\",\"active\":10,\"online\":20}]}}\n{\"result\":{\"statuses\":[{\"user\":\"u1000\",\"active\":30}]}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let mut users: Vec<String> = (0..=USER_STATUS_CHUNK_SIZE)
+            .map(|i| format!("u{i}"))
+            .collect();
+        users.push("u0".to_string());
+        let statuses = rt.block_on(client.get_user_status_many(users)).unwrap();
+
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(to_unix(statuses["u0"].online), 20);
+        assert_eq!(to_unix(statuses["u1000"].active), 30);
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        let commands: Vec<serde_json::Value> = String::from_utf8(body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0]["method"], "get_user_status");
+        assert_eq!(
+            commands[0]["params"]["users"].as_array().unwrap().len(),
+            USER_STATUS_CHUNK_SIZE
+        );
+        assert_eq!(commands[1]["params"]["users"], serde_json::json!(["u1000"]));
+    }
+
+    #[test]
+    fn test_presence_stats_many_error() {
+        let (addr, _requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"num_clients\":1,\"num_users\":1}}\n{\"error\":{\"code\":108,\"message\":\"not available\"}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt
+            .block_on(client.presence_stats_many(["chat", "news"]))
+            .unwrap_err();
+        let err = err.downcast_ref::<PipeCommandError>().unwrap();
+        assert_eq!(err.index, 1);
+        assert_eq!(err.method, "presence_stats");
+    }
+
+    #[test]
+    fn test_history_and_presence_many() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                b"{\"result\":{\"publication\":[],\"offset\":5,\"epoch\":\"e\"}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}".to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                b"{\"result\":{\"presence\":{\"c1\":{\"client\":\"c1\",\"user\":\"42\"}}}}".to_vec(),
+            ),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let history = rt
+            .block_on(client.history_many(["chat", "missing", "chat"], &[with_limit(1)]))
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history["chat"].as_ref().unwrap().offset, 5);
+        let err = history["missing"].as_ref().unwrap_err();
+        let err = err.downcast_ref::<PipeCommandError>().unwrap();
+        assert_eq!((err.index, err.method.as_str()), (1, "history"));
+        assert_eq!(err.error.code, 102);
+
+        let presence = rt.block_on(client.presence_many(["chat"])).unwrap();
+        let presence = presence["chat"].as_ref().unwrap();
+        assert_eq!(presence.presence["c1"].user, "42");
+
+        let requests = requests.join().unwrap();
+        assert_eq!(
+            String::from_utf8(requests[0].1.clone()).unwrap(),
+            "{\"method\":\"history\",\"params\":{\"channel\":\"chat\",\"limit\":1}}\n{\"method\":\"history\",\"params\":{\"channel\":\"missing\",\"limit\":1}}"
+        );
+    }
+
+    #[test]
+    fn test_broadcast_map() {
+        let (addr, _requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"responses\":[{\"result\":{\"offset\":3,\"epoch\":\"e\"}},{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}]}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let results = rt
+            .block_on(client.broadcast_map(
+                vec!["chat".to_string(), "missing".to_string()],
+                "{}",
+                &[],
+            ))
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["chat"].as_ref().unwrap().offset, Some(3));
+        assert_eq!(results["missing"].as_ref().unwrap_err().code, 102);
+    }
+
+    #[test]
+    fn test_subscribe_channels() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let results = rt
+            .block_on(client.subscribe_channels(
+                "42".to_string(),
+                ["personal:42", "missing:42"],
+                &[],
+            ))
+            .unwrap();
+
+        assert!(results["personal:42"].is_ok());
+        let err = results["missing:42"].as_ref().unwrap_err();
+        let err = err.downcast_ref::<PipeCommandError>().unwrap();
+        assert_eq!((err.index, err.method.as_str()), (1, "subscribe"));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"method\":\"subscribe\",\"params\":{\"channel\":\"personal:42\",\"user\":\"42\"}}\n{\"method\":\"subscribe\",\"params\":{\"channel\":\"missing:42\",\"user\":\"42\"}}"
+        );
+    }
+
+    #[test]
+    fn test_disconnect_many() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"error\":{\"code\":100,\"message\":\"internal server error\"}}"
+                .to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let results = rt
+            .block_on(client.disconnect_many(
+                vec!["1", "2", "1"],
+                &[with_disconnect(Disconnect {
+                    code: Some(4000),
+                    reason: Some("logout".to_string()),
+                    ..Default::default()
+                })],
+            ))
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results["1"].is_ok());
+        let err = results["2"].as_ref().unwrap_err();
+        let err = err.downcast_ref::<PipeCommandError>().unwrap();
+        assert_eq!((err.index, err.method.as_str()), (1, "disconnect"));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body.lines().count(), 2);
+        assert!(body.contains(r#""reason":"logout""#));
+    }
+
+    #[test]
+    fn test_refresh_many() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"result\":{}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let results = rt
+            .block_on(client.refresh_many(vec![
+                ("1", from_unix(100)),
+                ("2", from_unix(200)),
+                ("1", from_unix(300)),
+            ]))
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.values().all(Result::is_ok));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"method\":\"refresh\",\"params\":{\"user\":\"1\",\"expire_at\":300}}\n{\"method\":\"refresh\",\"params\":{\"user\":\"2\",\"expire_at\":200}}"
+        );
+    }
+
+    #[test]
+    fn test_call() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"items":[1,2]}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"offset":7,"epoch":"e"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"error":{"code":104,"message":"method not found"}}"#.to_vec(),
+            ),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let result = rt
+            .block_on(client.call("pro_items", serde_json::json!({"limit": 2})))
+            .unwrap();
+        assert_eq!(result, serde_json::json!({"items": [1, 2]}));
+
+        let result = rt
+            .block_on(client.call(
+                "publish",
+                serde_json::json!({"channel": "chat", "data": {"n": 1}}),
+            ))
+            .unwrap();
+        assert_eq!(
+            decode_publish(result.to_string().as_bytes())
+                .unwrap()
+                .offset,
+            Some(7)
+        );
+
+        let err = rt
+            .block_on(client.call("missing", serde_json::json!({})))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ProtocolError>().unwrap().code, 104);
+
+        // params of known method are checked before sending.
+        assert!(rt
+            .block_on(client.call("publish", serde_json::json!({"data": {}})))
+            .is_err());
+
+        let bodies: Vec<String> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(_, body)| String::from_utf8(body).unwrap())
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                r#"{"method":"pro_items","params":{"limit":2}}"#,
+                r#"{"method":"publish","params":{"channel":"chat","data":{"n":1}}}"#,
+                r#"{"method":"missing","params":{}}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_send_raw() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"ok\":true}}\n{\"result\":{}}".to_vec(),
+        )]);
+        let hook = Arc::new(RecordingHook {
+            events: Mutex::new(Vec::new()),
+        });
+        let config = Config {
+            addr: Some(addr),
+            hooks: vec![hook.clone()],
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let body = concat!(
+            r#"{"method":"future_method","params":{"z":1,"a":2}}"#,
+            "\n",
+            r#"{"method":"publish","params":{"channel":"chat","data":{},"future_flag":true}}"#
+        );
+        let replies = rt.block_on(client.send_raw(body.to_string())).unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0].result, Some(serde_json::json!({"ok": true})));
+
+        // body is sent byte for byte, unknown params are kept.
+        let (_, sent) = requests.join().unwrap().remove(0);
+        assert_eq!(String::from_utf8(sent).unwrap(), body);
+        assert_eq!(
+            hook.events.lock().unwrap()[0],
+            "request future_method,publish 2 attempt 1"
+        );
 
-    use super::*;
+        assert!(rt.block_on(client.send_raw(" \n".to_string())).is_err());
+        assert!(rt
+            .block_on(client.send_raw("not json".to_string()))
+            .is_err());
+    }
 
-    lazy_static! {
-        static ref ADDR: String =
-            env::var("API_URL").unwrap_or("http://127.0.0.1:8000/api".to_string());
-        static ref API_KEY: String =
-            env::var("API_KEY").unwrap_or("default_api_key_hex".to_string());
+    #[test]
+    fn test_device_list_stream() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"items":[{"id":"d1"},{"id":"d2"}],"next_cursor":"c1"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"items":[],"next_cursor":"c2"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"items":[{"id":"d3"}]}}"#.to_vec(),
+            ),
+        ]);
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let req = DeviceListRequest {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let ids: Vec<String> = rt.block_on(
+            client
+                .device_list_stream(req)
+                .map(|device| device.unwrap().id)
+                .collect(),
+        );
+        assert_eq!(ids, ["d1", "d2", "d3"]);
+
+        let cursors: Vec<serde_json::Value> = requests
+            .join()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_slice::<serde_json::Value>(body).unwrap())
+            .map(|cmd| {
+                assert_eq!(cmd["params"]["limit"], 2);
+                cmd["params"]["cursor"].clone()
+            })
+            .collect();
+        assert_eq!(cursors, [serde_json::Value::Null, "c1".into(), "c2".into()]);
     }
 
     #[test]
-    #[cfg(feature = "with_local_server")]
-    fn test_with_api_key() {
-        match env::var("API_KEY") {
-            Ok(_) => println!("API_URL environment variable set"),
-            Err(_) => panic!("API_URL environment variable not set"),
+    fn test_user_topic_list_stream_error() {
+        let (addr, _requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"items":[{"id":"1","user":"u","topic":"t"}],"next_cursor":"c1"}}"#
+                    .to_vec(),
+            ),
+            (500, String::new(), b"internal error".to_vec()),
+        ]);
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let results: Vec<_> = rt.block_on(
+            client
+                .user_topic_list_stream(UserTopicListRequest::default())
+                .collect(),
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().topic, "t");
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .downcast_ref::<ErrStatusCode>()
+            .is_some());
+    }
+
+    #[test]
+    fn test_decode_replies() {
+        let body = "\u{feff}{\"result\":{}}\r\n\r\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}\r\n\n";
+        let replies = decode_replies(body.as_bytes(), false).unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[1].error.as_ref().unwrap().code, 102);
+        assert!(decode_replies(body.as_bytes(), true).is_err());
+
+        let body = "{\"result\":{}}\r\n{\"result\":{}}\n";
+        assert_eq!(decode_replies(body.as_bytes(), true).unwrap().len(), 2);
+        assert!(decode_replies(b"\xff", false).is_err());
+    }
+
+    #[test]
+    fn test_tolerant_reply_parsing() {
+        let body =
+            b"\xef\xbb\xbf{\"result\":{\"offset\":1}}\n\n{\"result\":{\"offset\":2}}\n".to_vec();
+        let (addr, _requests) = serve(vec![
+            (200, String::new(), body.clone()),
+            (200, String::new(), body),
+        ]);
+        let rt = Runtime::new().unwrap();
+
+        let client = Client::new(Config {
+            addr: Some(addr.clone()),
+            ..Default::default()
+        });
+        let results = rt
+            .block_on(client.publish_many("chat".to_string(), ["{}", "{}"], &[]))
+            .unwrap();
+        assert_eq!(results[1].as_ref().unwrap().offset, Some(2));
+
+        let strict = Client::new(Config {
+            addr: Some(addr),
+            strict_decode: Some(true),
+            ..Default::default()
+        });
+        let err = rt
+            .block_on(strict.publish_many("chat".to_string(), ["{}", "{}"], &[]))
+            .unwrap_err();
+        assert!(err.downcast_ref::<ErrRequestFailed>().is_some());
+    }
+
+    #[test]
+    fn test_send_pipe_checked() {
+        let (addr, _requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_info().unwrap();
+        pipe.add_history_remove("chat".to_string()).unwrap();
+
+        let err = rt.block_on(client.send_pipe_checked(&pipe)).unwrap_err();
+        let err = err.downcast_ref::<PipeCommandError>().unwrap();
+        assert_eq!(err.index, 1);
+        assert_eq!(err.method, "history_remove");
+        assert_eq!(err.error.code, 102);
+    }
+
+    #[test]
+    fn test_send_pipe_typed() {
+        let (addr, _requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{\"offset\":1,\"epoch\":\"e\"}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}\n{\"result\":{}}\n{\"result\":{\"nodes\":[]}}"
+                .to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_presence("unknown".to_string()).unwrap();
+        pipe.add_history_remove("chat".to_string()).unwrap();
+        pipe.add_info().unwrap();
+
+        let results = rt.block_on(client.send_pipe_typed(&pipe)).unwrap();
+        assert_eq!(results.len(), 4);
+        match &results[0] {
+            Ok(TypedResult::Publish(result)) => assert_eq!(result.offset, Some(1)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.downcast_ref::<ProtocolError>().unwrap().code, 102);
+        assert!(matches!(results[2], Ok(TypedResult::Empty)));
+        match &results[3] {
+            Ok(TypedResult::Info(result)) => assert!(result.nodes.is_empty()),
+            other => panic!("unexpected result: {:?}", other),
         }
+    }
+
+    #[test]
+    fn test_disconnect_all() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"error\":{\"code\":100,\"message\":\"internal server error\"}}"
+                .to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let disconnect = Disconnect {
+            code: Some(4000),
+            reason: Some("security".to_string()),
+            ..Default::default()
+        };
+        let results = rt
+            .block_on(client.disconnect_all(
+                vec!["u1", "u2", "u1"],
+                disconnect,
+                vec!["admin".to_string()],
+            ))
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results["u1"].is_ok());
+        assert!(results["u2"].is_err());
 
-        match env::var("API_URL") {
-            Ok(_) => println!("API_URL environment variable set"),
-            Err(_) => panic!("API_URL environment variable not set"),
+        let (_, body) = requests.join().unwrap().remove(0);
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body.lines().count(), 2);
+        for line in body.lines() {
+            let command: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(command["method"], "disconnect");
+            assert_eq!(command["params"]["disconnect"]["code"], 4000);
+            assert_eq!(command["params"]["whitelist"][0], "admin");
         }
     }
 
     #[test]
-    fn test_decode_publish_valid_json() {
-        let json_data = r#"
-        {
-            "epoch": "1789378957", 
-            "offset": 42
-        }"#;
+    fn test_many_empty_input() {
+        let client = Client::new(Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
 
-        let result = decode_publish(json_data.as_bytes()).unwrap();
-        assert_eq!(result.offset, Some(42));
+        // nothing is sent, so unreachable server doesn't matter.
+        let no_items: [&str; 0] = [];
+        assert!(rt
+            .block_on(client.publish_many("chat".to_string(), no_items, &[]))
+            .unwrap()
+            .is_empty());
+        assert!(rt
+            .block_on(client.disconnect_many(no_items, &[]))
+            .unwrap()
+            .is_empty());
+        assert!(rt
+            .block_on(client.disconnect_all(no_items, Disconnect::default(), vec![]))
+            .unwrap()
+            .is_empty());
+        assert!(rt
+            .block_on(client.history_many(no_items, &[]))
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_decode_publish_empty_slice() {
-        let empty_slice: &[u8] = &[];
-        let result = decode_publish(empty_slice);
-        assert!(result.is_err());
+    fn test_send_borrowed_commands() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            b"{\"result\":{}}\n{\"result\":{}}".to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_history_remove("a".to_string()).unwrap();
+        pipe.add_history_remove("b".to_string()).unwrap();
+        let commands = pipe.commands();
+
+        let replies = rt.block_on(client.send(&commands)).unwrap();
+        assert_eq!(replies.len(), 2);
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"method\":\"history_remove\",\"params\":{\"channel\":\"a\"}}\n{\"method\":\"history_remove\",\"params\":{\"channel\":\"b\"}}"
+        );
     }
 
     #[test]
-    fn test_decode_publish_malformed_json() {
-        let malformed_json = b"{\"channel\": \"test_channel\", \"offset\": 42";
-        let result = decode_publish(malformed_json);
-        assert!(result.is_err());
+    fn test_send_pipe_owned() {
+        let (addr, _requests) = serve(vec![(200, String::new(), b"{\"result\":{}}".to_vec())]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let pipe = client.pipe();
+        pipe.add_history_remove("a".to_string()).unwrap();
+
+        let replies = rt.block_on(client.send_pipe_owned(&pipe)).unwrap();
+        assert_eq!(replies.len(), 1);
+        assert!(pipe.is_empty());
+        assert!(rt.block_on(client.send_pipe_owned(&pipe)).is_err());
     }
 
     #[test]
-    fn test_client_new() {
+    fn test_send_pipe_encoded_commands() {
+        let reply = b"{\"result\":{}}\n{\"result\":{}}".to_vec();
+        let (addr, requests) = serve(vec![
+            (200, String::new(), reply.clone()),
+            (200, String::new(), reply.clone()),
+            (200, String::new(), reply),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        let pipe = client.pipe();
+        pipe.add_history_remove("a".to_string()).unwrap();
+        pipe.add_publish("b".to_string(), r#"{"n":1}"#, &[])
+            .unwrap();
+        pipe.add_history_remove("c".to_string()).unwrap();
+        pipe.remove(0).unwrap();
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+
+        // commands serialized when added are reused until they're changed.
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+        pipe.update(|commands| {
+            if let RequestKind::HistoryRemoveRequest(request) = &mut commands[1].params {
+                request.channel = "d".to_string();
+            }
+        });
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+
+        let bodies: Vec<_> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(_, body)| String::from_utf8(body).unwrap())
+            .collect();
+        let expected = r#"{"method":"publish","params":{"channel":"b","data":{"n":1}}}"#;
+        assert_eq!(
+            bodies[0],
+            format!(
+                "{expected}\n{}",
+                r#"{"method":"history_remove","params":{"channel":"c"}}"#
+            )
+        );
+        assert_eq!(bodies[1], bodies[0]);
+        assert!(bodies[2].ends_with(r#"{"channel":"d"}}"#));
+    }
+
+    #[test]
+    fn test_retry_failed() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                b"{\"result\":{\"offset\":1}}\n{\"error\":{\"code\":111,\"message\":\"too many requests\"}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}\n{\"error\":{\"code\":100,\"message\":\"internal\"}}".to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                b"{\"result\":{\"offset\":2}}\n{\"error\":{\"code\":100,\"message\":\"internal\"}}".to_vec(),
+            ),
+            (200, String::new(), br#"{"result":{"offset":3}}"#.to_vec()),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        let pipe = client.pipe();
+        for channel in ["a", "b", "c", "d"] {
+            pipe.add_publish(channel.to_string(), "{}", &[]).unwrap();
+        }
+        let replies = rt.block_on(client.send_pipe(&pipe)).unwrap();
+        let replies = rt.block_on(client.retry_failed(&pipe, replies)).unwrap();
+
+        let offsets: Vec<_> = replies
+            .iter()
+            .map(|reply| reply.result.as_ref().map(|result| result["offset"].clone()))
+            .collect();
+        assert_eq!(
+            offsets,
+            [Some(1.into()), Some(2.into()), None, Some(3.into())]
+        );
+        assert_eq!(replies[2].error.as_ref().unwrap().code, 102);
+
+        // only commands failed with retryable codes are sent again.
+        let bodies: Vec<_> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(_, body)| String::from_utf8(body).unwrap())
+            .collect();
+        assert_eq!(bodies[1].lines().count(), 2);
+        assert!(bodies[1].contains(r#""channel":"b""#) && bodies[1].contains(r#""channel":"d""#));
+        assert_eq!(bodies[2].lines().count(), 1);
+        assert!(bodies[2].contains(r#""channel":"d""#));
+
+        assert!(rt.block_on(client.retry_failed(&pipe, Vec::new())).is_err());
+    }
+
+    #[test]
+    fn test_retry_failed_capped_with_options() {
+        let internal = br#"{"error":{"code":100,"message":"internal"}}"#.to_vec();
+        let (addr, requests) = serve(vec![
+            (200, String::new(), internal.clone());
+            MAX_RETRY_FAILED_ATTEMPTS as usize
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy {
+                max_attempts: 0,
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        let pipe = client.pipe();
+        pipe.add_publish("a".to_string(), "{}", &[]).unwrap();
+        let call_opts = CallOptions::default().header("X-Tenant", "acme");
+        let replies = rt
+            .block_on(client.send_pipe_with_options(&pipe, &call_opts))
+            .unwrap();
+        let replies = rt
+            .block_on(client.retry_failed_with_options(&pipe, replies, &call_opts))
+            .unwrap();
+        assert_eq!(replies[0].error.as_ref().unwrap().code, 100);
+
+        let requests = requests.join().unwrap();
+        assert_eq!(requests.len(), MAX_RETRY_FAILED_ATTEMPTS as usize);
+        assert!(requests
+            .iter()
+            .all(|(headers, _)| headers.contains("x-tenant: acme")));
+    }
+
+    #[test]
+    fn test_send_pipe_owned_restores_commands_on_error() {
         let config = Config {
-            addr: Some(ADDR.to_string()),
-            get_addr: None,
-            key: Some(API_KEY.to_string()),
-            http_client: None,
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
         };
+
+        let rt = Runtime::new().unwrap();
         let client = Client::new(config);
-        assert_eq!(client.endpoint, Some(ADDR.to_string()));
-        assert_eq!(client.api_key, Some(API_KEY.to_string()));
+        let pipe = client.pipe();
+        pipe.add_history_remove("a".to_string()).unwrap();
+        pipe.add_history_remove("b".to_string()).unwrap();
+
+        assert!(rt.block_on(client.send_pipe_owned(&pipe)).is_err());
+        assert_eq!(pipe.take_commands().unwrap().len(), 2);
+        assert!(pipe.take_commands().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_results_cache() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"channels":{"a":{"num_clients":1}}}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"channels":{"a":{"num_clients":2}}}}"#.to_vec(),
+            ),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            results_cache_ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        for _ in 0..2 {
+            let result = rt.block_on(client.channels(&[])).unwrap();
+            assert_eq!(result.channels["a"].num_clients, 1);
+        }
+
+        let fresh = client.with_call_options(CallOptions::default().force_refresh(true));
+        let result = rt.block_on(fresh.channels(&[])).unwrap();
+        assert_eq!(result.channels["a"].num_clients, 2);
+        let result = rt.block_on(client.channels(&[])).unwrap();
+        assert_eq!(result.channels["a"].num_clients, 2);
+
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_results_cache_scoped_by_headers() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"channels":{"a":{"num_clients":1}}}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"channels":{"b":{"num_clients":2}}}}"#.to_vec(),
+            ),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            results_cache_ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+        let tenant_a = client.with_call_options(CallOptions::default().header("X-Tenant", "a"));
+        let tenant_b = client.with_call_options(CallOptions::default().header("X-Tenant", "b"));
+
+        let rt = Runtime::new().unwrap();
+        for _ in 0..2 {
+            let result = rt.block_on(tenant_a.channels(&[])).unwrap();
+            assert!(result.channels.contains_key("a"));
+            let result = rt.block_on(tenant_b.channels(&[])).unwrap();
+            assert!(result.channels.contains_key("b"));
+        }
+
+        let requests = requests.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].0.to_lowercase().contains("x-tenant: a"));
+        assert!(requests[1].0.to_lowercase().contains("x-tenant: b"));
+    }
+
+    #[test]
+    fn test_shutdown_refuses_new_calls() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap();
+
+        let report = rt.block_on(client.shutdown(Duration::from_secs(1)));
+        assert!(report.is_clean());
+
+        let err = rt
+            .block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap_err();
+        assert!(err.downcast_ref::<ErrClientClosed>().is_some());
+        assert_eq!(requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_reports_dropped_requests() {
+        // listener never accepts, so request hangs until deadline.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = Config {
+            addr: Some(format!("http://{}/api", listener.local_addr().unwrap())),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let report = rt.block_on(async {
+            let publishing = client.clone();
+            let request = tokio::spawn(async move {
+                let _ = publishing
+                    .publish_with_options("chat".to_string(), "{}", PublishOptions::default())
+                    .await;
+            });
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let report = client.shutdown(Duration::from_millis(100)).await;
+            request.abort();
+            report
+        });
+
+        assert_eq!(
+            report,
+            ShutdownReport {
+                dropped_requests: 1,
+                dropped_tasks: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_max_in_flight() {
+        // server accepts connections but never responds, so requests hold their slots.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!("http://{}/api", listener.local_addr().unwrap());
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        std::thread::spawn(move || {
+            let mut streams = Vec::new();
+            for stream in listener.incoming() {
+                streams.push(stream.unwrap());
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let config = Config {
+            addr: Some(addr),
+            max_in_flight: Some(2),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(async {
+            for _ in 0..3 {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let _ = client
+                        .publish_with_options("chat".to_string(), "{}", PublishOptions::default())
+                        .await;
+                });
+            }
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        });
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert_eq!(client.in_flight.unwrap().available_permits(), 0);
+    }
+
+    #[test]
+    fn test_publish_idempotency_window() {
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"offset":2,"epoch":"e"}}"#.to_vec(),
+            ),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            idempotency_window: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        for _ in 0..2 {
+            let result = rt
+                .block_on(client.publish(
+                    "chat".to_string(),
+                    "{}",
+                    &[with_idempotency_key("k1".to_string())],
+                ))
+                .unwrap();
+            assert_eq!(result.offset, Some(1));
+        }
+
+        let result = rt
+            .block_on(client.publish(
+                "chat".to_string(),
+                "{}",
+                &[with_idempotency_key("k2".to_string())],
+            ))
+            .unwrap();
+        assert_eq!(result.offset, Some(2));
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_publish_idempotency_window_concurrent() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            idempotency_window: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let opts = [with_idempotency_key("k1".to_string())];
+        let publish = || client.publish("chat".to_string(), "{}", &opts);
+        let (first, second) = rt.block_on(async { tokio::join!(publish(), publish()) });
+        assert_eq!(first.unwrap().offset, Some(1));
+        assert_eq!(second.unwrap().offset, Some(1));
+        assert_eq!(requests.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_auto_idempotency_key() {
+        let reply = br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec();
+        let (addr, requests) = serve(vec![
+            (503, String::new(), b"unavailable".to_vec()),
+            (200, String::new(), reply.clone()),
+            (200, String::new(), reply),
+        ]);
+        let config = Config {
+            addr: Some(addr),
+            retry_policy: Some(RetryPolicy {
+                min_backoff: Duration::from_millis(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let opts = [with_auto_idempotency_key()];
+        let first = rt
+            .block_on(client.publish("chat".to_string(), "{}", &opts))
+            .unwrap();
+        let second = rt
+            .block_on(client.publish("chat".to_string(), "{}", &opts))
+            .unwrap();
+
+        let first_key = first.idempotency_key.unwrap();
+        assert_eq!(first_key.len(), 36);
+        assert_ne!(Some(first_key.clone()), second.idempotency_key);
+
+        // retried request carries the same key.
+        let keys: Vec<String> = requests
+            .join()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| {
+                let command: serde_json::Value = serde_json::from_slice(body).unwrap();
+                command["params"]["idempotency_key"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(keys[0], first_key);
+        assert_eq!(keys[1], first_key);
+        assert_eq!(Some(keys[2].clone()), second.idempotency_key);
+    }
+
+    #[test]
+    fn test_client_publish_with_options() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":3,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let options = PublishOptions::new().skip_history(true);
+        let result = rt
+            .block_on(client.publish_with_options("chat".to_string(), "{}", options))
+            .unwrap();
+        assert_eq!(result.offset, Some(3));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            r#"{"method":"publish","params":{"channel":"chat","data":{},"skip_history":true}}"#
+        );
+    }
+
+    #[test]
+    fn test_client_rate_limit() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"allowed":false,"tokens_left":0,"allowed_in":1500}}"#.to_vec(),
+        )]);
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let result = rt
+            .block_on(client.rate_limit(RateLimitRequest {
+                key: "login".to_string(),
+                rate: 10,
+                interval: 60000,
+                score: None,
+            }))
+            .unwrap();
+
+        assert!(!result.allowed);
+        assert_eq!(result.allowed_in, 1500);
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            r#"{"method":"rate_limit","params":{"key":"login","rate":10,"interval":60000}}"#
+        );
+    }
+
+    #[test]
+    fn test_client_get_user_status_error() {
+        let addr = serve_once(r#"{"error":{"code":108,"message":"not available"}}"#.to_string());
+        let config = Config {
+            addr: Some(addr),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        let err = rt
+            .block_on(client.get_user_status(vec!["42".to_string()]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "not available: 108");
     }
 
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_publish() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let data = r#"
@@ -102,14 +2759,30 @@ mod tests {
         assert!(!result.is_err());
     }
 
+    #[test]
+    fn test_broadcast_invalid_data() {
+        let client = Client::new(Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        let err = rt
+            .block_on(client.broadcast(vec!["chat".to_string()], "not json", &[]))
+            .unwrap_err();
+        assert!(err.is::<serde_json::Error>(), "{err}");
+    }
+
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_broadcast() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let data = r#"
@@ -137,11 +2810,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_subscribe() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -155,11 +2830,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_unsubscribe() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -176,11 +2853,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_disconnect() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         println!("{:?}", config.addr);
@@ -198,11 +2877,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_presence() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -214,11 +2895,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_presence_stats() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -230,11 +2913,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_history() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -246,11 +2931,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_history_remove() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -262,11 +2949,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_channels() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -278,11 +2967,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_info() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -294,11 +2985,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_multiple_commands() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -323,11 +3016,13 @@ mod tests {
     #[test]
     #[cfg(feature = "with_local_server")]
     fn test_client_multiple_request_in_pipe() {
+        let server = server();
         let config = Config {
-            addr: Some(ADDR.to_string()),
+            addr: Some(server.addr()),
             get_addr: None,
-            key: Some(API_KEY.to_string()),
+            key: Some(server.api_key()),
             http_client: None,
+            ..Default::default()
         };
 
         let rt = Runtime::new().unwrap();
@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// serve_once starts a server answering a single request with body and
+/// returns its address.
+pub fn serve_once(body: String) -> String {
+    serve_once_raw("", body.into_bytes()).0
+}
+
+/// serve_once_raw starts a server answering a single request with extra
+/// headers and body, returns its address and a handle resolving to headers
+/// of received request.
+pub fn serve_once_raw(headers: &str, body: Vec<u8>) -> (String, thread::JoinHandle<String>) {
+    let (addr, handle) = serve(vec![(200, headers.to_string(), body)]);
+    let handle = thread::spawn(move || handle.join().unwrap().remove(0).0);
+    (addr, handle)
+}
+
+/// Request is headers and body of request received by server.
+pub type Request = (String, Vec<u8>);
+
+/// serve starts a server answering requests with given status, extra headers
+/// and body one by one, returns its address and a handle resolving to headers
+/// and bodies of received requests.
+pub fn serve(responses: Vec<(u16, String, Vec<u8>)>) -> (String, thread::JoinHandle<Vec<Request>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}/api", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, headers, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_headers = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                request_headers.push_str(&line);
+            }
+            let mut request_body = vec![0; content_length];
+            reader.read_exact(&mut request_body).unwrap();
+
+            let mut response = format!(
+                "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+                status,
+                body.len(),
+                headers
+            )
+            .into_bytes();
+            response.extend_from_slice(&body);
+            reader.get_mut().write_all(&response).unwrap();
+            requests.push((request_headers, request_body));
+        }
+        requests
+    });
+
+    (addr, handle)
+}
+
+/// serve_keep_alive starts a server answering requests with bodies one by one
+/// over a single kept alive connection, returns its address.
+pub fn serve_keep_alive(bodies: Vec<Vec<u8>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}/api", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        for body in bodies {
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request_body = vec![0; content_length];
+            reader.read_exact(&mut request_body).unwrap();
+
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(&body);
+            reader.get_mut().write_all(&response).unwrap();
+        }
+    });
+
+    addr
+}
@@ -0,0 +1,78 @@
+use rucent::discovery::{
+    DiscoveredEndpoints, DnsDiscovery, Endpoint, ServiceDiscovery, StaticDiscovery,
+};
+use rucent::endpoint::EndpointSelector;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_static_discovery_notifies_about_change() {
+        let discovery = StaticDiscovery::new(["http://node1:8000/api"]);
+        let mut changes = discovery.changes().unwrap();
+        assert!(!changes.has_changed().unwrap());
+
+        discovery.set(["http://node2:8000/api", "http://node3:8000/api"]);
+        assert!(changes.has_changed().unwrap());
+        changes.mark_unchanged();
+
+        let rt = Runtime::new().unwrap();
+        let endpoints = rt.block_on(discovery.resolve()).unwrap();
+        assert_eq!(
+            endpoints,
+            vec![
+                Endpoint::from("http://node2:8000/api"),
+                Endpoint::from("http://node3:8000/api"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dns_discovery_resolves_localhost() {
+        let discovery = DnsDiscovery::new("localhost", 8000).with_path("/centrifugo/api");
+
+        let rt = Runtime::new().unwrap();
+        let endpoints = rt.block_on(discovery.resolve()).unwrap();
+
+        assert!(!endpoints.is_empty());
+        for endpoint in endpoints {
+            assert!(
+                endpoint.addr == "http://127.0.0.1:8000/centrifugo/api"
+                    || endpoint.addr == "http://[::1]:8000/centrifugo/api",
+                "{}",
+                endpoint.addr
+            );
+        }
+    }
+
+    #[test]
+    fn test_discovered_endpoints_refresh_keeps_stats() {
+        let discovery = Arc::new(StaticDiscovery::new([
+            "http://node1/api",
+            "http://node2/api",
+        ]));
+        let endpoints = DiscoveredEndpoints::new(discovery.clone(), Duration::from_secs(60));
+        let selector: &Arc<EndpointSelector> = endpoints.selector();
+        assert!(selector.select().is_none());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(endpoints.refresh()).unwrap();
+        selector.report("http://node1/api", Duration::from_millis(50), false);
+        selector.report("http://node2/api", Duration::from_millis(5), false);
+        assert_eq!(selector.select().unwrap(), "http://node2/api");
+
+        discovery.set(["http://node1/api", "http://node3/api"]);
+        rt.block_on(endpoints.refresh()).unwrap();
+
+        let stats = selector.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].latency, Some(Duration::from_millis(50)));
+        assert_eq!(stats[1].addr, "http://node3/api");
+        assert_eq!(stats[1].latency, None);
+    }
+}
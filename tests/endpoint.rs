@@ -0,0 +1,133 @@
+use rucent::endpoint::{EndpointCache, EndpointSelector};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_endpoint_cache_calls_get_addr_once_within_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cache = EndpointCache::new(
+            Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok("http://127.0.0.1:8000/api".to_string())
+            }),
+            Duration::from_secs(60),
+        );
+
+        for _ in 0..10 {
+            assert_eq!(cache.get().unwrap(), "http://127.0.0.1:8000/api");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_endpoint_cache_returns_stale_value_and_refreshes() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cache = EndpointCache::new(
+            Arc::new(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                Ok(format!("http://node{n}:8000/api"))
+            }),
+            Duration::from_millis(10),
+        );
+
+        assert_eq!(cache.get().unwrap(), "http://node0:8000/api");
+        thread::sleep(Duration::from_millis(20));
+
+        // no runtime here, so refresh happens inline but stale value is still returned.
+        assert_eq!(cache.get().unwrap(), "http://node0:8000/api");
+        assert_eq!(cache.get().unwrap(), "http://node1:8000/api");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_endpoint_cache_error_is_returned_when_empty() {
+        let cache = EndpointCache::new(
+            Arc::new(|| Err("discovery unavailable".into())),
+            Duration::from_secs(60),
+        );
+
+        assert!(cache.get().is_err());
+    }
+
+    #[test]
+    fn test_endpoint_cache_invalidate() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cache = EndpointCache::new(
+            Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok("http://127.0.0.1:8000/api".to_string())
+            }),
+            Duration::from_secs(60),
+        );
+
+        cache.get().unwrap();
+        cache.invalidate();
+        cache.get().unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_endpoint_selector_prefers_fastest() {
+        let selector = EndpointSelector::new(
+            vec!["a".to_string(), "b".to_string()],
+            Duration::from_secs(60),
+        );
+        // endpoints without samples are tried first.
+        assert_eq!(selector.select().unwrap(), "a");
+        selector.report("a", Duration::from_millis(50), false);
+        assert_eq!(selector.select().unwrap(), "b");
+        selector.report("b", Duration::from_millis(10), false);
+
+        for _ in 0..5 {
+            assert_eq!(selector.select().unwrap(), "b");
+        }
+        selector.report("b", Duration::from_millis(200), false);
+        selector.report("b", Duration::from_millis(200), false);
+        assert_eq!(selector.select().unwrap(), "a");
+    }
+
+    #[test]
+    fn test_endpoint_selector_demotes_and_probes() {
+        let selector = EndpointSelector::new(
+            vec!["a".to_string(), "b".to_string()],
+            Duration::from_millis(20),
+        );
+        selector.report("a", Duration::from_millis(1), false);
+        selector.report("b", Duration::from_millis(5), false);
+
+        selector.report("a", Duration::from_millis(1), true);
+        assert_eq!(selector.select().unwrap(), "a");
+        selector.report("a", Duration::from_millis(1), true);
+        assert!(!selector.stats()[0].healthy);
+        assert_eq!(selector.select().unwrap(), "b");
+
+        // after probe interval demoted endpoint gets a single probe request.
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(selector.select().unwrap(), "a");
+        assert_eq!(selector.select().unwrap(), "b");
+        selector.report("a", Duration::from_millis(1), false);
+        assert!(selector.stats()[0].healthy);
+        assert_eq!(selector.select().unwrap(), "a");
+    }
+
+    #[test]
+    fn test_endpoint_selector_all_demoted() {
+        let selector = EndpointSelector::new(vec!["a".to_string()], Duration::from_secs(60));
+        selector.report("a", Duration::from_millis(1), true);
+        selector.report("a", Duration::from_millis(1), true);
+        assert_eq!(selector.select().unwrap(), "a");
+        assert!(EndpointSelector::new(Vec::new(), Duration::from_secs(1))
+            .select()
+            .is_none());
+    }
+}
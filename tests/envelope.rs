@@ -0,0 +1,64 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config};
+use rucent::envelope::Envelope;
+use rucent::protocol::Publication;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct OrderCreated {
+        order_id: u64,
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let envelope = Envelope::new("order.created", OrderCreated { order_id: 7 });
+        assert_eq!(envelope.id.len(), 36);
+        assert!(envelope.timestamp > 0);
+        assert_ne!(envelope.id, Envelope::new("order.created", ()).id);
+
+        let data = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(data["type"], "order.created");
+        assert_eq!(data["payload"], json!({ "order_id": 7 }));
+
+        let publication = Publication {
+            offset: 1,
+            data,
+            b64data: None,
+            info: None,
+        };
+        assert_eq!(Envelope::decode(&publication).unwrap(), envelope);
+    }
+
+    #[test]
+    fn test_publish_envelope() {
+        let (addr, requests) = serve(vec![(
+            200,
+            String::new(),
+            br#"{"result":{"offset":1,"epoch":"e"}}"#.to_vec(),
+        )]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let envelope =
+            Envelope::new("order.created", OrderCreated { order_id: 7 }).with_id("evt-1");
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.publish_envelope("orders".to_string(), &envelope, &[]))
+            .unwrap();
+
+        let requests = requests.join().unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].1).unwrap();
+        assert_eq!(body["params"]["data"]["id"], "evt-1");
+        assert_eq!(body["params"]["data"]["payload"]["order_id"], 7);
+    }
+}
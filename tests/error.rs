@@ -0,0 +1,134 @@
+mod common;
+
+use common::{serve, serve_once_raw};
+use rucent::client::{Client, Config};
+use rucent::error::{ErrorExt, FailureKind};
+use rucent::pipe::PipeCommandError;
+use std::net::TcpListener;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn client(addr: String) -> Client {
+        Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_classify_status_and_api_errors() {
+        let (addr, _requests) = serve(vec![
+            (503, String::new(), b"unavailable".to_vec()),
+            (
+                200,
+                String::new(),
+                br#"{"error":{"code":102,"message":"unknown channel"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                b"{\"result\":{\"num_clients\":1,\"num_users\":1}}\n{\"error\":{\"code\":108,\"message\":\"not available\"}}"
+                    .to_vec(),
+            ),
+        ]);
+        let rt = Runtime::new().unwrap();
+        let client = client(addr);
+
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.as_status_code().unwrap().code, 503);
+        assert!(err.as_api_error().is_none());
+        assert!(err.is_retryable());
+        assert!(!err.is_timeout() && !err.is_connect());
+
+        let err = rt
+            .block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap_err();
+        assert_eq!(err.as_api_error().unwrap().code, 102);
+        assert!(err.as_status_code().is_none());
+        assert!(!err.is_retryable());
+
+        // api error is found in source of PipeCommandError.
+        let err = rt
+            .block_on(client.presence_stats_many(["chat", "news"]))
+            .unwrap_err();
+        assert!(err.downcast_ref::<PipeCommandError>().is_some());
+        assert_eq!(err.as_api_error().unwrap().code, 108);
+    }
+
+    #[test]
+    fn test_classify_transport_errors() {
+        let rt = Runtime::new().unwrap();
+
+        // nothing listens on port of dropped listener.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let err = rt
+            .block_on(client(format!("http://127.0.0.1:{port}/api")).info())
+            .unwrap_err();
+        assert!(err.is_connect());
+        assert!(err.is_retryable());
+        assert_eq!(err.failure_kind(), Some(FailureKind::Connect));
+
+        // listener accepts connection but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Client::new(Config {
+            addr: Some(format!("http://{}/api", listener.local_addr().unwrap())),
+            http_client: Some(
+                reqwest::Client::builder()
+                    .timeout(Duration::from_millis(100))
+                    .build()
+                    .unwrap(),
+            ),
+            ..Default::default()
+        });
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert!(err.is_timeout());
+        assert!(!err.is_connect());
+        assert_eq!(err.failure_kind(), Some(FailureKind::Timeout));
+        assert!(err.to_string().contains("kind: timeout"));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_classify_dns_tls_and_body_errors() {
+        let rt = Runtime::new().unwrap();
+
+        let err = rt
+            .block_on(client("http://rucent-test.invalid/api".to_string()).info())
+            .unwrap_err();
+        assert_eq!(err.failure_kind(), Some(FailureKind::Dns));
+
+        // plain HTTP server can't complete TLS handshake.
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        {
+            use std::io::Write;
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = format!("https://{}/api", listener.local_addr().unwrap());
+            let server = std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+            });
+            let err = rt.block_on(client(addr).info()).unwrap_err();
+            server.join().unwrap();
+            assert_eq!(err.failure_kind(), Some(FailureKind::TlsHandshake));
+        }
+
+        let (addr, _request) = serve_once_raw("Content-Encoding: gzip\r\n", b"not gzip".to_vec());
+        let err = rt.block_on(client(addr).info()).unwrap_err();
+        assert_eq!(err.failure_kind(), Some(FailureKind::BodyDecode));
+
+        // status errors are not transport failures.
+        let (addr, _requests) = serve(vec![(500, String::new(), Vec::new())]);
+        let err = rt.block_on(client(addr).info()).unwrap_err();
+        assert_eq!(err.failure_kind(), None);
+    }
+}
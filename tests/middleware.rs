@@ -0,0 +1,50 @@
+#![cfg(feature = "hmac")]
+use rucent::middleware::{HmacSigner, Middleware, DEFAULT_SIGNATURE_HEADER};
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_hmac_signer_sign() {
+        let signer = HmacSigner::new("key");
+        assert_eq!(
+            signer.sign(b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_hmac_signer_sets_header() {
+        let client = reqwest::Client::new();
+        let mut request = client
+            .post("http://127.0.0.1:8000/api")
+            .body("The quick brown fox jumps over the lazy dog")
+            .build()
+            .unwrap();
+
+        HmacSigner::new("key").on_request(&mut request).unwrap();
+        assert_eq!(
+            request.headers()[DEFAULT_SIGNATURE_HEADER],
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_hmac_signer_custom_header() {
+        let client = reqwest::Client::new();
+        let mut request = client
+            .post("http://127.0.0.1:8000/api")
+            .body("{}")
+            .build()
+            .unwrap();
+
+        HmacSigner::new("key")
+            .with_header("X-Body-Signature")
+            .on_request(&mut request)
+            .unwrap();
+        assert!(request.headers().contains_key("X-Body-Signature"));
+        assert!(!request.headers().contains_key(DEFAULT_SIGNATURE_HEADER));
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config};
+use rucent::namespace::{ErrNamespaceFeature, ErrUndeclaredNamespace, Namespace, Namespaces};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn namespaces() -> Namespaces {
+        Namespaces::new()
+            .with(Namespace::new("chat").presence(true).history(true))
+            .with(Namespace::new("news").history(true))
+    }
+
+    #[test]
+    fn test_namespaces_lookup() {
+        let namespaces = namespaces();
+        assert!(namespaces.lookup("chat:index").unwrap().presence);
+        assert!(!namespaces.get("news").unwrap().presence);
+
+        let err = namespaces.lookup("feed:index").unwrap_err();
+        assert_eq!(err.namespace.as_deref(), Some("feed"));
+        assert_eq!(
+            err.to_string(),
+            "channel feed:index is in undeclared namespace feed"
+        );
+        let err = namespaces.lookup("index").unwrap_err();
+        assert_eq!(err.namespace, None);
+
+        let namespaces = namespaces.with(Namespace::new(""));
+        assert!(namespaces.lookup("index").is_ok());
+    }
+
+    #[test]
+    fn test_namespace_channel() {
+        let client = Client::new(Config {
+            addr: Some("http://localhost:8000/api".to_string()),
+            ..Default::default()
+        });
+        let chat = Namespace::new("chat");
+        assert_eq!(chat.channel_name("room:1"), "chat:room:1");
+        assert_eq!(chat.channel(&client, "index").name(), "chat:index");
+        assert_eq!(Namespace::new("").channel_name("index"), "index");
+
+        let namespaces = namespaces();
+        assert_eq!(
+            namespaces.channel(&client, "news:sport").unwrap().name(),
+            "news:sport"
+        );
+        assert!(namespaces.channel(&client, "feed:sport").is_err());
+    }
+
+    #[test]
+    fn test_client_checks_namespaces() {
+        let (addr, requests) = serve(vec![
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+            (
+                200,
+                String::new(),
+                br#"{"result":{"num_clients":1,"num_users":1}}"#.to_vec(),
+            ),
+        ]);
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(Config {
+            addr: Some(addr),
+            namespaces: Some(namespaces()),
+            ..Default::default()
+        });
+
+        rt.block_on(client.publish("chat:index".to_string(), "{}", &[]))
+            .unwrap();
+        rt.block_on(client.presence_stats("chat:index".to_string()))
+            .unwrap();
+
+        let err = rt
+            .block_on(client.publish("feed:index".to_string(), "{}", &[]))
+            .unwrap_err();
+        assert!(err.downcast_ref::<ErrUndeclaredNamespace>().is_some());
+
+        let err = rt
+            .block_on(client.broadcast(
+                vec!["chat:index".to_string(), "feed:index".to_string()],
+                "{}",
+                &[],
+            ))
+            .unwrap_err();
+        assert!(err.downcast_ref::<ErrUndeclaredNamespace>().is_some());
+
+        let err = rt
+            .block_on(client.presence("news:sport".to_string()))
+            .unwrap_err();
+        let err = err.downcast_ref::<ErrNamespaceFeature>().unwrap();
+        assert_eq!(err.feature, "presence");
+        assert_eq!(
+            err.to_string(),
+            "presence is not enabled in namespace of channel news:sport"
+        );
+
+        // rejected commands are never sent.
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+}
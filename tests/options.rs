@@ -0,0 +1,77 @@
+use rucent::options::{
+    Disconnect, DisconnectOptions, PublishOptions, SubscribeOptions, UnsubscribeOptions,
+};
+use serde_json::json;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_client_field_names() {
+        let subscribe = SubscribeOptions::new().client("c1");
+        assert_eq!(
+            serde_json::to_value(&subscribe).unwrap(),
+            json!({ "client": "c1" })
+        );
+
+        let unsubscribe = UnsubscribeOptions::new().client("c1");
+        assert_eq!(
+            serde_json::to_value(&unsubscribe).unwrap(),
+            json!({ "client": "c1" })
+        );
+
+        let disconnect = DisconnectOptions::new()
+            .client("c1")
+            .client_whitelist(vec!["c2".to_string()]);
+        assert_eq!(
+            serde_json::to_value(&disconnect).unwrap(),
+            json!({ "client": "c1", "whitelist": ["c2"] })
+        );
+    }
+
+    #[test]
+    fn test_client_field_old_names_accepted() {
+        let options: DisconnectOptions = serde_json::from_value(json!({
+            "client_id": "c1",
+            "client_whitelist": ["c2"],
+        }))
+        .unwrap();
+        assert_eq!(options.client_id.as_deref(), Some("c1"));
+        assert_eq!(options.client_whitelist, Some(vec!["c2".to_string()]));
+
+        let options: SubscribeOptions =
+            serde_json::from_value(json!({ "client_id": "c1" })).unwrap();
+        assert_eq!(options.client_id.as_deref(), Some("c1"));
+    }
+
+    #[test]
+    fn test_disconnect_omits_unset_fields() {
+        let disconnect = Disconnect {
+            code: Some(4000),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&disconnect).unwrap(),
+            json!({ "code": 4000 })
+        );
+        assert_eq!(
+            serde_json::to_value(DisconnectOptions::new()).unwrap(),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn test_publish_options_omit_unset_fields() {
+        assert_eq!(
+            serde_json::to_value(PublishOptions::new()).unwrap(),
+            json!({})
+        );
+        let options = PublishOptions::new().skip_history(true);
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({ "skip_history": true })
+        );
+    }
+}
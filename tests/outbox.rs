@@ -0,0 +1,247 @@
+mod common;
+
+use common::{serve, serve_once};
+use rucent::client::{Client, Config};
+use rucent::outbox::{FileStore, OutboxPublisher, OutboxStore};
+use rucent::pipe::RequestKind;
+use rucent::retry::{dead_letter_channel, RetryPolicy};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn store_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rucent-outbox-{}-{}.log", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn new_client(addr: String) -> Client {
+        Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let path = store_path("reopen");
+        let client = new_client("http://127.0.0.1:1/api".to_string());
+
+        let publisher = OutboxPublisher::new(client, Arc::new(FileStore::open(&path).unwrap()));
+        for i in 0..3 {
+            publisher
+                .publish("chat".to_string(), &format!(r#"{{"n":{i}}}"#), &[])
+                .unwrap();
+        }
+        drop(publisher);
+
+        let store = FileStore::open(&path).unwrap();
+        let pending = store.pending().unwrap();
+        assert_eq!(
+            pending.iter().map(|entry| entry.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(pending[0].command.method, "publish");
+
+        store.ack(1).unwrap();
+        drop(store);
+
+        let store = FileStore::open(&path).unwrap();
+        assert_eq!(store.pending().unwrap().len(), 2);
+        assert_eq!(store.append(&pending[0].command).unwrap(), 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_flush_acks_delivered_only() {
+        let path = store_path("flush");
+        let addr = serve_once(
+            "{\"result\":{}}\n{\"error\":{\"code\":111,\"message\":\"too many requests\"}}"
+                .to_string(),
+        );
+
+        let store = Arc::new(FileStore::open(&path).unwrap());
+        let publisher = OutboxPublisher::new(new_client(addr), store.clone());
+        publisher.publish("chat".to_string(), "{}", &[]).unwrap();
+        publisher.publish("limited".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        assert_eq!(rt.block_on(publisher.flush()).unwrap(), 1);
+
+        let pending = store.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_drops_entry_after_max_attempts() {
+        let path = store_path("max_attempts");
+        let addr = serve_once(r#"{"error":{"code":100,"message":"internal"}}"#.to_string());
+
+        let store = Arc::new(FileStore::open(&path).unwrap());
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let publisher = OutboxPublisher::new(new_client(addr), store.clone())
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 1,
+                ..Default::default()
+            })
+            .with_dead_letter(dead_letter_channel(sender));
+        publisher.publish("chat".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        assert_eq!(rt.block_on(publisher.flush()).unwrap(), 0);
+        assert_eq!(publisher.pending().unwrap(), 0);
+
+        let (command, err) = receiver.try_recv().unwrap();
+        assert_eq!(command.method, "publish");
+        assert_eq!(err, "internal: 100");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_drops_permanently_rejected_entry() {
+        let path = store_path("rejected");
+        let (addr, requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"error":{"code":102,"message":"unknown channel"}}"#.to_vec(),
+            ),
+            (
+                200,
+                String::new(),
+                br#"{"error":{"code":102,"message":"unknown channel"}}"#.to_vec(),
+            ),
+        ]);
+
+        let store = Arc::new(FileStore::open(&path).unwrap());
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        // default policy retries transient errors forever, but not this one.
+        let publisher = OutboxPublisher::new(new_client(addr.clone()), store.clone())
+            .with_dead_letter(dead_letter_channel(sender));
+        publisher.publish("unknown".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        assert_eq!(rt.block_on(publisher.flush()).unwrap(), 0);
+        assert_eq!(publisher.pending().unwrap(), 0);
+        let (_, err) = receiver.try_recv().unwrap();
+        assert_eq!(err, "unknown channel: 102");
+
+        // background task is not stuck backing off on rejected entry.
+        let publisher = Arc::new(publisher);
+        publisher.publish("unknown".to_string(), "{}", &[]).unwrap();
+        rt.block_on(async {
+            let task = publisher.clone().start();
+            let (_, err) = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(err, "unknown channel: 102");
+            task.abort();
+        });
+        assert_eq!(publisher.pending().unwrap(), 0);
+        assert_eq!(requests.join().unwrap().len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_isolates_entry_rejected_with_bad_request() {
+        let path = store_path("bad_request");
+        let ok = (200, String::new(), br#"{"result":{}}"#.to_vec());
+        let bad_request = (400, String::new(), b"bad request".to_vec());
+        let (addr, requests) = serve(vec![bad_request.clone(), ok.clone(), bad_request, ok]);
+
+        let store = Arc::new(FileStore::open(&path).unwrap());
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let publisher = OutboxPublisher::new(new_client(addr), store.clone())
+            .with_dead_letter(dead_letter_channel(sender));
+        for channel in ["a", "b", "c"] {
+            publisher.publish(channel.to_string(), "{}", &[]).unwrap();
+        }
+
+        let rt = Runtime::new().unwrap();
+        assert_eq!(rt.block_on(publisher.flush()).unwrap(), 2);
+        assert_eq!(publisher.pending().unwrap(), 0);
+
+        let (command, err) = receiver.try_recv().unwrap();
+        assert!(matches!(
+            command.params,
+            RequestKind::PublishRequest(request) if request.channel == "b"
+        ));
+        assert!(err.contains("400"), "{err}");
+        assert!(receiver.try_recv().is_err());
+
+        // whole batch first, then entries one by one.
+        let lines: Vec<_> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(_, body)| String::from_utf8(body).unwrap().lines().count())
+            .collect();
+        assert_eq!(lines, vec![3, 1, 1, 1]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_keeps_entries_on_transport_error() {
+        let path = store_path("transport");
+        let publisher = OutboxPublisher::new(
+            new_client("http://127.0.0.1:1/api".to_string()),
+            Arc::new(FileStore::open(&path).unwrap()),
+        );
+        publisher.publish("chat".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        assert!(rt.block_on(publisher.flush()).is_err());
+        assert_eq!(publisher.pending().unwrap(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_outbox_task_stops_on_shutdown() {
+        let path = store_path("shutdown");
+        let client = new_client("http://127.0.0.1:1/api".to_string());
+        let publisher = Arc::new(OutboxPublisher::new(
+            client.clone(),
+            Arc::new(FileStore::open(&path).unwrap()),
+        ));
+        publisher.publish("chat".to_string(), "{}", &[]).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let report = rt.block_on(async {
+            let task = publisher.clone().start();
+            let report = client.shutdown(Duration::from_secs(1)).await;
+            task.await.unwrap();
+            report
+        });
+
+        assert!(report.is_clean());
+        assert_eq!(publisher.pending().unwrap(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), policy.min_backoff);
+        assert_eq!(policy.backoff(2), policy.min_backoff * 2);
+        assert_eq!(policy.backoff(100), policy.max_backoff);
+        assert!(policy.can_retry(2));
+        assert!(!policy.can_retry(3));
+    }
+}
@@ -0,0 +1,63 @@
+use rucent::pattern::{namespace, ChannelPattern};
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_channel_pattern_matches() {
+        let cases = [
+            ("chat:*", "chat:index", true),
+            ("chat:*", "chat:", true),
+            ("chat:*", "news:index", false),
+            ("*", "chat:room:1", true),
+            ("chat:room:?", "chat:room:1", true),
+            ("chat:room:?", "chat:room:12", false),
+            ("user:[0-9]*", "user:42", true),
+            ("user:[0-9]*", "user:x42", false),
+            ("user:[!0-9]*", "user:x42", true),
+            ("{chat,news}:*", "news:sport", true),
+            ("{chat,news}:*", "feed:sport", false),
+            ("*:{a,b{1,2}}", "x:b2", true),
+            ("*:{a,b{1,2}}", "x:b3", false),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+            (r"chat:\*", "chat:*", true),
+            (r"chat:\*", "chat:index", false),
+            ("exact", "exact", true),
+            ("exact", "exactly", false),
+        ];
+        for (pattern, channel, matches) in cases {
+            let compiled = ChannelPattern::new(pattern).unwrap();
+            assert_eq!(compiled.matches(channel), matches, "{pattern} {channel}");
+        }
+    }
+
+    #[test]
+    fn test_channel_pattern_invalid() {
+        for pattern in ["chat:[a-", "{chat,news", r"chat:\", "[z-a]"] {
+            let err = ChannelPattern::new(pattern).unwrap_err();
+            assert_eq!(err.pattern, pattern);
+        }
+    }
+
+    #[test]
+    fn test_channel_pattern_filter() {
+        let pattern = ChannelPattern::new("chat:*").unwrap();
+        let channels = vec![
+            "chat:a".to_string(),
+            "news:b".to_string(),
+            "chat:c".to_string(),
+        ];
+        assert_eq!(pattern.filter(channels), vec!["chat:a", "chat:c"]);
+        assert_eq!(pattern.filter(["news:b"]), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_namespace() {
+        assert_eq!(namespace("chat:index"), Some("chat"));
+        assert_eq!(namespace("chat:room:1"), Some("chat"));
+        assert_eq!(namespace("index"), None);
+    }
+}
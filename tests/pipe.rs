@@ -0,0 +1,543 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config};
+use rucent::options::{
+    unix_time, with_connections_user, with_disconnect_client, with_limit, with_pattern,
+    with_refresh_expire_in, with_refresh_expired, with_skip_history, DisconnectOptions,
+    HistoryOptions, PublishOptions, RefreshOptions, SubscribeOptions, UnsubscribeOptions,
+};
+use rucent::pipe::{
+    Command, DeviceListRequest, DeviceRegisterRequest, DeviceRemoveRequest, DeviceTopicListRequest,
+    DeviceTopicUpdateRequest, DeviceUpdateRequest, ErrMethodMismatch, Method, Pipe,
+    PresenceRequest, RateLimitRequest, RequestKind, SendPushNotificationRequest,
+    UserTopicListRequest, UserTopicUpdateRequest,
+};
+use rucent::protocol::{Error, Replies, Reply};
+use rucent::timestamp::to_unix;
+use serde_json::json;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn new_client() -> Client {
+        Client::new(Config {
+            addr: Some("http://127.0.0.1:8000/api".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_request_body_flattens_options() {
+        let replies = [r#"{"result":{}}"#; 6].join("\n");
+        let (addr, requests) = serve(vec![(200, String::new(), replies.into_bytes())]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let pipe = client.pipe();
+        let publish = PublishOptions::new().skip_history(true);
+        pipe.add_publish_with_options("chat".to_string(), r#"{"n":1}"#, publish.clone())
+            .unwrap();
+        pipe.add_broadcast_with_options(vec!["a".to_string()], r#"{"n":2}"#, publish)
+            .unwrap();
+        pipe.add_subscribe_with_options(
+            "chat".to_string(),
+            "42".to_string(),
+            SubscribeOptions::new().presence(true).client("c1"),
+        )
+        .unwrap();
+        pipe.add_unsubscribe_with_options(
+            "chat".to_string(),
+            "42".to_string(),
+            UnsubscribeOptions::new().client("c1"),
+        )
+        .unwrap();
+        pipe.add_disconnect_with_options(
+            "42".to_string(),
+            DisconnectOptions::new()
+                .client("c1")
+                .client_whitelist(vec!["c2".to_string()]),
+        )
+        .unwrap();
+        pipe.add_history_with_options("chat".to_string(), HistoryOptions::new().limit(10))
+            .unwrap();
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        let params: Vec<serde_json::Value> = String::from_utf8(body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["params"].clone())
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                json!({ "channel": "chat", "data": { "n": 1 }, "skip_history": true }),
+                json!({ "channels": ["a"], "data": { "n": 2 }, "skip_history": true }),
+                json!({ "channel": "chat", "user": "42", "presence": true, "client": "c1" }),
+                json!({ "channel": "chat", "user": "42", "client": "c1" }),
+                json!({ "user": "42", "client": "c1", "whitelist": ["c2"] }),
+                json!({ "channel": "chat", "limit": 10 }),
+            ]
+        );
+
+        // pipe persisted with to_json uses the same flat layout.
+        let json = pipe.to_json().unwrap();
+        assert!(json.contains(r#""params":{"channel":"chat","data":{"n":1},"skip_history":true}"#));
+        assert!(!json.contains(r#""options""#));
+    }
+
+    #[test]
+    fn test_pipe_json_round_trip() {
+        let pipe = new_client().pipe();
+        pipe.add_publish(
+            "chat".to_string(),
+            r#"{"text":"hi"}"#,
+            &[with_skip_history(true)],
+        )
+        .unwrap();
+        pipe.add_broadcast(vec!["a".to_string(), "b".to_string()], "{}", &[])
+            .unwrap();
+        pipe.add_subscribe("chat".to_string(), "42".to_string(), &[])
+            .unwrap();
+        pipe.add_unsubscribe("chat".to_string(), "42".to_string(), &[])
+            .unwrap();
+        pipe.add_disconnect(
+            "42".to_string(),
+            &[with_disconnect_client("c1".to_string())],
+        )
+        .unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+        pipe.add_presence_stats("chat".to_string()).unwrap();
+        pipe.add_history("chat".to_string(), &[with_limit(10)])
+            .unwrap();
+        pipe.add_history_remove("chat".to_string()).unwrap();
+        pipe.add_channels(&[with_pattern("chat*".to_string())])
+            .unwrap();
+        pipe.add_info().unwrap();
+
+        let json = pipe.to_json().unwrap();
+        let restored = Pipe::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+
+        let commands = restored.commands();
+        assert_eq!(commands.len(), 11);
+        assert!(matches!(
+            &commands[0].params,
+            RequestKind::PublishRequest(req) if req.options.skip_history == Some(true)
+        ));
+        assert!(matches!(
+            &commands[1].params,
+            RequestKind::BroadcastRequest(_)
+        ));
+        assert!(matches!(
+            &commands[2].params,
+            RequestKind::SubscribeRequest(_)
+        ));
+        assert!(matches!(
+            &commands[3].params,
+            RequestKind::UnsubscribeRequest(_)
+        ));
+        assert!(matches!(
+            &commands[4].params,
+            RequestKind::DisconnectRequest(req) if req.options.client_id.as_deref() == Some("c1")
+        ));
+        assert!(matches!(
+            &commands[7].params,
+            RequestKind::HistoryRequest(req) if req.options.limit == Some(10)
+        ));
+        assert!(matches!(
+            &commands[9].params,
+            RequestKind::ChannelsRequest(req) if req.pattern.as_deref() == Some("chat*")
+        ));
+        assert_eq!(commands[10].method, "info");
+    }
+
+    #[test]
+    fn test_method() {
+        let pipe = new_client().pipe();
+        pipe.add_presence_stats("chat".to_string()).unwrap();
+        pipe.add_rate_limit(RateLimitRequest {
+            key: "login".to_string(),
+            rate: 1,
+            interval: 1000,
+            score: None,
+        })
+        .unwrap();
+        assert_eq!(
+            pipe.methods(),
+            vec![Method::PresenceStats, Method::RateLimit]
+        );
+        assert!(pipe
+            .to_json()
+            .unwrap()
+            .contains(r#""method":"presence_stats""#));
+
+        for method in [
+            "publish",
+            "presence_stats",
+            "send_push_notification",
+            "info",
+        ] {
+            let parsed = Method::from(method);
+            assert!(!matches!(parsed, Method::Custom(_)));
+            assert_eq!(parsed.as_str(), method);
+            assert_eq!(parsed.to_string(), method);
+        }
+
+        let command: Command =
+            serde_json::from_str(r#"{"method":"new_method","params":{"x":1}}"#).unwrap();
+        assert_eq!(command.method, Method::Custom("new_method".to_string()));
+        assert!(matches!(command.params, RequestKind::Value(_)));
+        assert_eq!(
+            serde_json::to_string(&command).unwrap(),
+            r#"{"method":"new_method","params":{"x":1}}"#
+        );
+    }
+
+    #[test]
+    fn test_pipe_json_round_trip_every_command() {
+        let pipe = new_client().pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_broadcast(vec!["a".to_string()], "{}", &[])
+            .unwrap();
+        pipe.add_subscribe("chat".to_string(), "42".to_string(), &[])
+            .unwrap();
+        pipe.add_unsubscribe("chat".to_string(), "42".to_string(), &[])
+            .unwrap();
+        pipe.add_disconnect("42".to_string(), &[]).unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+        pipe.add_presence_stats("chat".to_string()).unwrap();
+        pipe.add_history("chat".to_string(), &[]).unwrap();
+        pipe.add_history_remove("chat".to_string()).unwrap();
+        pipe.add_channels(&[]).unwrap();
+        pipe.add_info().unwrap();
+        pipe.add_refresh("42".to_string(), &[]).unwrap();
+        pipe.add_connections(&[]).unwrap();
+        pipe.add_update_user_status(vec!["42".to_string()], Some("away".to_string()))
+            .unwrap();
+        pipe.add_get_user_status(vec!["42".to_string()]).unwrap();
+        pipe.add_delete_user_status(vec!["42".to_string()]).unwrap();
+        pipe.add_device_register(DeviceRegisterRequest {
+            provider: "fcm".to_string(),
+            token: "t".to_string(),
+            platform: "web".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        pipe.add_device_update(DeviceUpdateRequest::default())
+            .unwrap();
+        pipe.add_device_remove(DeviceRemoveRequest::default())
+            .unwrap();
+        pipe.add_device_list(DeviceListRequest::default()).unwrap();
+        pipe.add_device_topic_list(DeviceTopicListRequest::default())
+            .unwrap();
+        pipe.add_device_topic_update(DeviceTopicUpdateRequest {
+            device_id: "d".to_string(),
+            op: "add".to_string(),
+            topics: vec!["news".to_string()],
+        })
+        .unwrap();
+        pipe.add_user_topic_list(UserTopicListRequest::default())
+            .unwrap();
+        pipe.add_user_topic_update(UserTopicUpdateRequest {
+            user: "42".to_string(),
+            op: "set".to_string(),
+            topics: Vec::new(),
+        })
+        .unwrap();
+        pipe.add_send_push_notification(SendPushNotificationRequest::default())
+            .unwrap();
+        pipe.add_rate_limit(RateLimitRequest::default()).unwrap();
+
+        let json = pipe.to_json().unwrap();
+        let restored = Pipe::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+
+        let commands = restored.commands();
+        assert_eq!(commands.len(), 26);
+        for command in commands.iter() {
+            assert!(!matches!(command.method, Method::Custom(_)));
+            assert_eq!(command.params.method().as_ref(), Some(&command.method));
+            command.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pipe_rejects_mismatched_params() {
+        let pipe = new_client().pipe();
+        let err = pipe
+            .add(Command {
+                method: Method::Info,
+                params: RequestKind::PresenceRequest(PresenceRequest {
+                    channel: "chat".to_string(),
+                }),
+            })
+            .unwrap_err();
+        let err = err.downcast_ref::<ErrMethodMismatch>().unwrap();
+        assert_eq!(err.params_method, Some(Method::Presence));
+        assert_eq!(
+            err.to_string(),
+            "info command can't have params of presence command"
+        );
+
+        assert!(pipe
+            .add(Command {
+                method: Method::Publish,
+                params: RequestKind::Value(serde_json::json!({"channel": "chat"})),
+            })
+            .is_err());
+        assert!(pipe.is_empty());
+
+        pipe.add(Command::custom("new_method", serde_json::json!({"x": 1})))
+            .unwrap();
+        pipe.add_custom("presence", serde_json::json!({"channel": "chat"}))
+            .unwrap();
+        assert!(pipe
+            .add_custom("presence", serde_json::json!({"user": "42"}))
+            .is_err());
+        assert_eq!(pipe.methods(), vec!["new_method", "presence"]);
+        assert!(matches!(
+            pipe.iter().nth(1).unwrap().params,
+            RequestKind::PresenceRequest(_)
+        ));
+
+        // params of presence can't be decoded as params of another command.
+        assert!(Pipe::from_json(r#"[{"method":"publish","params":{"channel":"chat"}}]"#).is_err());
+    }
+
+    #[test]
+    fn test_pipe_from_json_invalid() {
+        assert!(Pipe::from_json("not json").is_err());
+        assert!(Pipe::from_json(r#"[{"method":"publish","params":{}}]"#).is_err());
+    }
+
+    #[test]
+    fn test_refresh_expiration_from_duration_and_system_time() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(unix_time(at), 1_700_000_000);
+        assert_eq!(
+            RefreshOptions::new()
+                .expire_at_time(at)
+                .expire_at
+                .map(to_unix),
+            Some(1_700_000_000)
+        );
+
+        let before = unix_time(SystemTime::now());
+        let pipe = new_client().pipe();
+        pipe.add_refresh(
+            "42".to_string(),
+            &[with_refresh_expire_in(Duration::from_secs(3600))],
+        )
+        .unwrap();
+        let expire_at = match pipe.iter().next().unwrap().params {
+            RequestKind::RefreshRequest(request) => to_unix(request.options.expire_at.unwrap()),
+            other => panic!("unexpected params: {:?}", other),
+        };
+        assert!((before + 3600..=before + 3601).contains(&expire_at));
+    }
+
+    #[test]
+    fn test_pipe_json_round_trip_extended_commands() {
+        let pipe = new_client().pipe();
+        pipe.add_refresh("42".to_string(), &[with_refresh_expired(true)])
+            .unwrap();
+        pipe.add_connections(&[with_connections_user("42".to_string())])
+            .unwrap();
+        pipe.add_get_user_status(vec!["42".to_string()]).unwrap();
+        pipe.add_device_list(DeviceListRequest {
+            limit: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+        pipe.add_rate_limit(RateLimitRequest {
+            key: "login".to_string(),
+            rate: 10,
+            interval: 60000,
+            score: None,
+        })
+        .unwrap();
+
+        let json = pipe.to_json().unwrap();
+        assert!(json.contains(r#"{"method":"refresh","params":{"user":"42","expired":true}}"#));
+
+        let restored = Pipe::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+
+        let commands = restored.commands();
+        assert!(matches!(
+            &commands[0].params,
+            RequestKind::RefreshRequest(req) if req.options.expired == Some(true)
+        ));
+        assert!(matches!(
+            &commands[1].params,
+            RequestKind::ConnectionsRequest(req) if req.options.user.as_deref() == Some("42")
+        ));
+        assert!(matches!(
+            &commands[2].params,
+            RequestKind::GetUserStatusRequest(_)
+        ));
+        assert!(matches!(
+            &commands[3].params,
+            RequestKind::DeviceListRequest(req) if req.limit == Some(10)
+        ));
+        assert!(matches!(
+            &commands[4].params,
+            RequestKind::RateLimitRequest(req) if req.key == "login"
+        ));
+    }
+
+    #[test]
+    fn test_pipe_with_options_wire_format() {
+        let pipe = new_client().pipe();
+        let options = PublishOptions::new()
+            .skip_history(true)
+            .tags([("source".to_string(), "api".to_string())].into())
+            .idempotency_key("k1");
+        pipe.add_publish_with_options("chat".to_string(), "{}", options)
+            .unwrap();
+        pipe.add_disconnect_with_options("42".to_string(), DisconnectOptions::new().client("c1"))
+            .unwrap();
+        pipe.add_history_with_options("chat".to_string(), HistoryOptions::new().limit(5))
+            .unwrap();
+
+        let json = pipe.to_json().unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"[{"method":"publish","params":{"channel":"chat","data":{},"skip_history":true,"tags":{"source":"api"},"idempotency_key":"k1"}},"#,
+                r#"{"method":"disconnect","params":{"user":"42","client":"c1"}},"#,
+                r#"{"method":"history","params":{"channel":"chat","limit":5}}]"#
+            )
+        );
+
+        let restored = Pipe::from_json(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn test_pipe_introspection() {
+        let pipe = new_client().pipe();
+        assert!(pipe.is_empty());
+
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_publish("deleted".to_string(), "{}", &[]).unwrap();
+        pipe.add_info().unwrap();
+        assert_eq!(pipe.len(), 3);
+        assert_eq!(pipe.methods(), vec!["publish", "publish", "info"]);
+
+        let idx = pipe
+            .iter()
+            .position(|cmd| {
+                matches!(&cmd.params, RequestKind::PublishRequest(req) if req.channel == "deleted")
+            })
+            .unwrap();
+        let removed = pipe.remove(idx).unwrap();
+        assert_eq!(removed.method, "publish");
+        assert!(pipe.remove(10).is_none());
+        assert_eq!(pipe.methods(), vec!["publish", "info"]);
+    }
+
+    #[test]
+    fn test_pipe_survives_poisoned_lock() {
+        let pipe = new_client().pipe();
+        pipe.add_info().unwrap();
+
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| pipe.update(|_| panic!("task panicked holding pipe")))
+                .join()
+                .unwrap_err();
+        });
+
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        assert_eq!(pipe.methods(), vec!["info", "publish"]);
+        assert!(pipe.to_json().is_ok());
+        pipe.reset();
+        assert!(pipe.is_empty());
+        assert!(pipe.take_commands().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pipe_errors() {
+        let pipe = new_client().pipe();
+        pipe.add_info().unwrap();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+
+        let replies = vec![
+            Reply {
+                error: None,
+                result: Some(serde_json::json!({})),
+            },
+            Reply {
+                error: Some(Error {
+                    code: 102,
+                    message: "unknown channel".to_string(),
+                }),
+                result: None,
+            },
+            Reply {
+                error: None,
+                result: Some(serde_json::json!({})),
+            },
+        ];
+
+        let errors = pipe.errors(&replies);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].index, 1);
+        assert_eq!(errors[0].method, "publish");
+        assert_eq!(errors[0].error.code, 102);
+        assert_eq!(
+            errors[0].to_string(),
+            "command 1 (publish) failed: unknown channel: 102"
+        );
+    }
+
+    #[test]
+    fn test_replies_errors() {
+        let replies = Replies::from(vec![
+            Reply {
+                error: Some(Error {
+                    code: 100,
+                    message: "internal server error".to_string(),
+                }),
+                result: None,
+            },
+            Reply {
+                error: None,
+                result: None,
+            },
+            Reply {
+                error: Some(Error {
+                    code: 102,
+                    message: "unknown channel".to_string(),
+                }),
+                result: None,
+            },
+        ]);
+
+        assert!(replies.has_errors());
+        assert_eq!(
+            replies
+                .errors()
+                .map(|(index, err)| (index, err.code))
+                .collect::<Vec<_>>(),
+            vec![(0, 100), (2, 102)]
+        );
+        assert_eq!(replies.len(), 3);
+
+        let results = replies.into_results();
+        assert_eq!(results[0].as_ref().unwrap_err().code, 100);
+        assert_eq!(results[1].as_ref().unwrap(), &serde_json::Value::Null);
+    }
+}
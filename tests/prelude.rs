@@ -0,0 +1,38 @@
+mod common;
+
+use common::serve_once;
+use rucent::prelude::*;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    async fn publish(client: &Client) -> rucent::Result<PublishResult> {
+        client
+            .publish(
+                "chat".to_string(),
+                "{}",
+                &[with_skip_history(true), with_auto_idempotency_key()],
+            )
+            .await
+    }
+
+    #[test]
+    fn test_prelude_publish() {
+        let addr = serve_once(r#"{"result":{"offset":1,"epoch":"e"}}"#.to_string());
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let result = rt.block_on(publish(&client)).unwrap();
+        assert_eq!(result.offset, Some(1));
+
+        // alias keeps std error parameter usable.
+        let parsed: rucent::Result<u32, std::num::ParseIntError> = "7".parse();
+        assert_eq!(parsed, Ok(7));
+    }
+}
@@ -0,0 +1,240 @@
+use rucent::protocol::{
+    ChannelsResult, DataError, HistoryResult, InfoResult, NamespaceStats, NodeTotals, PayloadData,
+    PresenceResult,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Message {
+        text: String,
+    }
+
+    #[test]
+    fn test_publication_data_as() {
+        let history: HistoryResult = serde_json::from_value(json!({
+            "publication": [
+                { "offset": 1, "data": { "text": "hi" } },
+                { "offset": 2, "data": { "body": "hi" } },
+            ],
+            "offset": 2,
+            "epoch": "e",
+        }))
+        .unwrap();
+
+        let message: Message = history.publication[0].data_as().unwrap();
+        assert_eq!(message.text, "hi");
+
+        let err: DataError = history.publication[1].data_as::<Message>().unwrap_err();
+        assert_eq!(err.field, "data");
+        assert!(err.type_name.ends_with("Message"));
+        assert!(err.to_string().contains("missing field `text`"), "{err}");
+
+        let raw = history.publication[1].data_raw().unwrap();
+        assert_eq!(raw.get(), r#"{"body":"hi"}"#);
+    }
+
+    #[test]
+    fn test_publication_payload() {
+        let history: HistoryResult = serde_json::from_value(json!({
+            "publication": [
+                { "offset": 1, "data": { "text": "hi" } },
+                { "offset": 2, "b64data": "AAEC/w==" },
+                { "offset": 3, "b64data": "not base64!" },
+            ],
+            "offset": 3,
+            "epoch": "e",
+        }))
+        .unwrap();
+        let publications = &history.publication;
+
+        assert!(!publications[0].is_binary());
+        assert!(publications[0].binary_data().is_none());
+        assert_eq!(
+            publications[0].payload().unwrap(),
+            PayloadData::Json(json!({ "text": "hi" }))
+        );
+
+        assert!(publications[1].is_binary());
+        assert_eq!(
+            publications[1].payload().unwrap(),
+            PayloadData::Binary(vec![0, 1, 2, 255])
+        );
+
+        let err = publications[2].payload().unwrap_err();
+        assert_eq!(err.field, "b64data");
+    }
+
+    #[test]
+    fn test_history_iteration() {
+        let history: HistoryResult = serde_json::from_value(json!({
+            "publication": [
+                { "offset": 9, "data": { "text": "c" } },
+                { "offset": 8, "data": { "text": "b" } },
+                { "offset": 7, "data": { "text": "a" } },
+            ],
+            "offset": 9,
+            "epoch": "e1",
+        }))
+        .unwrap();
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.offsets().collect::<Vec<_>>(), vec![9, 8, 7]);
+        assert_eq!(history.latest().unwrap().offset, 9);
+
+        let position = history.position();
+        assert_eq!(position.offset, Some(9));
+        assert_eq!(position.epoch.as_deref(), Some("e1"));
+
+        let mut texts = Vec::new();
+        for publication in &history {
+            texts.push(publication.data_as::<Message>().unwrap().text);
+        }
+        assert_eq!(texts, vec!["c", "b", "a"]);
+
+        let owned: Vec<_> = history.into_iter().map(|p| p.offset).collect();
+        assert_eq!(owned, vec![9, 8, 7]);
+    }
+
+    #[test]
+    fn test_channels_aggregation() {
+        let channels: ChannelsResult = serde_json::from_value(json!({
+            "channels": {
+                "chat:a": { "num_clients": 5 },
+                "chat:b": { "num_clients": 2 },
+                "news": { "num_clients": 5 },
+                "orders:1": { "num_clients": 1 },
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(channels.total_clients(), 13);
+        assert_eq!(
+            channels.sorted_by_clients(),
+            vec![("chat:a", 5), ("news", 5), ("chat:b", 2), ("orders:1", 1)]
+        );
+        assert_eq!(channels.top_n(2), vec![("chat:a", 5), ("news", 5)]);
+        assert_eq!(channels.top_n(10).len(), 4);
+
+        let namespaces = channels.namespaces();
+        assert_eq!(
+            namespaces[&Some("chat")],
+            NamespaceStats {
+                num_channels: 2,
+                num_clients: 7,
+            }
+        );
+        assert_eq!(namespaces[&None].num_clients, 5);
+        assert_eq!(namespaces.len(), 3);
+    }
+
+    #[test]
+    fn test_info_aggregation() {
+        let node = |name: &str, version: &str, clients: u32| {
+            json!({
+                "uid": format!("uid-{}", name),
+                "name": name,
+                "version": version,
+                "num_clients": clients,
+                "num_users": clients / 2,
+                "num_channels": 3,
+                "uptime": 60,
+            })
+        };
+        let info: InfoResult = serde_json::from_value(json!({
+            "nodes": [
+                node("n1", "5.10.0", 10),
+                node("n2", "5.9.1", 4),
+                node("n3", "v5.10.0-rc1", 2),
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            info.totals(),
+            NodeTotals {
+                num_nodes: 3,
+                num_clients: 16,
+                num_users: 8,
+                num_channels: 9,
+            }
+        );
+        assert_eq!(info.node_by_name("n2").unwrap().uid, "uid-n2");
+        assert!(info.node_by_name("n4").is_none());
+        assert_eq!(info.min_version(), Some("5.9.1"));
+        assert_eq!(info.max_version(), Some("5.10.0"));
+        assert!(info.has_mixed_versions());
+
+        let empty = InfoResult { nodes: Vec::new() };
+        assert_eq!(empty.totals(), NodeTotals::default());
+        assert_eq!(empty.min_version(), None);
+        assert!(!empty.has_mixed_versions());
+    }
+
+    #[test]
+    fn test_node_info_extended_fields() {
+        let info: InfoResult = serde_json::from_value(json!({
+            "nodes": [
+                {
+                    "uid": "u1",
+                    "name": "n1",
+                    "version": "5.4.0",
+                    "num_clients": 2,
+                    "num_users": 1,
+                    "num_channels": 1,
+                    "uptime": 10,
+                    "num_subs": 3,
+                    "process": { "cpu": 1.5, "rss": 1024 },
+                    "metrics": { "interval": 60, "items": { "client_num_connect": 2 } },
+                },
+                {
+                    "uid": "u2",
+                    "name": "n2",
+                    "version": "3.0.0",
+                    "num_clients": 0,
+                    "num_users": 0,
+                    "num_channels": 0,
+                    "uptime": 10,
+                },
+            ],
+        }))
+        .unwrap();
+
+        let node = &info.nodes[0];
+        assert_eq!(node.num_subs, Some(3));
+        let process = node.process.as_ref().unwrap();
+        assert_eq!((process.cpu, process.rss), (1.5, 1024));
+        let metrics = node.metrics.as_ref().unwrap();
+        assert_eq!(metrics.interval, 60.0);
+        assert_eq!(metrics.items["client_num_connect"], 2.0);
+
+        let old = &info.nodes[1];
+        assert!(old.num_subs.is_none() && old.process.is_none() && old.metrics.is_none());
+        assert!(!serde_json::to_string(old).unwrap().contains("num_subs"));
+    }
+
+    #[test]
+    fn test_client_info_as() {
+        let presence: PresenceResult = serde_json::from_value(json!({
+            "presence": {
+                "c1": {
+                    "user": "42",
+                    "client": "c1",
+                    "conn_info": { "text": "device" },
+                },
+            },
+        }))
+        .unwrap();
+
+        let info = &presence.presence["c1"];
+        let conn_info: Option<Message> = info.conn_info_as().unwrap();
+        assert_eq!(conn_info.unwrap().text, "device");
+        assert_eq!(info.chan_info_as::<Message>().unwrap(), None);
+        assert!(info.conn_info_as::<u64>().is_err());
+    }
+}
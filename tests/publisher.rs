@@ -0,0 +1,63 @@
+mod common;
+
+use common::serve_once_raw;
+use rucent::client::{Client, Config, ErrRes};
+use rucent::options::PublishOptions;
+use rucent::publisher::{NoopPublisher, Publisher};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// notify is domain code written against Publisher.
+    async fn notify<P: Publisher>(publisher: &P, user: &str) -> Result<u64, ErrRes> {
+        let result = publisher
+            .publish(
+                format!("personal:{user}"),
+                r#"{"text":"hi"}"#,
+                PublishOptions::default(),
+            )
+            .await?;
+        Ok(result.offset.unwrap_or_default())
+    }
+
+    #[test]
+    fn test_client_publisher() {
+        let (addr, request) =
+            serve_once_raw("", br#"{"result":{"offset":3,"epoch":"e"}}"#.to_vec());
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let offset = rt.block_on(notify(&client, "42")).unwrap();
+        assert_eq!(offset, 3);
+        assert!(request.join().unwrap().starts_with("POST"));
+    }
+
+    #[test]
+    fn test_noop_publisher() {
+        let rt = Runtime::new().unwrap();
+        let result = rt.block_on(async {
+            assert_eq!(notify(&NoopPublisher, "42").await.unwrap(), 0);
+            // futures are Send, so publishing can be moved to a task.
+            tokio::spawn(async {
+                NoopPublisher
+                    .broadcast(
+                        vec!["a".to_string(), "b".to_string()],
+                        "{}",
+                        PublishOptions::default(),
+                    )
+                    .await
+                    .map(|result| result.responses.len())
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap()
+        });
+        assert_eq!(result.unwrap(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+use rucent::pipe::{
+    ApnsPushNotification, PushNotification, PushRecipient, SendPushNotificationRequest,
+};
+use rucent::push::{
+    ttl_string, AndroidConfig, AndroidPriority, ApnsMessage, FcmMessage, HmsAndroidConfig,
+    HmsMessage, HmsUrgency,
+};
+use serde_json::json;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_push_payload_builders() {
+        let notification = PushNotification::default()
+            .with_fcm(
+                FcmMessage::new()
+                    .notification("Hello", "World")
+                    .data("order", "42")
+                    .android(AndroidConfig {
+                        priority: Some(AndroidPriority::High),
+                        ttl: Some(ttl_string(Duration::from_secs(3600))),
+                        ..Default::default()
+                    }),
+            )
+            .with_apns(
+                ApnsMessage::new()
+                    .alert("Hello", "World")
+                    .badge(1)
+                    .header("apns-collapse-id", "order-42")
+                    .custom("order", 42),
+            )
+            .with_hms(
+                HmsMessage::new()
+                    .notification("Hello", "World")
+                    .data(&json!({"order": 42}))
+                    .android(HmsAndroidConfig {
+                        urgency: Some(HmsUrgency::High),
+                        category: Some("IM".to_string()),
+                        ..Default::default()
+                    }),
+            );
+        let req = SendPushNotificationRequest {
+            recipient: PushRecipient {
+                fcm_tokens: Some(vec!["token".to_string()]),
+                ..Default::default()
+            },
+            notification,
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            value["notification"],
+            json!({
+                "fcm": {"message": {
+                    "notification": {"title": "Hello", "body": "World"},
+                    "data": {"order": "42"},
+                    "android": {"priority": "HIGH", "ttl": "3600s"},
+                }},
+                "apns": {
+                    "headers": {"apns-collapse-id": "order-42"},
+                    "payload": {
+                        "aps": {"alert": {"title": "Hello", "body": "World"}, "badge": 1},
+                        "order": 42,
+                    },
+                },
+                "hms": {"message": {
+                    "notification": {"title": "Hello", "body": "World"},
+                    "data": "{\"order\":42}",
+                    "android": {"urgency": "HIGH", "category": "IM"},
+                }},
+            })
+        );
+    }
+
+    #[test]
+    fn test_apns_background() {
+        let apns: ApnsPushNotification = ApnsMessage::new().background().into();
+        assert_eq!(apns.payload, json!({"aps": {"content-available": 1}}));
+        let headers = apns.headers.unwrap();
+        assert_eq!(headers["apns-push-type"], "background");
+        assert_eq!(headers["apns-priority"], "5");
+
+        let apns: ApnsPushNotification = ApnsMessage::new().sound("default").into();
+        assert!(apns.headers.is_none());
+    }
+}
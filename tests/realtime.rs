@@ -0,0 +1,175 @@
+#![cfg(feature = "realtime")]
+
+use futures_util::StreamExt;
+use rucent::realtime::{RealtimeClient, RealtimeConfig, RealtimeEvent};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// read_text reads a single masked text frame sent by client.
+    fn read_text(stream: &mut impl Read) -> Value {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x81);
+        assert!(header[1] & 0x80 != 0, "client frames must be masked");
+        let len = match header[1] & 0x7f {
+            126 => {
+                let mut len = [0; 2];
+                stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut mask = [0; 4];
+        stream.read_exact(&mut mask).unwrap();
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).unwrap();
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    /// write_frame writes a single unmasked frame as server does.
+    fn write_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) {
+        let mut frame = vec![0x80 | opcode, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        stream.write_all(&frame).unwrap();
+    }
+
+    /// serve_ws accepts a single WebSocket connection and runs handler on it,
+    /// returns address and a handle resolving to upgrade request headers.
+    fn serve_ws(
+        handler: impl FnOnce(&mut BufReader<TcpStream>, &mut TcpStream) + Send + 'static,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!(
+            "ws://{}/connection/websocket",
+            listener.local_addr().unwrap()
+        );
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                headers.push_str(&line);
+            }
+            writer
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n")
+                .unwrap();
+            handler(&mut reader, &mut writer);
+            headers
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_realtime_subscribe_receives_publications() {
+        let (addr, handle) = serve_ws(|reader, writer| {
+            let connect = read_text(reader);
+            assert_eq!(connect["connect"]["token"], "jwt");
+            let reply = format!(
+                r#"{{"id":{},"connect":{{"client":"c1","version":"5.0.0","ping":25,"pong":true}}}}"#,
+                connect["id"]
+            );
+            write_frame(writer, 0x1, reply.as_bytes());
+
+            let subscribe = read_text(reader);
+            assert_eq!(subscribe["subscribe"]["channel"], "news");
+            let reply = format!(r#"{{"id":{},"subscribe":{{}}}}"#, subscribe["id"]);
+            write_frame(writer, 0x1, reply.as_bytes());
+            write_frame(
+                writer,
+                0x1,
+                br#"{"push":{"channel":"news","pub":{"data":{"n":1},"offset":7}}}"#,
+            );
+
+            // server ping is answered with pong.
+            write_frame(writer, 0x1, b"{}");
+            assert_eq!(read_text(reader), serde_json::json!({}));
+
+            write_frame(
+                writer,
+                0x1,
+                br#"{"push":{"disconnect":{"code":3001,"reason":"shutdown"}}}"#,
+            );
+        });
+
+        let rt = Runtime::new().unwrap();
+        let events: Vec<RealtimeEvent> = rt.block_on(async {
+            let (client, events) = RealtimeClient::connect(RealtimeConfig {
+                url: addr,
+                token: Some("jwt".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+            assert_eq!(client.connect_result().client, "c1");
+            client.subscribe("news").await.unwrap();
+            events.collect().await
+        });
+
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            RealtimeEvent::Publication {
+                channel,
+                publication,
+            } => {
+                assert_eq!(channel, "news");
+                assert_eq!(publication.offset, 7);
+                assert_eq!(publication.data["n"], 1);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match &events[1] {
+            RealtimeEvent::Disconnected { code, reason } => {
+                assert_eq!(*code, 3001);
+                assert_eq!(reason, "shutdown");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let headers = handle.join().unwrap().to_lowercase();
+        assert!(headers.contains("upgrade: websocket"));
+        assert!(headers.contains("sec-websocket-version: 13"));
+    }
+
+    #[test]
+    fn test_realtime_reply_error() {
+        let (addr, handle) = serve_ws(|reader, writer| {
+            let connect = read_text(reader);
+            let reply = format!(
+                r#"{{"id":{},"error":{{"code":109,"message":"token expired"}}}}"#,
+                connect["id"]
+            );
+            write_frame(writer, 0x1, reply.as_bytes());
+            write_frame(writer, 0x8, &1000u16.to_be_bytes());
+        });
+
+        let rt = Runtime::new().unwrap();
+        let err = rt
+            .block_on(RealtimeClient::connect(RealtimeConfig {
+                url: addr,
+                ..Default::default()
+            }))
+            .err()
+            .unwrap();
+        let err = err.downcast_ref::<rucent::protocol::Error>().unwrap();
+        assert_eq!(err.code, 109);
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config, ErrStatusCode};
+use rucent::record::{read_exchanges, Exchange, Recorder, REDACTED};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn recorded(buffer: &SharedBuffer) -> Vec<Exchange> {
+        read_exchanges(&buffer.0.lock().unwrap()[..]).unwrap()
+    }
+
+    #[test]
+    fn test_recorder_captures_exchanges() {
+        let (addr, _requests) = serve(vec![
+            (
+                200,
+                String::new(),
+                br#"{"result":{"offset":1,"epoch":"e","token":"t2"}}"#.to_vec(),
+            ),
+            (500, String::new(), b"internal error".to_vec()),
+        ]);
+        let buffer = SharedBuffer::default();
+        let recorder = Recorder::new(buffer.clone()).redact_field("token");
+        let config = Config {
+            addr: Some(addr.clone()),
+            key: Some("secret".to_string()),
+            recorder: Some(Arc::new(recorder)),
+            ..Default::default()
+        };
+
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(config);
+        rt.block_on(client.publish("chat".to_string(), r#"{"token":"t1","n":1}"#, &[]))
+            .unwrap();
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 500);
+
+        let exchanges = recorded(&buffer);
+        assert_eq!(exchanges.len(), 2);
+
+        let exchange = &exchanges[0];
+        assert_eq!(exchange.endpoint, addr);
+        assert_eq!(exchange.status, 200);
+        let authorization = exchange
+            .headers
+            .iter()
+            .find(|(name, _)| name == "authorization")
+            .unwrap();
+        assert_eq!(authorization.1, REDACTED);
+        assert_eq!(
+            exchange.request,
+            r#"{"method":"publish","params":{"channel":"chat","data":{"n":1,"token":"[REDACTED]"}}}"#
+        );
+        assert!(!exchange.response.contains("t2"));
+        assert!(!serde_json::to_string(&exchanges)
+            .unwrap()
+            .contains("secret"));
+
+        assert_eq!(exchanges[1].status, 500);
+        assert_eq!(exchanges[1].response, "internal error");
+    }
+
+    #[test]
+    fn test_read_exchanges() {
+        let exchange = Exchange {
+            endpoint: "http://localhost:8000/api".to_string(),
+            headers: vec![("x-api-key".to_string(), REDACTED.to_string())],
+            request: r#"{"method":"info","params":{}}"#.to_string(),
+            status: 200,
+            response: r#"{"result":{"nodes":[]}}"#.to_string(),
+        };
+        let line = serde_json::to_string(&exchange).unwrap();
+        let file = format!("{line}\n\n{line}\n");
+
+        let exchanges = read_exchanges(file.as_bytes()).unwrap();
+        assert_eq!(exchanges, vec![exchange.clone(), exchange]);
+        assert!(read_exchanges(&b"not json"[..]).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config, ErrStatusCode};
+use rucent::secret::KeyFile;
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn key_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rucent-{}-{}.key", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_key_file_reload() {
+        let path = key_path("reload");
+        std::fs::write(&path, "old-key\n").unwrap();
+        let key_file = KeyFile::new(&path);
+        assert_eq!(key_file.key().as_deref(), Some("old-key"));
+        assert!(!key_file.reload());
+
+        std::fs::write(&path, "new-key").unwrap();
+        assert!(key_file.reload());
+        assert_eq!(key_file.key().as_deref(), Some("new-key"));
+
+        // key is kept while file is unreadable, e.g. during rotation.
+        std::fs::remove_file(&path).unwrap();
+        assert!(!key_file.reload());
+        assert_eq!(key_file.key().as_deref(), Some("new-key"));
+        assert!(KeyFile::read(&path).is_err());
+    }
+
+    #[test]
+    fn test_client_key_file() {
+        let path = key_path("client");
+        std::fs::write(&path, "old-key").unwrap();
+        let (addr, requests) = serve(vec![
+            (401, String::new(), b"unauthorized".to_vec()),
+            (401, String::new(), b"unauthorized".to_vec()),
+            (200, String::new(), br#"{"result":{}}"#.to_vec()),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            key: Some("config-key".to_string()),
+            key_file: Some(path.clone()),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        // key did not change, so request is not repeated.
+        let err = rt
+            .block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 401);
+
+        std::fs::write(&path, "new-key").unwrap();
+        rt.block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap();
+
+        let keys: Vec<_> = requests
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(headers, _)| {
+                headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("authorization: apikey "))
+                    .unwrap()
+                    .trim()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(keys, ["old-key", "old-key", "new-key"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_client_try_new_key_file() {
+        let err = Client::try_new(Config {
+            addr: Some("http://localhost:8000/api".to_string()),
+            key_file: Some(key_path("missing")),
+            ..Default::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.field, "key_file");
+    }
+}
@@ -0,0 +1,167 @@
+use rucent::admin::{AdminClient, AdminConfig};
+use rucent::client::{
+    CallOptions, Client, Config, ConfigError, ErrPayloadTooLarge, ErrRequestFailed, ErrRes,
+    ErrResponseTooLarge, ErrStatusCode,
+};
+use rucent::envelope::Envelope;
+use rucent::options::{
+    with_auto_idempotency_key, with_skip_history, ChannelsOptions, Disconnect, DisconnectOptions,
+    PublishOptions,
+};
+use rucent::pattern::PatternError;
+use rucent::pipe::{
+    DeviceListRequest, DeviceRegisterRequest, DeviceRemoveRequest, DeviceTopicListRequest,
+    DeviceTopicUpdateRequest, DeviceUpdateRequest, PipeCommandError, RateLimitRequest,
+    SendPushNotificationRequest, UserTopicListRequest, UserTopicUpdateRequest,
+};
+use rucent::protocol::Error as ProtocolError;
+use rucent::publisher::{NoopPublisher, Publisher};
+use rucent::shutdown::ErrClientClosed;
+use rucent::validation::ErrInvalidData;
+use std::error::Error;
+use std::future::Future;
+
+// Futures below are never polled, the tests only have to compile.
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_send<T, E>(_: impl Future<Output = Result<T, E>> + Send)
+    where
+        E: Send + Sync + 'static,
+    {
+    }
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    fn assert_error<E: Error + Send + Sync + 'static>() {}
+
+    fn new_client() -> Client {
+        Client::new(Config {
+            addr: Some("http://localhost:8000/api".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_error_type_is_send_sync() {
+        assert_send_sync::<ErrRes>();
+        assert_error::<ErrStatusCode>();
+        assert_error::<ErrRequestFailed>();
+        assert_error::<ErrResponseTooLarge>();
+        assert_error::<ErrPayloadTooLarge>();
+        assert_error::<ConfigError>();
+        assert_error::<PipeCommandError>();
+        assert_error::<ProtocolError>();
+        assert_error::<ErrClientClosed>();
+        assert_error::<ErrInvalidData>();
+        assert_error::<PatternError>();
+    }
+
+    #[test]
+    fn test_client_futures_are_send() {
+        let client = new_client();
+        let channel = || "chat".to_string();
+        let user = || "42".to_string();
+
+        assert_send(client.publish(channel(), "{}", &[with_skip_history(true)]));
+        assert_send(client.publish_with_options(channel(), "{}", PublishOptions::new()));
+        assert_send(client.publish_many(channel(), ["{}"], &[with_auto_idempotency_key()]));
+        assert_send(client.broadcast(vec![channel()], "{}", &[]));
+        assert_send(client.broadcast_with_options(vec![channel()], "{}", PublishOptions::new()));
+        assert_send(client.subscribe(channel(), user(), &[]));
+        assert_send(client.unsubscribe(channel(), user(), &[]));
+        assert_send(client.disconnect(user(), &[]));
+        assert_send(client.disconnect_all([user()], Disconnect::default(), vec![]));
+        assert_send(client.presence(channel()));
+        assert_send(client.presence_stats(channel()));
+        assert_send(client.history(channel(), &[]));
+        assert_send(client.history_remove(channel()));
+        assert_send(client.channels(&[]));
+        assert_send(client.channels_with_options(ChannelsOptions::default()));
+        assert_send(client.info());
+        assert_send(client.refresh(user(), &[]));
+        assert_send(client.connections(&[]));
+        assert_send(client.update_user_status(vec![user()], None));
+        assert_send(client.get_user_status(vec![user()]));
+        assert_send(client.delete_user_status(vec![user()]));
+        assert_send(client.device_register(DeviceRegisterRequest::default()));
+        assert_send(client.device_update(DeviceUpdateRequest::default()));
+        assert_send(client.device_remove(DeviceRemoveRequest::default()));
+        assert_send(client.device_list(DeviceListRequest::default()));
+        assert_send(client.device_topic_list(DeviceTopicListRequest::default()));
+        assert_send(client.device_topic_update(DeviceTopicUpdateRequest::default()));
+        assert_send(client.user_topic_list(UserTopicListRequest::default()));
+        assert_send(client.user_topic_update(UserTopicUpdateRequest::default()));
+        assert_send(client.send_push_notification(SendPushNotificationRequest::default()));
+        assert_send(client.rate_limit(RateLimitRequest::default()));
+        assert_send(client.publish_envelope(channel(), &Envelope::new("ping", ()), &[]));
+        assert_send(client.warmup(1));
+
+        let pipe = client.pipe();
+        assert_send(client.send_pipe(&pipe));
+        assert_send(client.send_pipe_checked(&pipe));
+        assert_send(client.send_pipe_typed(&pipe));
+        assert_send(client.send_pipe_owned(&pipe));
+        assert_send(client.send_pipe_with_options(&pipe, &CallOptions::default()));
+    }
+
+    #[test]
+    fn test_handle_futures_are_send() {
+        let client = new_client();
+        let channel = client.channel("chat");
+        assert_send(channel.publish("{}", &[]));
+        assert_send(channel.history(&[]));
+        assert_send(channel.presence());
+        assert_send(channel.presence_stats());
+        assert_send(channel.subscribe("42".to_string(), &[]));
+
+        let user = client.user("42");
+        assert_send(user.subscribe("chat".to_string(), &[]));
+        assert_send(user.unsubscribe("chat".to_string(), &[]));
+        assert_send(user.disconnect(&[]));
+        assert_send(user.refresh(&[]));
+        assert_send(user.status());
+
+        assert_send(Publisher::publish(
+            &client,
+            "chat".to_string(),
+            "{}",
+            PublishOptions::new(),
+        ));
+        assert_send(NoopPublisher.publish("chat".to_string(), "{}", PublishOptions::new()));
+    }
+
+    #[test]
+    fn test_admin_futures_are_send() {
+        let admin = AdminClient::new(AdminConfig::default());
+        assert_send(admin.login());
+        assert_send(admin.info());
+        assert_send(admin.channels(ChannelsOptions::default()));
+        assert_send(admin.presence("chat".to_string()));
+        assert_send(admin.disconnect("42".to_string(), DisconnectOptions::default()));
+    }
+
+    #[cfg(feature = "realtime")]
+    #[test]
+    fn test_realtime_futures_are_send() {
+        use rucent::realtime::{RealtimeClient, RealtimeConfig};
+        use rucent::uni::{UniConfig, UniConsumer};
+
+        assert_send(RealtimeClient::connect(RealtimeConfig::default()));
+        assert_send(UniConsumer::connect(UniConfig::default()));
+    }
+
+    #[test]
+    fn test_spawn_publish() {
+        // regression: used to fail to compile, error type wasn't Send.
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let client = new_client();
+        let _ = rt.block_on(async move {
+            tokio::spawn(async move { client.publish("chat".to_string(), "{}", &[]).await })
+                .await
+                .unwrap()
+        });
+    }
+}
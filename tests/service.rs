@@ -0,0 +1,118 @@
+#![cfg(feature = "tower")]
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config, ErrRequestFailed, ErrRes};
+use rucent::service::{http_layer, HttpRequest};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::runtime::Runtime;
+use tower_service::Service;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// SetHeader is a minimal layer similar to tower-http SetRequestHeader.
+    #[derive(Clone)]
+    struct SetHeader<S> {
+        inner: S,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl<S: Service<HttpRequest>> Service<HttpRequest> for SetHeader<S> {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = S::Future;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, mut request: HttpRequest) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            request
+                .headers_mut()
+                .insert("x-layer", "set-header".parse().unwrap());
+            self.inner.call(request)
+        }
+    }
+
+    /// Canned answers requests without sending them, with body of other type.
+    #[derive(Clone)]
+    struct Canned {
+        status: u16,
+    }
+
+    impl Service<HttpRequest> for Canned {
+        type Response = http::Response<String>;
+        type Error = ErrRes;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, ErrRes>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: HttpRequest) -> Self::Future {
+            let status = self.status;
+            Box::pin(async move {
+                if status == 0 {
+                    return Err("service unavailable".into());
+                }
+                Ok(http::Response::builder()
+                    .status(status)
+                    .body(r#"{"result":{"nodes":[]}}"#.to_string())
+                    .unwrap())
+            })
+        }
+    }
+
+    #[test]
+    fn test_http_layer_wraps_requests() {
+        let (addr, requests) = serve(vec![(200, String::new(), br#"{"result":{}}"#.to_vec())]);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer_calls = calls.clone();
+        let client = Client::new(Config {
+            addr: Some(addr),
+            http_layer: Some(http_layer(move |inner| SetHeader {
+                inner,
+                calls: layer_calls.clone(),
+            })),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let (headers, _) = requests.join().unwrap().remove(0);
+        assert!(headers.contains("x-layer: set-header"));
+        assert_eq!(client.transport_stats().requests_sent, 1);
+    }
+
+    #[test]
+    fn test_http_layer_replaces_transport() {
+        let rt = Runtime::new().unwrap();
+        let client = Client::new(Config {
+            addr: Some("http://localhost:1/api".to_string()),
+            http_layer: Some(http_layer(|_| Canned { status: 200 })),
+            ..Default::default()
+        });
+        let info = rt.block_on(client.info()).unwrap();
+        assert!(info.nodes.is_empty());
+
+        let client = Client::new(Config {
+            addr: Some("http://localhost:1/api".to_string()),
+            http_layer: Some(http_layer(|_| Canned { status: 0 })),
+            ..Default::default()
+        });
+        let err = rt.block_on(client.info()).unwrap_err();
+        let err = err.downcast_ref::<ErrRequestFailed>().unwrap();
+        assert_eq!(err.source.to_string(), "service unavailable");
+    }
+}
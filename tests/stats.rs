@@ -0,0 +1,51 @@
+mod common;
+
+use common::serve;
+use rucent::client::{Client, Config};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_client_stats() {
+        let publish_reply = br#"{"result":{}}"#.to_vec();
+        let pipe_reply =
+            b"{\"result\":{}}\n{\"error\":{\"code\":102,\"message\":\"unknown channel\"}}".to_vec();
+        let (addr, _requests) = serve(vec![
+            (200, String::new(), publish_reply.clone()),
+            (200, String::new(), pipe_reply.clone()),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+        let rt = Runtime::new().unwrap();
+
+        rt.block_on(client.publish("chat".to_string(), "{}", &[]))
+            .unwrap();
+        let pipe = client.pipe();
+        pipe.add_publish("chat".to_string(), "{}", &[]).unwrap();
+        pipe.add_presence("chat".to_string()).unwrap();
+        rt.block_on(client.send_pipe(&pipe)).unwrap();
+
+        // stats are shared with clones.
+        let stats = client.clone().stats();
+        assert_eq!(stats.commands_sent["publish"], 2);
+        assert_eq!(stats.commands_sent["presence"], 1);
+        assert_eq!(stats.errors[&102], 1);
+        assert_eq!(stats.errors.len(), 1);
+        assert!(stats.bytes_sent > 0);
+        assert_eq!(
+            stats.bytes_received,
+            (publish_reply.len() + pipe_reply.len()) as u64
+        );
+
+        assert_eq!(client.reset_stats(), stats);
+        let stats = client.stats();
+        assert!(stats.commands_sent.is_empty() && stats.errors.is_empty());
+        assert_eq!((stats.bytes_sent, stats.bytes_received), (0, 0));
+    }
+}
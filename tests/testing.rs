@@ -0,0 +1,327 @@
+#![cfg(feature = "testing")]
+
+use rucent::client::{decode_history, decode_info, decode_replies, ErrStatusCode};
+use rucent::error::ErrorExt;
+use rucent::options::{
+    with_disconnect_client_whitelist, with_limit, with_pattern, with_reverse, with_since,
+    with_subscribe_client, StreamPosition,
+};
+use rucent::pipe::RequestKind;
+use rucent::protocol::{ClientInfo, NodeInfo};
+use rucent::record::Recorder;
+use rucent::testing::{
+    client_info_fixture, error_reply_fixture, history_fixture, info_fixture, node_info_fixture,
+    publication_fixture, response_body_fixture, result_reply_fixture, CentrifugoContainer,
+    ContainerConfig, FakeConfig, FakeServer, ReplayServer, StubReply, StubServer, FIXTURE_EPOCH,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_replay_recorded_exchanges() {
+        let path = std::env::temp_dir().join(format!(
+            "rucent-replay-{}-{}.ndjson",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        let stub = StubServer::start();
+        stub.enqueue_result(json!({ "offset": 3, "epoch": "e1" }));
+        stub.enqueue_error(102, "unknown channel");
+        let mut config = stub.config();
+        config.recorder = Some(Arc::new(Recorder::create(&path).unwrap()));
+        let client = rucent::client::Client::new(config);
+        let rt = Runtime::new().unwrap();
+        rt.block_on(client.publish("news".to_string(), r#"{"n":1}"#, &[]))
+            .unwrap();
+        rt.block_on(client.presence("news".to_string()))
+            .unwrap_err();
+
+        let replay = ReplayServer::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.remaining(), 2);
+
+        let client = replay.client();
+        let published = rt
+            .block_on(client.publish("news".to_string(), r#"{"n":1}"#, &[]))
+            .unwrap();
+        assert_eq!(published.offset, Some(3));
+        let err = rt
+            .block_on(client.presence("news".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown channel"), "{err}");
+        assert_eq!(replay.remaining(), 0);
+        assert_eq!(
+            replay.received()[0],
+            r#"{"method":"publish","params":{"channel":"news","data":{"n":1}}}"#
+        );
+
+        let err = rt.block_on(client.info()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 500);
+    }
+
+    #[test]
+    fn test_fixtures_decode() {
+        let history = history_fixture([json!({"text": "a"}), json!({"text": "b"})]);
+        let decoded = decode_history(&serde_json::to_vec(&history).unwrap()).unwrap();
+        assert_eq!(decoded.offset, 2);
+        assert_eq!(decoded.epoch, FIXTURE_EPOCH);
+        assert_eq!(decoded.publication[1].offset, 2);
+        assert_eq!(decoded.publication[1].data["text"], "b");
+        assert_eq!(decoded.publication[0].info.as_ref().unwrap().user, "42");
+
+        let info = info_fixture(&["node-1", "node-2"]);
+        let decoded = decode_info(&serde_json::to_vec(&info).unwrap()).unwrap();
+        assert_eq!(decoded.totals().num_clients, 20);
+        assert!(!decoded.has_mixed_versions());
+
+        let node = NodeInfo {
+            version: "5.3.0".to_string(),
+            ..node_info_fixture("node-3")
+        };
+        assert_eq!(node.name, "node-3");
+        let info = ClientInfo {
+            conn_info: Some(json!({"name": "Alice"})),
+            ..client_info_fixture("alice")
+        };
+        assert_eq!(info.client, "client-alice");
+        assert_eq!(publication_fixture(7, json!(1)).offset, 7);
+    }
+
+    #[test]
+    fn test_reply_fixtures() {
+        let body = response_body_fixture(&[
+            result_reply_fixture(&history_fixture([json!({})])),
+            error_reply_fixture(102, "unknown channel"),
+        ]);
+        let replies = decode_replies(body.as_bytes(), true).unwrap();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0].result.as_ref().unwrap()["offset"], 1);
+        assert_eq!(replies[1].error.as_ref().unwrap().code, 102);
+    }
+
+    #[test]
+    fn test_stub_serves_enqueued_replies() {
+        let stub = StubServer::start();
+        stub.enqueue_result(json!({ "offset": 7, "epoch": "e1" }));
+        stub.enqueue_error(102, "unknown channel");
+
+        let client = stub.client();
+        let rt = Runtime::new().unwrap();
+        let (published, err) = rt.block_on(async {
+            let published = client
+                .publish("news".to_string(), r#"{"n":1}"#, &[])
+                .await
+                .unwrap();
+            let err = client.presence("news".to_string()).await.unwrap_err();
+            (published, err.to_string())
+        });
+
+        assert_eq!(published.offset, Some(7));
+        assert!(err.contains("unknown channel"), "{err}");
+        stub.assert_received(&["publish", "presence"]);
+
+        match &stub.received()[0].params {
+            RequestKind::PublishRequest(request) => assert_eq!(request.channel, "news"),
+            other => panic!("unexpected params: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stub_serves_status() {
+        let stub = StubServer::start();
+        stub.enqueue(StubReply::Status(503, "unavailable".to_string()));
+
+        let client = stub.client();
+        let rt = Runtime::new().unwrap();
+        let err = rt
+            .block_on(client.publish("news".to_string(), "{}", &[]))
+            .unwrap_err();
+
+        assert_eq!(err.downcast_ref::<ErrStatusCode>().unwrap().code, 503);
+        assert!(stub.received().is_empty());
+    }
+
+    #[test]
+    fn test_stub_batch() {
+        let stub = StubServer::start();
+        stub.enqueue_result(json!({ "offset": 1 }));
+
+        let rt = Runtime::new().unwrap();
+        let body: Value = rt.block_on(async {
+            reqwest::Client::new()
+                .post(format!("{}/batch", stub.addr()))
+                .json(&json!({
+                    "commands": [
+                        { "publish": { "channel": "news", "data": {} } },
+                        { "info": {} },
+                    ]
+                }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(
+            body,
+            json!({ "replies": [{ "publish": { "offset": 1 } }, { "info": {} }] })
+        );
+        stub.assert_received(&["publish", "info"]);
+    }
+
+    #[test]
+    fn test_fake_history() {
+        let fake = FakeServer::start_with(FakeConfig {
+            history_size: Some(3),
+        });
+        let client = fake.client();
+        let rt = Runtime::new().unwrap();
+
+        let mut epoch = String::new();
+        for i in 1..=4 {
+            let data = json!({ "n": i }).to_string();
+            let result = rt
+                .block_on(client.publish("news".to_string(), &data, &[]))
+                .unwrap();
+            assert_eq!(result.offset, Some(i));
+            epoch = result.epoch.unwrap();
+        }
+        assert_eq!(fake.history("news").len(), 3);
+
+        // publications are returned only with limit.
+        let history = rt
+            .block_on(client.history("news".to_string(), &[]))
+            .unwrap();
+        assert!(history.publication.is_empty());
+        assert_eq!(
+            (history.offset, history.epoch.as_str()),
+            (4, epoch.as_str())
+        );
+
+        let history = rt
+            .block_on(client.history("news".to_string(), &[with_limit(-1)]))
+            .unwrap();
+        let offsets: Vec<_> = history.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, [2, 3, 4]);
+
+        let history = rt
+            .block_on(client.history("news".to_string(), &[with_limit(2), with_reverse(true)]))
+            .unwrap();
+        let offsets: Vec<_> = history.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, [4, 3]);
+
+        let history = rt
+            .block_on(client.history(
+                "news".to_string(),
+                &[
+                    with_limit(10),
+                    with_since(StreamPosition {
+                        offset: Some(2),
+                        epoch: Some(epoch.clone()),
+                    }),
+                ],
+            ))
+            .unwrap();
+        let offsets: Vec<_> = history.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, [3, 4]);
+
+        // position evicted from history or in other epoch can't be recovered.
+        for since in [(0, epoch.clone()), (3, "other".to_string())] {
+            let err = rt
+                .block_on(client.history(
+                    "news".to_string(),
+                    &[with_since(StreamPosition {
+                        offset: Some(since.0),
+                        epoch: Some(since.1),
+                    })],
+                ))
+                .unwrap_err();
+            assert_eq!(err.as_api_error().unwrap().code, 112);
+        }
+
+        rt.block_on(client.history_remove("news".to_string()))
+            .unwrap();
+        assert!(fake.history("news").is_empty());
+    }
+
+    #[test]
+    fn test_fake_presence() {
+        let fake = FakeServer::start();
+        let client = fake.client();
+        let rt = Runtime::new().unwrap();
+
+        let browser = fake.connect("chat:index", "alice");
+        rt.block_on(client.subscribe(
+            "chat:index".to_string(),
+            "bob".to_string(),
+            &[with_subscribe_client("bob-1".to_string())],
+        ))
+        .unwrap();
+        rt.block_on(client.subscribe("news".to_string(), "alice".to_string(), &[]))
+            .unwrap();
+
+        let presence = rt
+            .block_on(client.presence("chat:index".to_string()))
+            .unwrap();
+        assert_eq!(presence.presence[&browser].user, "alice");
+        assert_eq!(presence.presence["bob-1"].user, "bob");
+
+        let channels = rt
+            .block_on(client.channels(&[with_pattern("chat:*".to_string())]))
+            .unwrap();
+        assert_eq!(channels.channels.len(), 1);
+        assert_eq!(channels.channels["chat:index"].num_clients, 2);
+
+        let info = rt.block_on(client.info()).unwrap();
+        assert_eq!(info.nodes[0].num_users, 2);
+        assert_eq!(info.nodes[0].num_clients, 3);
+
+        // whitelisted connection of alice survives disconnect.
+        rt.block_on(client.disconnect(
+            "alice".to_string(),
+            &[with_disconnect_client_whitelist(vec![browser.clone()])],
+        ))
+        .unwrap();
+        let channels = rt.block_on(client.channels(&[])).unwrap();
+        assert!(!channels.channels.contains_key("news"));
+
+        rt.block_on(client.unsubscribe("chat:index".to_string(), "bob".to_string(), &[]))
+            .unwrap();
+        let stats = rt
+            .block_on(client.presence_stats("chat:index".to_string()))
+            .unwrap();
+        assert_eq!((stats.num_clients, stats.num_users), (1, 1));
+
+        let methods: Vec<_> = fake
+            .received()
+            .iter()
+            .map(|cmd| cmd.method.as_str().to_string())
+            .collect();
+        assert_eq!(methods[..2], ["subscribe", "subscribe"]);
+    }
+
+    #[test]
+    fn test_container_start_without_docker() {
+        let err = CentrifugoContainer::start(ContainerConfig {
+            docker: Some("/nonexistent/docker".to_string()),
+            ..Default::default()
+        })
+        .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("failed to run /nonexistent/docker"));
+    }
+}
@@ -0,0 +1,48 @@
+use rucent::options::RefreshOptions;
+use rucent::protocol::UserStatus;
+use rucent::timestamp::{from_system_time, from_unix, to_unix};
+use serde_json::json;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let options = RefreshOptions::new().expire_at(from_unix(1_700_000_000));
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(value, json!({ "expire_at": 1_700_000_000 }));
+        let decoded: RefreshOptions = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.expire_at, options.expire_at);
+
+        let decoded: RefreshOptions = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(decoded.expire_at, None);
+
+        let status: UserStatus =
+            serde_json::from_value(json!({ "user": "42", "active": 10 })).unwrap();
+        assert_eq!(to_unix(status.active), 10);
+        assert_eq!(to_unix(status.online), 0);
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            json!({ "user": "42", "active": 10, "online": 0, "state": "" })
+        );
+
+        let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+        assert_eq!(to_unix(from_system_time(at)), 1_700_000_000);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn test_timestamp_is_offset_date_time() {
+        let status: UserStatus =
+            serde_json::from_value(json!({ "user": "42", "active": 1_700_000_000 })).unwrap();
+        let expected = time::Date::from_calendar_date(2023, time::Month::November, 14)
+            .unwrap()
+            .with_hms(22, 13, 20)
+            .unwrap()
+            .assume_utc();
+        assert_eq!(status.active, expected);
+    }
+}
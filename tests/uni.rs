@@ -0,0 +1,104 @@
+#![cfg(feature = "realtime")]
+mod common;
+
+use common::serve;
+use futures_util::StreamExt;
+use rucent::realtime::RealtimeEvent;
+use rucent::uni::{UniConfig, UniConsumer, UniTransport};
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn publications(events: &[RealtimeEvent]) -> Vec<(String, u64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                RealtimeEvent::Publication {
+                    channel,
+                    publication,
+                } => Some((channel.clone(), publication.offset)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uni_sse_yields_publications() {
+        let body = concat!(
+            ": comment\n",
+            "data: {\"connect\":{\"client\":\"c1\",\"version\":\"5.0.0\"}}\n\n",
+            "data: {\"channel\":\"news\",\"pub\":{\"data\":{\"n\":1},\"offset\":1}}\r\n\r\n",
+            "data: {}\n\n",
+            "data: {\"channel\":\"other\",\"pub\":{\"data\":{},\"offset\":1}}\n\n",
+            "data: {\"channel\":\"news\",\"pub\":{\"data\":{\"n\":2},\"offset\":2}}\n\n",
+            "data: {\"disconnect\":{\"code\":3001,\"reason\":\"shutdown\"}}\n\n",
+        );
+        let (addr, requests) = serve(vec![(
+            200,
+            "X-Accel-Buffering: no\r\n".to_string(),
+            body.as_bytes().to_vec(),
+        )]);
+
+        let rt = Runtime::new().unwrap();
+        let (connect_result, events) = rt.block_on(async {
+            let (connect_result, events) = UniConsumer::connect(UniConfig {
+                url: addr,
+                token: Some("jwt".to_string()),
+                channels: vec!["news".to_string()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+            (connect_result, events.collect::<Vec<_>>().await)
+        });
+
+        assert_eq!(connect_result.client, "c1");
+        assert_eq!(
+            publications(&events),
+            vec![("news".to_string(), 1), ("news".to_string(), 2)]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(RealtimeEvent::Disconnected { code: 3001, .. })
+        ));
+
+        let (headers, _) = requests.join().unwrap().remove(0);
+        assert!(headers.starts_with("GET /api?cf_connect="));
+        assert!(headers.contains("jwt"));
+    }
+
+    #[test]
+    fn test_uni_http_stream_yields_publications() {
+        let body = concat!(
+            "{\"connect\":{\"client\":\"c1\"}}\n",
+            "{\"channel\":\"news\",\"pub\":{\"data\":{\"n\":1},\"offset\":1}}\n",
+        );
+        let (addr, requests) = serve(vec![(200, String::new(), body.as_bytes().to_vec())]);
+
+        let rt = Runtime::new().unwrap();
+        let events = rt.block_on(async {
+            let (_, events) = UniConsumer::connect(UniConfig {
+                url: addr,
+                transport: UniTransport::HttpStream,
+                token: Some("jwt".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+            events.collect::<Vec<_>>().await
+        });
+
+        assert_eq!(publications(&events), vec![("news".to_string(), 1)]);
+        assert!(matches!(
+            events.last(),
+            Some(RealtimeEvent::Disconnected { code: 0, .. })
+        ));
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["token"], "jwt");
+    }
+}
@@ -0,0 +1,48 @@
+use rucent::validation::{DataValidator, DataValidators, ErrInvalidData};
+use serde_json::json;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn require(field: &'static str) -> DataValidator {
+        Arc::new(move |data| match data.get(field) {
+            Some(_) => Ok(()),
+            None => Err(format!("missing {field}")),
+        })
+    }
+
+    #[test]
+    fn test_channel_validator_takes_precedence() {
+        let validators = DataValidators::new()
+            .with_namespace("chat", require("text"))
+            .with_channel("chat:system", require("code"));
+
+        assert!(validators
+            .validate("chat:index", &json!({"text": "hi"}))
+            .is_ok());
+        assert_eq!(
+            validators.validate("chat:index", &json!({})),
+            Err(ErrInvalidData {
+                channel: "chat:index".to_string(),
+                message: "missing text".to_string(),
+            })
+        );
+        assert!(validators
+            .validate("chat:system", &json!({"code": 1}))
+            .is_ok());
+        assert!(validators
+            .validate("chat:system", &json!({"text": "hi"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_channels_without_validator_pass() {
+        let validators = DataValidators::new().with_namespace("chat", require("text"));
+
+        assert!(validators.validate("news", &json!(1)).is_ok());
+        assert!(validators.validate("orders:1", &json!({})).is_ok());
+    }
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::serve;
+use futures_util::StreamExt;
+use rucent::client::{Client, Config, ErrRequestFailed};
+use rucent::watch::{ChannelEvent, ChannelsWatcher, PresenceEvent, PresenceWatcher};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn ok_response(body: &str) -> (u16, String, Vec<u8>) {
+        (200, String::new(), body.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_channels_watcher_emits_diffs() {
+        let (addr, _requests) = serve(vec![
+            ok_response(r#"{"result":{"channels":{"a":{"num_clients":1}}}}"#),
+            ok_response(r#"{"result":{"channels":{"a":{"num_clients":3},"b":{"num_clients":1}}}}"#),
+            ok_response(r#"{"result":{"channels":{"b":{"num_clients":1}}}}"#),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let events: Vec<ChannelEvent> = rt.block_on(
+            ChannelsWatcher::new(&client, Duration::from_millis(10))
+                .stream()
+                .take(4)
+                .map(|event| event.unwrap())
+                .collect(),
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                ChannelEvent::Created {
+                    channel: "a".to_string(),
+                    num_clients: 1
+                },
+                ChannelEvent::CountChanged {
+                    channel: "a".to_string(),
+                    old: 1,
+                    new: 3
+                },
+                ChannelEvent::Created {
+                    channel: "b".to_string(),
+                    num_clients: 1
+                },
+                ChannelEvent::Removed {
+                    channel: "a".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_channels_watcher_yields_errors() {
+        let client = Client::new(Config {
+            addr: Some("http://127.0.0.1:1/api".to_string()),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let mut stream =
+            Box::pin(ChannelsWatcher::new(&client, Duration::from_millis(10)).stream());
+        let err = rt.block_on(stream.next()).unwrap().unwrap_err();
+        // original error is passed through, so it can be inspected.
+        assert!(err.is::<ErrRequestFailed>(), "{err}");
+        assert!(rt.block_on(stream.next()).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_watcher_stream_ends_on_shutdown() {
+        let (addr, _requests) = serve(vec![ok_response(
+            r#"{"result":{"channels":{"a":{"num_clients":1}}}}"#,
+        )]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let mut stream = Box::pin(ChannelsWatcher::new(&client, Duration::from_secs(60)).stream());
+        assert!(rt.block_on(stream.next()).unwrap().is_ok());
+
+        let (event, report) = rt.block_on(async {
+            let event = tokio::spawn(async move { stream.next().await.is_none() });
+            let report = client.shutdown(Duration::from_secs(1)).await;
+            (event.await.unwrap(), report)
+        });
+        assert!(event);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_presence_watcher_emits_joined_and_left() {
+        let (addr, requests) = serve(vec![
+            ok_response(r#"{"result":{"presence":{"c1":{"user":"1","client":"c1"}}}}"#),
+            ok_response(
+                r#"{"result":{"presence":{"c1":{"user":"1","client":"c1"},"c2":{"user":"2","client":"c2"}}}}"#,
+            ),
+            ok_response(r#"{"result":{"presence":{"c2":{"user":"2","client":"c2"}}}}"#),
+        ]);
+        let client = Client::new(Config {
+            addr: Some(addr),
+            ..Default::default()
+        });
+
+        let rt = Runtime::new().unwrap();
+        let events: Vec<PresenceEvent> = rt.block_on(
+            PresenceWatcher::new(&client, "chat", Duration::from_millis(10))
+                .stream()
+                .take(3)
+                .map(|event| event.unwrap())
+                .collect(),
+        );
+
+        let users: Vec<(bool, String)> = events
+            .into_iter()
+            .map(|event| match event {
+                PresenceEvent::Joined(info) => (true, info.client),
+                PresenceEvent::Left(info) => (false, info.client),
+            })
+            .collect();
+        assert_eq!(
+            users,
+            vec![
+                (true, "c1".to_string()),
+                (true, "c2".to_string()),
+                (false, "c1".to_string())
+            ]
+        );
+
+        let (_, body) = requests.join().unwrap().remove(0);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            r#"{"method":"presence","params":{"channel":"chat"}}"#
+        );
+    }
+}